@@ -0,0 +1,166 @@
+use std::{collections::HashSet, sync::Arc};
+
+use anyhow::{Result, bail};
+use clap::ValueEnum;
+
+use crate::wordlist::Words;
+
+/// Which end of the word the affixes go on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Position {
+    Suffix,
+    Prefix,
+    Both,
+}
+
+/// What `--affix-years`, `--affix-digits`, and `--affix-symbols` asked for.
+#[derive(Clone, Debug, Default)]
+pub struct AffixSpec {
+    pub years: Option<(u32, u32)>,
+    pub digits: Option<(usize, usize)>,
+    pub symbols: String,
+}
+
+impl std::fmt::Display for AffixSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut parts = Vec::new();
+        if let Some((first, last)) = self.years {
+            parts.push(format!("years {first}-{last}"));
+        }
+        if let Some((min, max)) = self.digits {
+            parts.push(if min == max {
+                format!("{min} digit{}", if min == 1 { "" } else { "s" })
+            } else {
+                format!("{min}-{max} digits")
+            });
+        }
+        if !self.symbols.is_empty() {
+            parts.push(format!("symbols {:?}", self.symbols));
+        }
+        f.write_str(&parts.join(", "))
+    }
+}
+
+impl AffixSpec {
+    pub fn is_empty(&self) -> bool {
+        self.years.is_none() && self.digits.is_none() && self.symbols.is_empty()
+    }
+
+    /// Numbers (single digits, years, then longer digit runs), symbols, and each number followed
+    /// by each symbol, without duplicates.
+    fn affixes(&self) -> Result<Vec<String>> {
+        if let Some((_, max_digits)) = self.digits
+            && max_digits > 6
+        {
+            bail!("--affix-digits runs longer than 6 digits are a brute force; use --charset");
+        }
+        let digit_runs =
+            |length: usize| (0..10u64.pow(length as u32)).map(move |n| format!("{n:0length$}"));
+        let mut seen = HashSet::new();
+        let mut numbers = Vec::new();
+        let mut push = |numbers: &mut Vec<String>, number: String| {
+            if seen.insert(number.clone()) {
+                numbers.push(number);
+            }
+        };
+        if let Some((1, _)) = self.digits {
+            digit_runs(1).for_each(|number| push(&mut numbers, number));
+        }
+        if let Some((first, last)) = self.years {
+            for year in first..=last {
+                push(&mut numbers, year.to_string());
+            }
+            for year in first..=last {
+                push(&mut numbers, format!("{:02}", year % 100));
+            }
+        }
+        if let Some((min_digits, max_digits)) = self.digits {
+            for length in min_digits.max(2)..=max_digits {
+                digit_runs(length).for_each(|number| push(&mut numbers, number));
+            }
+        }
+
+        let mut affixes = numbers.clone();
+        let mut symbols: Vec<char> = Vec::new();
+        for symbol in self.symbols.chars() {
+            if !symbols.contains(&symbol) {
+                symbols.push(symbol);
+            }
+        }
+        affixes.extend(symbols.iter().map(char::to_string));
+        for number in &numbers {
+            affixes.extend(symbols.iter().map(|symbol| format!("{number}{symbol}")));
+        }
+        Ok(affixes)
+    }
+}
+
+/// Every word of a list with every affix attached, one affix across the whole list at a time.
+pub struct Affixed {
+    words: Arc<Words>,
+    affixes: Vec<String>,
+    position: Position,
+    len: u64,
+}
+
+impl Affixed {
+    pub fn new(words: Arc<Words>, spec: &AffixSpec, position: Position) -> Result<Self> {
+        let affixes = spec.affixes()?;
+        let ends = if position == Position::Both { 2 } else { 1 };
+        let len = (words.len() as u64)
+            .checked_mul(affixes.len() as u64 * ends)
+            .filter(|_| !affixes.is_empty())
+            .ok_or_else(|| anyhow::anyhow!("Affix keyspace is empty or too large"))?;
+        Ok(Self {
+            words,
+            affixes,
+            position,
+            len,
+        })
+    }
+
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn words(&self) -> &Words {
+        &self.words
+    }
+
+    /// The wordlist index `index` attaches its affix to.
+    pub fn word_index(&self, index: u64) -> usize {
+        (index % self.words.len() as u64) as usize
+    }
+
+    /// With both ends, every suffix comes before any prefix.
+    pub fn write(&self, index: u64, buf: &mut String) {
+        let word = self.words.get(self.word_index(index));
+        let affix = (index / self.words.len() as u64) as usize;
+        let (affix, prefix) = match self.position {
+            Position::Suffix => (affix, false),
+            Position::Prefix => (affix, true),
+            Position::Both => (affix % self.affixes.len(), affix >= self.affixes.len()),
+        };
+        let affix = &self.affixes[affix];
+        buf.clear();
+        if prefix {
+            buf.push_str(affix);
+            buf.push_str(word);
+        } else {
+            buf.push_str(word);
+            buf.push_str(affix);
+        }
+    }
+}
+
+/// Parses a `FIRST-LAST` range of years.
+pub fn parse_years(value: &str) -> Result<(u32, u32), String> {
+    let invalid = || format!("invalid year range \"{value}\" (expected e.g. 1990-2030)");
+    let (first, last) = value.split_once('-').ok_or_else(invalid)?;
+    let first: u32 = first.trim().parse().map_err(|_| invalid())?;
+    let last: u32 = last.trim().parse().map_err(|_| invalid())?;
+    if first > last || last > 9999 {
+        return Err(invalid());
+    }
+    Ok((first, last))
+}
@@ -0,0 +1,760 @@
+use std::io::{Read, Seek, SeekFrom};
+
+use anyhow::{Context, Result, anyhow, bail, ensure};
+
+use crate::extract;
+
+mod recover;
+
+pub use recover::{Recovery, scan_local_headers};
+
+const LOCAL_HEADER_SIGNATURE: u32 = 0x0403_4b50;
+const CENTRAL_HEADER_SIGNATURE: u32 = 0x0201_4b50;
+const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x0605_4b50;
+const ZIP64_END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x0606_4b50;
+const ZIP64_LOCATOR_SIGNATURE: u32 = 0x0706_4b50;
+
+const LOCAL_HEADER_LEN: usize = 30;
+const CENTRAL_HEADER_LEN: usize = 46;
+const END_OF_CENTRAL_DIRECTORY_LEN: usize = 22;
+const ZIP64_END_OF_CENTRAL_DIRECTORY_LEN: usize = 56;
+const ZIP64_LOCATOR_LEN: usize = 20;
+const MAX_COMMENT_LEN: usize = u16::MAX as usize;
+
+const ZIP64_EXTRA_ID: u16 = 0x0001;
+const AES_EXTRA_ID: u16 = 0x9901;
+
+const FLAG_ENCRYPTED: u16 = 0x0001;
+const FLAG_DATA_DESCRIPTOR: u16 = 0x0008;
+const FLAG_STRONG_ENCRYPTION: u16 = 0x0040;
+const METHOD_AES: u16 = 99;
+
+const HOST_UNIX: u8 = 3;
+const HOST_OSX: u8 = 19;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compression {
+    /// Verified by streaming the decrypted bytes straight through the CRC check.
+    Stored,
+    /// Verified by inflating the decrypted stream, which also catches most wrong keys early.
+    Deflated,
+    Deflate64,
+    Bzip2,
+    Lzma,
+    Zstd,
+    Xz,
+    Ppmd,
+    Other(u16),
+}
+
+impl Compression {
+    fn from_method(method: u16) -> Self {
+        match method {
+            0 => Self::Stored,
+            8 => Self::Deflated,
+            9 => Self::Deflate64,
+            12 => Self::Bzip2,
+            14 => Self::Lzma,
+            93 => Self::Zstd,
+            95 => Self::Xz,
+            98 => Self::Ppmd,
+            other => Self::Other(other),
+        }
+    }
+
+    /// Whether this build can decompress the method; the optional ones are cargo features.
+    pub fn is_supported(self) -> bool {
+        match self {
+            Self::Stored | Self::Deflated => true,
+            Self::Deflate64 => cfg!(feature = "deflate64"),
+            Self::Bzip2 => cfg!(feature = "bzip2"),
+            Self::Lzma => cfg!(feature = "lzma"),
+            Self::Zstd => cfg!(feature = "zstd"),
+            Self::Xz => cfg!(feature = "xz"),
+            Self::Ppmd => cfg!(feature = "ppmd"),
+            Self::Other(_) => false,
+        }
+    }
+}
+
+impl std::fmt::Display for Compression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Stored => "stored",
+            Self::Deflated => "deflate",
+            Self::Deflate64 => "Deflate64",
+            Self::Bzip2 => "bzip2",
+            Self::Lzma => "LZMA",
+            Self::Zstd => "Zstandard",
+            Self::Xz => "XZ",
+            Self::Ppmd => "PPMd",
+            Self::Other(1) => "shrink",
+            Self::Other(6) => "implode",
+            Self::Other(method) => return write!(f, "method {method}"),
+        };
+        f.write_str(name)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encryption {
+    None,
+    ZipCrypto,
+    /// WinZip AES; `strength` is 1, 2, or 3 for 128, 192, or 256-bit keys.
+    Aes {
+        strength: u8,
+    },
+    /// PKWARE strong encryption, which is not supported.
+    Strong,
+}
+
+impl std::fmt::Display for Encryption {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::None => f.write_str("unencrypted"),
+            Self::ZipCrypto => f.write_str("ZipCrypto"),
+            Self::Aes { strength } => write!(f, "AES-{}", 64 + 64 * u32::from(*strength)),
+            Self::Strong => f.write_str("strong encryption"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Entry {
+    pub name: String,
+    pub compression: Compression,
+    pub encryption: Encryption,
+    pub compressed_size: u64,
+    pub uncompressed_size: u64,
+    /// Absolute position of the local file header within the input.
+    pub header_offset: u64,
+    /// Unix file type and permission bits, when the archiver recorded them.
+    pub unix_mode: Option<u32>,
+    record: Vec<u8>,
+    record_offset_field: OffsetField,
+}
+
+/// Where the local header offset lives inside the raw central directory record.
+#[derive(Clone, Copy, Debug)]
+enum OffsetField {
+    Fixed,
+    Zip64Extra(usize),
+}
+
+impl Entry {
+    pub fn is_dir(&self) -> bool {
+        self.name.ends_with('/')
+    }
+
+    pub fn is_symlink(&self) -> bool {
+        self.unix_mode
+            .is_some_and(|mode| mode & 0o170_000 == 0o120_000)
+    }
+
+    /// The entry's raw central directory record.
+    pub fn record(&self) -> &[u8] {
+        &self.record
+    }
+
+    /// The byte a ZipCrypto entry's decrypted header ends with: the top byte of the CRC, or of
+    /// the modification time for entries written with a data descriptor, as Info-ZIP does.
+    pub fn zipcrypto_check_byte(&self) -> u8 {
+        if le_u16(&self.record, 8) & FLAG_DATA_DESCRIPTOR != 0 {
+            (le_u16(&self.record, 12) >> 8) as u8
+        } else {
+            (le_u32(&self.record, 16) >> 24) as u8
+        }
+    }
+
+    fn is_verifiable(&self) -> bool {
+        self.ensure_verifiable().is_ok()
+    }
+
+    /// Fails up front when no password could ever be confirmed against this entry.
+    pub fn ensure_verifiable(&self) -> Result<()> {
+        match self.encryption {
+            Encryption::None => bail!("Entry \"{}\" is not encrypted", self.name),
+            Encryption::Strong => bail!(
+                "Entry \"{}\" uses PKWARE strong encryption, which is not supported",
+                self.name
+            ),
+            Encryption::ZipCrypto | Encryption::Aes { .. } => {}
+        }
+        if !self.compression.is_supported() {
+            bail!(
+                "Entry \"{}\" is compressed with {}, which this build cannot decompress; \
+                 pick another entry with --target",
+                self.name,
+                self.compression
+            );
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct CentralDirectory {
+    pub entries: Vec<Entry>,
+}
+
+/// Reads the central directory, following ZIP64 end records and extra fields when present.
+pub fn read_central_directory<R: Read + Seek>(reader: &mut R) -> Result<CentralDirectory> {
+    let file_len = reader.seek(SeekFrom::End(0))?;
+    let tail_len = file_len.min((END_OF_CENTRAL_DIRECTORY_LEN + MAX_COMMENT_LEN) as u64);
+    let tail_start = file_len - tail_len;
+    let tail = read_at(reader, tail_start, tail_len as usize)?;
+    ensure!(
+        tail.len() >= END_OF_CENTRAL_DIRECTORY_LEN,
+        "Not a ZIP archive: too short for an end of central directory"
+    );
+
+    let eocd_in_tail = (0..=tail.len().saturating_sub(END_OF_CENTRAL_DIRECTORY_LEN))
+        .rev()
+        .find(|&i| le_u32(&tail, i) == END_OF_CENTRAL_DIRECTORY_SIGNATURE)
+        .ok_or_else(|| anyhow!("Not a ZIP archive: end of central directory not found"))?;
+    let eocd_pos = tail_start + eocd_in_tail as u64;
+    let eocd = &tail[eocd_in_tail..];
+    ensure!(
+        eocd.len() >= END_OF_CENTRAL_DIRECTORY_LEN,
+        "Truncated end of central directory"
+    );
+
+    let mut entry_count = u64::from(le_u16(eocd, 10));
+    let mut cd_size = u64::from(le_u32(eocd, 12));
+    let mut cd_offset = u64::from(le_u32(eocd, 16));
+    let mut cd_end = eocd_pos;
+
+    if eocd_pos >= ZIP64_LOCATOR_LEN as u64 {
+        let locator = read_at(
+            reader,
+            eocd_pos - ZIP64_LOCATOR_LEN as u64,
+            ZIP64_LOCATOR_LEN,
+        )?;
+        if le_u32(&locator, 0) == ZIP64_LOCATOR_SIGNATURE {
+            let record_offset = le_u64(&locator, 8);
+            // Prepended data (e.g. self-extractor stubs) shifts everything, so fall back to the
+            // position right before the locator when the stored offset doesn't line up.
+            let adjacent = eocd_pos
+                .checked_sub((ZIP64_LOCATOR_LEN + ZIP64_END_OF_CENTRAL_DIRECTORY_LEN) as u64);
+            let record_pos = [Some(record_offset), adjacent]
+                .into_iter()
+                .flatten()
+                .find(|&pos| {
+                    read_at(reader, pos, 4).is_ok_and(|sig| {
+                        le_u32(&sig, 0) == ZIP64_END_OF_CENTRAL_DIRECTORY_SIGNATURE
+                    })
+                })
+                .ok_or_else(|| anyhow!("Corrupt ZIP64 end of central directory"))?;
+            let record = read_at(reader, record_pos, ZIP64_END_OF_CENTRAL_DIRECTORY_LEN)?;
+            entry_count = le_u64(&record, 32);
+            cd_size = le_u64(&record, 40);
+            cd_offset = le_u64(&record, 48);
+            cd_end = record_pos;
+        }
+    }
+
+    let base = cd_end
+        .checked_sub(cd_size)
+        .and_then(|start| start.checked_sub(cd_offset))
+        .ok_or_else(|| anyhow!("Central directory offsets point outside the archive"))?;
+    let cd_len = usize::try_from(cd_size).context("Central directory too large")?;
+    let cd = read_at(reader, base + cd_offset, cd_len)?;
+
+    let mut entries = Vec::with_capacity(entry_count.min(1 << 20) as usize);
+    let mut pos = 0;
+    while pos + CENTRAL_HEADER_LEN <= cd.len() && le_u32(&cd, pos) == CENTRAL_HEADER_SIGNATURE {
+        let (mut entry, record_len) = parse_central_record(&cd[pos..])?;
+        entry.header_offset += base;
+        entries.push(entry);
+        pos += record_len;
+    }
+
+    if entries.len() as u64 != entry_count {
+        bail!(
+            "Central directory lists {entry_count} entries but {} could be read",
+            entries.len()
+        );
+    }
+
+    Ok(CentralDirectory { entries })
+}
+
+fn parse_central_record(record: &[u8]) -> Result<(Entry, usize)> {
+    let made_by_host = record[5];
+    let flags = le_u16(record, 8);
+    let mut method = le_u16(record, 10);
+    let name_len = usize::from(le_u16(record, 28));
+    let extra_len = usize::from(le_u16(record, 30));
+    let comment_len = usize::from(le_u16(record, 32));
+    let record_len = CENTRAL_HEADER_LEN + name_len + extra_len + comment_len;
+    ensure!(
+        record.len() >= record_len,
+        "Truncated central directory record"
+    );
+
+    let raw_name = &record[CENTRAL_HEADER_LEN..CENTRAL_HEADER_LEN + name_len];
+    let name = if flags & 0x0800 != 0 {
+        String::from_utf8_lossy(raw_name).into_owned()
+    } else {
+        decode_cp437(raw_name)
+    };
+
+    let mut compressed_size = u64::from(le_u32(record, 20));
+    let mut uncompressed_size = u64::from(le_u32(record, 24));
+    let mut header_offset = u64::from(le_u32(record, 42));
+    let external_mode = le_u32(record, 38) >> 16;
+    let unix_mode = (matches!(made_by_host, HOST_UNIX | HOST_OSX) && external_mode != 0)
+        .then_some(external_mode);
+    let mut record_offset_field = OffsetField::Fixed;
+    let mut encryption = if flags & FLAG_STRONG_ENCRYPTION != 0 {
+        Encryption::Strong
+    } else if flags & FLAG_ENCRYPTED != 0 {
+        Encryption::ZipCrypto
+    } else {
+        Encryption::None
+    };
+
+    let extra_start = CENTRAL_HEADER_LEN + name_len;
+    let extra = &record[extra_start..extra_start + extra_len];
+    let mut cursor = 0;
+    while cursor + 4 <= extra.len() {
+        let id = le_u16(extra, cursor);
+        let len = usize::from(le_u16(extra, cursor + 2));
+        let body_start = cursor + 4;
+        let body = extra
+            .get(body_start..body_start + len)
+            .ok_or_else(|| anyhow!("Truncated extra field in \"{name}\""))?;
+
+        match id {
+            ZIP64_EXTRA_ID => {
+                // Only the fields saturated in the fixed header are present, in this order.
+                let mut field = 0;
+                let next = |field: &mut usize| -> Result<u64> {
+                    let value = body
+                        .get(*field..*field + 8)
+                        .map(|bytes| le_u64(bytes, 0))
+                        .ok_or_else(|| anyhow!("Truncated ZIP64 extra field in \"{name}\""))?;
+                    *field += 8;
+                    Ok(value)
+                };
+                if uncompressed_size == u64::from(u32::MAX) {
+                    uncompressed_size = next(&mut field)?;
+                }
+                if compressed_size == u64::from(u32::MAX) {
+                    compressed_size = next(&mut field)?;
+                }
+                if header_offset == u64::from(u32::MAX) {
+                    record_offset_field = OffsetField::Zip64Extra(extra_start + body_start + field);
+                    header_offset = next(&mut field)?;
+                }
+            }
+            AES_EXTRA_ID if body.len() >= 7 => {
+                encryption = Encryption::Aes { strength: body[4] };
+                method = le_u16(body, 5);
+            }
+            _ => {}
+        }
+        cursor = body_start + len;
+    }
+
+    if method == METHOD_AES {
+        bail!("Entry \"{name}\" uses AES but has no AES extra field");
+    }
+
+    Ok((
+        Entry {
+            name,
+            compression: Compression::from_method(method),
+            encryption,
+            compressed_size,
+            uncompressed_size,
+            header_offset,
+            unix_mode,
+            record: record[..record_len].to_vec(),
+            record_offset_field,
+        },
+        record_len,
+    ))
+}
+
+/// Picks the entry named `target`, or the first encrypted entry that can be verified, falling
+/// back to the first non-directory entry so its problem can be reported. Without a `target`,
+/// entries whose names match a `skip` pattern are never picked.
+pub fn select_target(entries: &[Entry], target: Option<&str>, skip: &[String]) -> Result<usize> {
+    match target {
+        Some(name) => entries
+            .iter()
+            .position(|entry| !entry.is_dir() && entry.name == name)
+            .ok_or_else(|| anyhow!("Target file \"{name}\" not found in archive")),
+        None => {
+            let eligible = |entry: &Entry| {
+                !entry.is_dir()
+                    && !skip
+                        .iter()
+                        .any(|pattern| extract::wildcard_match(pattern, &entry.name))
+            };
+            entries
+                .iter()
+                .position(|entry| eligible(entry) && entry.is_verifiable())
+                .or_else(|| entries.iter().position(eligible))
+                .ok_or_else(|| {
+                    if entries.iter().any(|entry| !entry.is_dir()) {
+                        anyhow!("Every file in the archive matches --skip-entry")
+                    } else {
+                        anyhow!("Archive contains no files to test")
+                    }
+                })
+        }
+    }
+}
+
+/// Reads just `entry` from the input and wraps it in a standalone single-entry archive, so each
+/// candidate only has to parse a tiny central directory no matter how large the original is.
+pub fn extract_entry_archive<R: Read + Seek>(reader: &mut R, entry: &Entry) -> Result<Vec<u8>> {
+    let header = read_at(reader, entry.header_offset, LOCAL_HEADER_LEN)
+        .with_context(|| format!("Failed to read local header for \"{}\"", entry.name))?;
+    ensure!(
+        le_u32(&header, 0) == LOCAL_HEADER_SIGNATURE,
+        "Local header for \"{}\" is missing or corrupt",
+        entry.name
+    );
+    let local_len =
+        LOCAL_HEADER_LEN + usize::from(le_u16(&header, 26)) + usize::from(le_u16(&header, 28));
+    let data_len = usize::try_from(entry.compressed_size)
+        .with_context(|| format!("Entry \"{}\" is too large to load", entry.name))?;
+
+    let mut bytes = read_at(reader, entry.header_offset, local_len + data_len)
+        .with_context(|| format!("Failed to read data for \"{}\"", entry.name))?;
+
+    let cd_offset = bytes.len() as u64;
+    let mut record = entry.record.clone();
+    match entry.record_offset_field {
+        OffsetField::Fixed => record[42..46].copy_from_slice(&0u32.to_le_bytes()),
+        OffsetField::Zip64Extra(at) => record[at..at + 8].copy_from_slice(&0u64.to_le_bytes()),
+    }
+    bytes.extend_from_slice(&record);
+    let cd_size = record.len() as u64;
+
+    if cd_offset >= u64::from(u32::MAX) {
+        let record_pos = bytes.len() as u64;
+        bytes.extend_from_slice(&ZIP64_END_OF_CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes());
+        bytes.extend_from_slice(&(ZIP64_END_OF_CENTRAL_DIRECTORY_LEN as u64 - 12).to_le_bytes());
+        bytes.extend_from_slice(&45u16.to_le_bytes());
+        bytes.extend_from_slice(&45u16.to_le_bytes());
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes.extend_from_slice(&1u64.to_le_bytes());
+        bytes.extend_from_slice(&1u64.to_le_bytes());
+        bytes.extend_from_slice(&cd_size.to_le_bytes());
+        bytes.extend_from_slice(&cd_offset.to_le_bytes());
+        bytes.extend_from_slice(&ZIP64_LOCATOR_SIGNATURE.to_le_bytes());
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes.extend_from_slice(&record_pos.to_le_bytes());
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        push_end_of_central_directory(&mut bytes, u16::MAX, u32::MAX, u32::MAX);
+    } else {
+        push_end_of_central_directory(&mut bytes, 1, cd_size as u32, cd_offset as u32);
+    }
+
+    Ok(bytes)
+}
+
+/// The extra fields of `entry`'s local header, which carry more (such as access times) than the
+/// central directory's, leaving out the ZIP64 and AES fields that describe how it is stored.
+pub fn local_extra_fields<R: Read + Seek>(
+    reader: &mut R,
+    entry: &Entry,
+) -> Result<Vec<(u16, Vec<u8>)>> {
+    let header = read_at(reader, entry.header_offset, LOCAL_HEADER_LEN)
+        .with_context(|| format!("Failed to read local header for \"{}\"", entry.name))?;
+    ensure!(
+        le_u32(&header, 0) == LOCAL_HEADER_SIGNATURE,
+        "Local header for \"{}\" is missing or corrupt",
+        entry.name
+    );
+    let name_len = u64::from(le_u16(&header, 26));
+    let extra_len = usize::from(le_u16(&header, 28));
+    let extra = read_at(
+        reader,
+        entry.header_offset + LOCAL_HEADER_LEN as u64 + name_len,
+        extra_len,
+    )?;
+    let mut fields = Vec::new();
+    let mut cursor = 0;
+    while cursor + 4 <= extra.len() {
+        let id = le_u16(&extra, cursor);
+        let len = usize::from(le_u16(&extra, cursor + 2));
+        let Some(body) = extra.get(cursor + 4..cursor + 4 + len) else {
+            break;
+        };
+        if id != ZIP64_EXTRA_ID && id != AES_EXTRA_ID {
+            fields.push((id, body.to_vec()));
+        }
+        cursor += 4 + len;
+    }
+    Ok(fields)
+}
+
+/// The raw, still encrypted data of `entry` inside an archive built by
+/// [`extract_entry_archive`], which starts with the entry's local header.
+pub fn entry_data<'a>(entry_archive: &'a [u8], entry: &Entry) -> Result<&'a [u8]> {
+    ensure!(
+        entry_archive.len() >= LOCAL_HEADER_LEN,
+        "Local header for \"{}\" is truncated",
+        entry.name
+    );
+    let start = LOCAL_HEADER_LEN
+        + usize::from(le_u16(entry_archive, 26))
+        + usize::from(le_u16(entry_archive, 28));
+    usize::try_from(entry.compressed_size)
+        .ok()
+        .and_then(|len| entry_archive.get(start..start.checked_add(len)?))
+        .ok_or_else(|| anyhow!("Data for \"{}\" is truncated", entry.name))
+}
+
+/// Rebuilds an archive from [`extract_entry_archive`] around `plain`, the entry's data with its
+/// ZipCrypto layer and header already removed, so it opens as an unencrypted entry.
+pub fn with_decrypted_data(entry_archive: &[u8], entry: &Entry, plain: &[u8]) -> Result<Vec<u8>> {
+    let data_len = entry_data(entry_archive, entry)?.len();
+    let data_start = LOCAL_HEADER_LEN
+        + usize::from(le_u16(entry_archive, 26))
+        + usize::from(le_u16(entry_archive, 28));
+    // The record already points at offset zero.
+    let record_start = data_start + data_len;
+    let mut record = entry_archive[record_start..record_start + entry.record.len()].to_vec();
+    ensure!(
+        le_u32(&record, 20) != u32::MAX,
+        "Entry \"{}\" uses ZIP64 sizes, which cannot be rewritten",
+        entry.name
+    );
+    let plain_len = u32::try_from(plain.len())?;
+
+    let mut bytes = entry_archive[..data_start].to_vec();
+    let flags = le_u16(&bytes, 6) & !FLAG_ENCRYPTED;
+    bytes[6..8].copy_from_slice(&flags.to_le_bytes());
+    // Entries written with a data descriptor leave the local sizes zero.
+    if le_u32(&bytes, 18) != 0 {
+        bytes[18..22].copy_from_slice(&plain_len.to_le_bytes());
+    }
+    bytes.extend_from_slice(plain);
+
+    let flags = le_u16(&record, 8) & !FLAG_ENCRYPTED;
+    record[8..10].copy_from_slice(&flags.to_le_bytes());
+    record[20..24].copy_from_slice(&plain_len.to_le_bytes());
+    let cd_offset = u32::try_from(bytes.len())?;
+    bytes.extend_from_slice(&record);
+    push_end_of_central_directory(&mut bytes, 1, record.len() as u32, cd_offset);
+    Ok(bytes)
+}
+
+fn push_end_of_central_directory(bytes: &mut Vec<u8>, entries: u16, size: u32, offset: u32) {
+    bytes.extend_from_slice(&END_OF_CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes());
+    bytes.extend_from_slice(&0u16.to_le_bytes());
+    bytes.extend_from_slice(&0u16.to_le_bytes());
+    bytes.extend_from_slice(&entries.to_le_bytes());
+    bytes.extend_from_slice(&entries.to_le_bytes());
+    bytes.extend_from_slice(&size.to_le_bytes());
+    bytes.extend_from_slice(&offset.to_le_bytes());
+    bytes.extend_from_slice(&0u16.to_le_bytes());
+}
+
+/// Reads `len` bytes at `offset`, refusing up front a range that runs past the end of the input
+/// so a size taken from a crafted header never turns into a huge allocation.
+fn read_at<R: Read + Seek>(reader: &mut R, offset: u64, len: usize) -> Result<Vec<u8>> {
+    let end = reader.seek(SeekFrom::End(0))?;
+    ensure!(
+        offset
+            .checked_add(len as u64)
+            .is_some_and(|stop| stop <= end),
+        "Unexpected end of archive reading {len} bytes at {offset}, past its {end} bytes"
+    );
+    reader.seek(SeekFrom::Start(offset))?;
+    let mut buffer = vec![0u8; len];
+    reader
+        .read_exact(&mut buffer)
+        .with_context(|| format!("Unexpected end of archive reading {len} bytes at {offset}"))?;
+    Ok(buffer)
+}
+
+fn decode_cp437(bytes: &[u8]) -> String {
+    const HIGH: &str = "ÇüéâäàåçêëèïîìÄÅÉæÆôöòûùÿÖÜ¢£¥₧ƒáíóúñÑªº¿⌐¬½¼¡«»░▒▓│┤╡╢╖╕╣║╗╝╜╛┐└┴┬├─┼╞╟╚╔╩╦╠═╬╧╨╤╥╙╘╒╓╫╪┘┌█▄▌▐▀αßΓπΣσµτΦΘΩδ∞φε∩≡±≥≤⌠⌡÷≈°∙·√ⁿ²■\u{a0}";
+    bytes
+        .iter()
+        .map(|&byte| {
+            if byte < 0x80 {
+                char::from(byte)
+            } else {
+                HIGH.chars().nth(usize::from(byte - 0x80)).unwrap_or('?')
+            }
+        })
+        .collect()
+}
+
+fn le_u16(bytes: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([bytes[at], bytes[at + 1]])
+}
+
+fn le_u32(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(bytes[at..at + 4].try_into().expect("slice of four bytes"))
+}
+
+fn le_u64(bytes: &[u8], at: usize) -> u64 {
+    u64::from_le_bytes(bytes[at..at + 8].try_into().expect("slice of eight bytes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Read};
+
+    use super::*;
+
+    /// A stored, unencrypted archive whose every size and offset lives in ZIP64 extra fields
+    /// and whose end records are the ZIP64 ones, after `stub` bytes of prepended data.
+    fn zip64_archive(stub: &[u8], files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut bytes = stub.to_vec();
+        let mut directory = Vec::new();
+        for (name, data) in files {
+            let offset = (bytes.len() - stub.len()) as u64;
+            let mut crc = flate2::Crc::new();
+            crc.update(data);
+            let len = data.len() as u64;
+
+            bytes.extend_from_slice(&LOCAL_HEADER_SIGNATURE.to_le_bytes());
+            bytes.extend_from_slice(&45u16.to_le_bytes());
+            bytes.extend_from_slice(&[0; 8]);
+            bytes.extend_from_slice(&crc.sum().to_le_bytes());
+            bytes.extend_from_slice(&[0xff; 8]);
+            bytes.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            bytes.extend_from_slice(&20u16.to_le_bytes());
+            bytes.extend_from_slice(name.as_bytes());
+            bytes.extend_from_slice(&ZIP64_EXTRA_ID.to_le_bytes());
+            bytes.extend_from_slice(&16u16.to_le_bytes());
+            bytes.extend_from_slice(&len.to_le_bytes());
+            bytes.extend_from_slice(&len.to_le_bytes());
+            bytes.extend_from_slice(data);
+
+            directory.extend_from_slice(&CENTRAL_HEADER_SIGNATURE.to_le_bytes());
+            directory.extend_from_slice(&[45, HOST_UNIX, 45, 0]);
+            directory.extend_from_slice(&[0; 8]);
+            directory.extend_from_slice(&crc.sum().to_le_bytes());
+            directory.extend_from_slice(&[0xff; 8]);
+            directory.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            directory.extend_from_slice(&28u16.to_le_bytes());
+            directory.extend_from_slice(&[0; 10]);
+            directory.extend_from_slice(&[0xff; 4]);
+            directory.extend_from_slice(name.as_bytes());
+            directory.extend_from_slice(&ZIP64_EXTRA_ID.to_le_bytes());
+            directory.extend_from_slice(&24u16.to_le_bytes());
+            directory.extend_from_slice(&len.to_le_bytes());
+            directory.extend_from_slice(&len.to_le_bytes());
+            directory.extend_from_slice(&offset.to_le_bytes());
+        }
+        let cd_offset = (bytes.len() - stub.len()) as u64;
+        bytes.extend_from_slice(&directory);
+        let record_offset = (bytes.len() - stub.len()) as u64;
+        let count = files.len() as u64;
+
+        bytes.extend_from_slice(&ZIP64_END_OF_CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes());
+        bytes.extend_from_slice(&(ZIP64_END_OF_CENTRAL_DIRECTORY_LEN as u64 - 12).to_le_bytes());
+        bytes.extend_from_slice(&45u16.to_le_bytes());
+        bytes.extend_from_slice(&45u16.to_le_bytes());
+        bytes.extend_from_slice(&[0; 8]);
+        bytes.extend_from_slice(&count.to_le_bytes());
+        bytes.extend_from_slice(&count.to_le_bytes());
+        bytes.extend_from_slice(&(directory.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(&cd_offset.to_le_bytes());
+
+        bytes.extend_from_slice(&ZIP64_LOCATOR_SIGNATURE.to_le_bytes());
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes.extend_from_slice(&record_offset.to_le_bytes());
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        push_end_of_central_directory(&mut bytes, u16::MAX, u32::MAX, u32::MAX);
+        bytes
+    }
+
+    const FILES: &[(&str, &[u8])] = &[("first.txt", b"hello"), ("second.txt", b"world!\n")];
+
+    #[test]
+    fn reads_zip64_end_records_and_extra_fields() {
+        let bytes = zip64_archive(b"", FILES);
+        let entries = read_central_directory(&mut Cursor::new(&bytes))
+            .unwrap()
+            .entries;
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].name, "first.txt");
+        assert_eq!(entries[0].header_offset, 0);
+        assert_eq!(entries[0].compressed_size, 5);
+        assert_eq!(entries[0].uncompressed_size, 5);
+        assert_eq!(entries[1].name, "second.txt");
+        assert_eq!(entries[1].header_offset, 30 + 9 + 20 + 5);
+        assert_eq!(entries[1].compressed_size, 7);
+        assert!(matches!(
+            entries[1].record_offset_field,
+            OffsetField::Zip64Extra(_)
+        ));
+    }
+
+    #[test]
+    fn finds_zip64_record_after_prepended_data() {
+        let stub = [0x4d; 100];
+        let bytes = zip64_archive(&stub, FILES);
+        let entries = read_central_directory(&mut Cursor::new(&bytes))
+            .unwrap()
+            .entries;
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].header_offset, 100);
+        assert_eq!(entries[1].header_offset, 100 + 30 + 9 + 20 + 5);
+    }
+
+    #[test]
+    fn entry_archive_round_trips() {
+        let bytes = zip64_archive(b"", FILES);
+        let mut reader = Cursor::new(&bytes);
+        let entries = read_central_directory(&mut reader).unwrap().entries;
+        let entry_archive = extract_entry_archive(&mut reader, &entries[1]).unwrap();
+
+        let isolated = read_central_directory(&mut Cursor::new(&entry_archive))
+            .unwrap()
+            .entries;
+        assert_eq!(isolated.len(), 1);
+        assert_eq!(isolated[0].name, "second.txt");
+        assert_eq!(isolated[0].header_offset, 0);
+        assert_eq!(isolated[0].compressed_size, 7);
+        assert_eq!(
+            entry_data(&entry_archive, &isolated[0]).unwrap(),
+            b"world!\n"
+        );
+
+        let mut zip = zip::ZipArchive::new(Cursor::new(&entry_archive)).unwrap();
+        let mut contents = Vec::new();
+        zip.by_index(0).unwrap().read_to_end(&mut contents).unwrap();
+        assert_eq!(contents, b"world!\n");
+    }
+
+    #[test]
+    fn rejects_entry_sizes_past_the_end_of_the_input() {
+        let bytes = zip64_archive(b"", FILES);
+        let mut reader = Cursor::new(&bytes);
+        let mut entries = read_central_directory(&mut reader).unwrap().entries;
+        entries[0].compressed_size = 1 << 60;
+        let error = extract_entry_archive(&mut reader, &entries[0]).unwrap_err();
+        assert!(format!("{error:#}").contains("Unexpected end of archive"));
+    }
+
+    #[test]
+    fn rejects_truncated_zip64_extra_field() {
+        let mut bytes = zip64_archive(b"", &FILES[..1]);
+        // Shrink the central record's ZIP64 field so the offset no longer fits.
+        let at = bytes.len() - 22 - 20 - 56 - 28 + 2;
+        bytes[at..at + 2].copy_from_slice(&16u16.to_le_bytes());
+        let error = read_central_directory(&mut Cursor::new(&bytes)).unwrap_err();
+        assert!(format!("{error:#}").contains("Truncated ZIP64 extra field"));
+    }
+
+    #[test]
+    fn rejects_truncated_input_without_panicking() {
+        for bytes in [&b""[..], b"PK\x03", b"PK\x05\x06", &[0; 21]] {
+            assert!(read_central_directory(&mut Cursor::new(bytes)).is_err());
+        }
+    }
+}
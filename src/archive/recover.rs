@@ -0,0 +1,247 @@
+use std::io::{Read, Seek, SeekFrom};
+
+use anyhow::{Result, anyhow, ensure};
+
+use super::{
+    AES_EXTRA_ID, CENTRAL_HEADER_LEN, CENTRAL_HEADER_SIGNATURE, Compression, Encryption, Entry,
+    FLAG_DATA_DESCRIPTOR, FLAG_ENCRYPTED, FLAG_STRONG_ENCRYPTION, LOCAL_HEADER_LEN,
+    LOCAL_HEADER_SIGNATURE, METHOD_AES, OffsetField, ZIP64_EXTRA_ID, decode_cp437, le_u16, le_u32,
+    le_u64, read_at,
+};
+
+const DATA_DESCRIPTOR_SIGNATURE: u32 = 0x0807_4b50;
+const SCAN_WINDOW: usize = 1 << 20;
+
+/// Result of rebuilding entry metadata from local file headers alone.
+pub struct Recovery {
+    pub entries: Vec<Entry>,
+    /// Offsets of local headers that were found but could not be used, with the reason.
+    pub damaged: Vec<(u64, String)>,
+}
+
+/// Walks the input looking for local file headers, for archives whose central directory is
+/// missing or corrupt.
+pub fn scan_local_headers<R: Read + Seek>(reader: &mut R) -> Result<Recovery> {
+    let file_len = reader.seek(SeekFrom::End(0))?;
+    let mut recovery = Recovery {
+        entries: Vec::new(),
+        damaged: Vec::new(),
+    };
+
+    let mut position = 0;
+    while let Some(offset) = find_signature(reader, position, file_len, LOCAL_HEADER_SIGNATURE)? {
+        match parse_local_entry(reader, offset, file_len) {
+            Ok((entry, data_end)) => {
+                recovery.entries.push(entry);
+                position = data_end;
+            }
+            Err(error) => {
+                recovery.damaged.push((offset, format!("{error:#}")));
+                position = offset + 4;
+            }
+        }
+    }
+
+    Ok(recovery)
+}
+
+fn parse_local_entry<R: Read + Seek>(
+    reader: &mut R,
+    offset: u64,
+    file_len: u64,
+) -> Result<(Entry, u64)> {
+    let header = read_at(reader, offset, LOCAL_HEADER_LEN)?;
+    let version_needed = le_u16(&header, 4);
+    let flags = le_u16(&header, 6);
+    let mut method = le_u16(&header, 8);
+    let modified = &header[10..14];
+    let mut crc32 = le_u32(&header, 14);
+    let mut compressed_size = u64::from(le_u32(&header, 18));
+    let mut uncompressed_size = u64::from(le_u32(&header, 22));
+    let name_len = usize::from(le_u16(&header, 26));
+    let extra_len = usize::from(le_u16(&header, 28));
+
+    let names_and_extra = read_at(
+        reader,
+        offset + LOCAL_HEADER_LEN as u64,
+        name_len + extra_len,
+    )?;
+    let raw_name = &names_and_extra[..name_len];
+    let extra = &names_and_extra[name_len..];
+    let name = if flags & 0x0800 != 0 {
+        String::from_utf8_lossy(raw_name).into_owned()
+    } else {
+        decode_cp437(raw_name)
+    };
+    ensure!(!name.is_empty(), "local header has an empty file name");
+
+    let mut encryption = if flags & FLAG_STRONG_ENCRYPTION != 0 {
+        Encryption::Strong
+    } else if flags & FLAG_ENCRYPTED != 0 {
+        Encryption::ZipCrypto
+    } else {
+        Encryption::None
+    };
+    let mut aes_extra = None;
+    let mut cursor = 0;
+    while cursor + 4 <= extra.len() {
+        let id = le_u16(extra, cursor);
+        let len = usize::from(le_u16(extra, cursor + 2));
+        let Some(body) = extra.get(cursor + 4..cursor + 4 + len) else {
+            break;
+        };
+        match id {
+            ZIP64_EXTRA_ID if body.len() >= 16 => {
+                uncompressed_size = le_u64(body, 0);
+                compressed_size = le_u64(body, 8);
+            }
+            AES_EXTRA_ID if body.len() >= 7 => {
+                encryption = Encryption::Aes { strength: body[4] };
+                method = le_u16(body, 5);
+                aes_extra = Some(&extra[cursor..cursor + 4 + len]);
+            }
+            _ => {}
+        }
+        cursor += 4 + len;
+    }
+
+    let data_start = offset + (LOCAL_HEADER_LEN + name_len + extra_len) as u64;
+    let mut data_end = data_start + compressed_size;
+    if flags & FLAG_DATA_DESCRIPTOR != 0 {
+        let descriptor = find_data_descriptor(reader, data_start, file_len)?;
+        crc32 = descriptor.crc32;
+        compressed_size = descriptor.compressed_size;
+        uncompressed_size = descriptor.uncompressed_size;
+        data_end = descriptor.end;
+    }
+    ensure!(
+        data_end <= file_len,
+        "entry data runs past the end of the input"
+    );
+
+    // The verifier consumes a central directory record, so build one from what the local header
+    // told us, with ZIP64 fields for anything that doesn't fit in 32 bits.
+    let needs_zip64 = compressed_size >= u64::from(u32::MAX)
+        || uncompressed_size >= u64::from(u32::MAX)
+        || offset >= u64::from(u32::MAX);
+    let clamp = |value: u64| -> u32 { if needs_zip64 { u32::MAX } else { value as u32 } };
+    let mut record_extra = Vec::new();
+    if needs_zip64 {
+        record_extra.extend_from_slice(&ZIP64_EXTRA_ID.to_le_bytes());
+        record_extra.extend_from_slice(&24u16.to_le_bytes());
+        record_extra.extend_from_slice(&uncompressed_size.to_le_bytes());
+        record_extra.extend_from_slice(&compressed_size.to_le_bytes());
+        record_extra.extend_from_slice(&offset.to_le_bytes());
+    }
+    if let Some(aes_extra) = aes_extra {
+        record_extra.extend_from_slice(aes_extra);
+    }
+
+    let mut record = Vec::with_capacity(CENTRAL_HEADER_LEN + name_len + record_extra.len());
+    record.extend_from_slice(&CENTRAL_HEADER_SIGNATURE.to_le_bytes());
+    record.extend_from_slice(&version_needed.to_le_bytes());
+    record.extend_from_slice(&version_needed.to_le_bytes());
+    record.extend_from_slice(&flags.to_le_bytes());
+    record.extend_from_slice(&header[8..10]);
+    record.extend_from_slice(modified);
+    record.extend_from_slice(&crc32.to_le_bytes());
+    record.extend_from_slice(&clamp(compressed_size).to_le_bytes());
+    record.extend_from_slice(&clamp(uncompressed_size).to_le_bytes());
+    record.extend_from_slice(&(name_len as u16).to_le_bytes());
+    record.extend_from_slice(&(record_extra.len() as u16).to_le_bytes());
+    record.extend_from_slice(&[0; 8]);
+    record.extend_from_slice(&0u32.to_le_bytes());
+    record.extend_from_slice(&clamp(offset).to_le_bytes());
+    record.extend_from_slice(raw_name);
+    record.extend_from_slice(&record_extra);
+
+    let record_offset_field = if needs_zip64 {
+        OffsetField::Zip64Extra(CENTRAL_HEADER_LEN + name_len + 4 + 16)
+    } else {
+        OffsetField::Fixed
+    };
+
+    if method == METHOD_AES {
+        return Err(anyhow!("\"{name}\" uses AES but has no AES extra field"));
+    }
+
+    Ok((
+        Entry {
+            name,
+            compression: Compression::from_method(method),
+            encryption,
+            compressed_size,
+            uncompressed_size,
+            header_offset: offset,
+            unix_mode: None,
+            record,
+            record_offset_field,
+        },
+        data_end,
+    ))
+}
+
+struct DataDescriptor {
+    crc32: u32,
+    compressed_size: u64,
+    uncompressed_size: u64,
+    end: u64,
+}
+
+/// Locates the descriptor that trails streamed entries, accepting only one whose recorded size
+/// matches its distance from the start of the data.
+fn find_data_descriptor<R: Read + Seek>(
+    reader: &mut R,
+    data_start: u64,
+    file_len: u64,
+) -> Result<DataDescriptor> {
+    let mut position = data_start;
+    while let Some(at) = find_signature(reader, position, file_len, DATA_DESCRIPTOR_SIGNATURE)? {
+        let distance = at - data_start;
+        if let Ok(descriptor) = read_at(reader, at, 16)
+            && u64::from(le_u32(&descriptor, 8)) == distance
+        {
+            return Ok(DataDescriptor {
+                crc32: le_u32(&descriptor, 4),
+                compressed_size: distance,
+                uncompressed_size: u64::from(le_u32(&descriptor, 12)),
+                end: at + 16,
+            });
+        }
+        if let Ok(descriptor) = read_at(reader, at, 24)
+            && le_u64(&descriptor, 8) == distance
+        {
+            return Ok(DataDescriptor {
+                crc32: le_u32(&descriptor, 4),
+                compressed_size: distance,
+                uncompressed_size: le_u64(&descriptor, 16),
+                end: at + 24,
+            });
+        }
+        position = at + 1;
+    }
+    Err(anyhow!("streamed entry has no matching data descriptor"))
+}
+
+fn find_signature<R: Read + Seek>(
+    reader: &mut R,
+    start: u64,
+    file_len: u64,
+    signature: u32,
+) -> Result<Option<u64>> {
+    let needle = signature.to_le_bytes();
+    let mut window_start = start;
+    while window_start + 4 <= file_len {
+        let len = (file_len - window_start).min(SCAN_WINDOW as u64) as usize;
+        let window = read_at(reader, window_start, len)?;
+        if let Some(at) = window.windows(4).position(|bytes| bytes == needle) {
+            return Ok(Some(window_start + at as u64));
+        }
+        if window_start + len as u64 >= file_len {
+            break;
+        }
+        // Overlap windows so a signature straddling the boundary is still seen.
+        window_start += len as u64 - 3;
+    }
+    Ok(None)
+}
@@ -0,0 +1,198 @@
+use std::{path::PathBuf, time::Duration};
+
+use anyhow::{Result, bail};
+
+use crate::generate::{self, BruteForce, Candidates, Generator, Hints};
+use crate::{Args, batch, fetch, load_target, strength, tune, wordlist};
+
+/// Passwords that top every published leak, tried before anything else.
+const QUICK: &[&str] = &[
+    "123456",
+    "password",
+    "123456789",
+    "12345678",
+    "12345",
+    "qwerty",
+    "1234567",
+    "111111",
+    "1234567890",
+    "123123",
+    "abc123",
+    "1234",
+    "password1",
+    "iloveyou",
+    "1q2w3e4r",
+    "000000",
+    "qwerty123",
+    "zaq12wsx",
+    "dragon",
+    "sunshine",
+    "princess",
+    "letmein",
+    "654321",
+    "monkey",
+    "1qaz2wsx",
+    "123321",
+    "qwertyuiop",
+    "superman",
+    "asdfghjkl",
+    "welcome",
+    "admin",
+    "football",
+    "baseball",
+    "master",
+    "shadow",
+    "michael",
+    "trustno1",
+    "666666",
+    "121212",
+    "7777777",
+    "123qwe",
+    "passw0rd",
+    "secret",
+    "changeme",
+    "test",
+    "test123",
+    "root",
+    "toor",
+    "login",
+    "starwars",
+    "hello",
+    "freedom",
+    "whatever",
+    "charlie",
+    "jordan",
+    "hunter",
+    "ashley",
+    "mustang",
+    "access",
+    "Password1",
+    "Password",
+    "P@ssw0rd",
+    "admin123",
+    "welcome1",
+];
+
+/// Well-known places security distributions install wordlists.
+const SYSTEM_WORDLISTS: &[&str] = &[
+    "/usr/share/wordlists/rockyou.txt",
+    "/usr/share/wordlists/rockyou.txt.gz",
+    "/usr/share/seclists/Passwords/Common-Credentials/10-million-password-list-top-1000000.txt",
+    "/usr/share/john/password.lst",
+];
+
+/// Mask character sets from richest to plainest; the first that reaches this length fits.
+const MASKS: &[&str] = &["?a", "?l?u?d", "?l?d", "?d"];
+const MASK_TARGET_LEN: usize = 6;
+const MAX_DIGITS: usize = 4;
+
+/// The built-in quick list, for the stage `--auto` puts first.
+pub fn quick_list() -> Candidates {
+    let mut candidates = Candidates::default();
+    for password in QUICK {
+        candidates.push(password);
+    }
+    candidates
+}
+
+/// `--auto`: fills in an escalating plan that fits `budget` at the rate this machine reaches
+/// on the target: the quick list, the wordlists (the biggest local one unless some were given),
+/// those words with digits appended, and a mask as long as what is left of the budget allows.
+pub fn plan(args: &mut Args, budget: Duration) -> Result<()> {
+    if batch::is_batch(args) {
+        bail!("--auto needs a single --zip");
+    }
+    let target = load_target(
+        &args.zip[0],
+        args.target.as_deref(),
+        &args.skip_entry,
+        args.backend,
+    )?;
+    let entry = &target.entries[target.index];
+    let rate = tune::probe_rate(&target.verifier) * args.threads as f64;
+    let mut remaining = budget.as_secs_f64() - QUICK.len() as f64 / rate;
+    println!(
+        "Auto plan for {} ({}) within {}, at about {rate:.0} candidates/s:",
+        entry.name,
+        entry.encryption,
+        strength::describe(budget.as_secs_f64())
+    );
+    println!("  {} common passwords", QUICK.len());
+
+    if args.wordlist.is_empty() {
+        match biggest_local_wordlist() {
+            Some(path) => args.wordlist.push(path),
+            None => println!("  No local wordlist found; `unzippoo fetch rockyou` downloads one"),
+        }
+    }
+    let mut words = 0u64;
+    for path in &args.wordlist {
+        let len = wordlist::load(&fetch::resolve_wordlist(path)?)?.words.len() as u64;
+        println!("  {len} words from {}", path.display());
+        words += len;
+    }
+    remaining -= words as f64 / rate;
+
+    if words > 0 && args.affix_digits.is_none() {
+        // Half of what is left at most, so the mask still gets a share.
+        let runs = |digits: usize| -> f64 { (1..=digits as i32).map(|run| 10f64.powi(run)).sum() };
+        let fits = (1..=MAX_DIGITS)
+            .rev()
+            .find(|&digits| words as f64 * runs(digits) / rate <= remaining / 2.0);
+        if let Some(digits) = fits {
+            args.affix_digits = Some((1, digits));
+            remaining -= words as f64 * runs(digits) / rate;
+            println!("  Those words followed by 1 to {digits} digits");
+        }
+    }
+
+    let hints = Hints {
+        prefix: args.known_prefix.clone().unwrap_or_default(),
+        suffix: args.known_suffix.clone().unwrap_or_default(),
+        contains: args.known_contains.clone().unwrap_or_default(),
+    };
+    let mut mask = None;
+    for spec in MASKS {
+        let charset = generate::parse_charset(spec).map_err(anyhow::Error::msg)?;
+        let mut len = 0;
+        while len < 16 {
+            let whole = (hints.len() + 1, hints.len() + len + 1);
+            let brute = BruteForce::new(charset.chars.clone(), whole.0, whole.1, hints.clone())?;
+            if Generator::BruteForce(brute).len() as f64 / rate > remaining {
+                break;
+            }
+            len += 1;
+        }
+        if len > 0 {
+            mask = Some((charset, len));
+        }
+        if len >= MASK_TARGET_LEN {
+            break;
+        }
+    }
+    match mask {
+        Some((charset, len)) => {
+            println!("  Brute force {} up to length {len}", charset.spec);
+            args.length = (hints.len() + 1, hints.len() + len);
+            args.charset = Some(charset);
+        }
+        None => println!("  No time is left for a brute force"),
+    }
+    args.time_limit = Some(budget);
+    Ok(())
+}
+
+/// The largest wordlist on this machine, by file size.
+fn biggest_local_wordlist() -> Option<PathBuf> {
+    fetch::local_wordlists()
+        .into_iter()
+        .chain(SYSTEM_WORDLISTS.iter().map(PathBuf::from))
+        .filter_map(|path| {
+            Some((
+                path.metadata().ok().filter(|meta| meta.is_file())?.len(),
+                path,
+            ))
+        })
+        .max_by_key(|&(size, _)| size)
+        .map(|(_, path)| path)
+}
@@ -0,0 +1,383 @@
+use std::{
+    path::{Path, PathBuf},
+    process::ExitCode,
+    sync::{
+        OnceLock,
+        atomic::{AtomicUsize, Ordering},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Result, bail};
+
+use crate::dedup::{self, Record};
+use crate::fetch::sha256_file;
+use crate::i18n::{Message, tr};
+use crate::manifest::{self, Entry};
+use crate::report::{RunResult, Status};
+use crate::spool;
+use crate::summary::{self, Row};
+use crate::{Args, Search, Session, control};
+
+/// What `--dedup` knows about the queue: archives that need not be attacked because identical
+/// contents were, or will be, attacked with the same plan.
+struct Dedup {
+    digests: Vec<Option<String>>,
+    /// The earlier queue entry each one copies.
+    copies: Vec<Option<usize>>,
+    /// Conclusive outcomes of earlier runs from `--dedup-file`.
+    recorded: Vec<Record>,
+    /// The record each entry was skipped for, set when a runner reaches it.
+    skipped: Vec<OnceLock<Record>>,
+}
+
+/// Attacks every `--zip` and `--manifest` archive, highest priority first and
+/// `--parallel-archives` of them at a time.
+pub fn run(args: &Args) -> ExitCode {
+    let started_at = Instant::now();
+    let fail = |error: anyhow::Error, archives: &mut dyn Iterator<Item = &Path>| {
+        eprintln!("Error: {error:?}");
+        let results: Vec<RunResult> = archives
+            .map(|zip| crate::error_result(zip, &error, started_at.elapsed()))
+            .collect();
+        crate::write_result(args, &results);
+        Status::Error.exit_code()
+    };
+    let queue = match validate(args).and_then(|()| manifest::queue(args)) {
+        Ok(queue) => queue,
+        Err(error) => return fail(error, &mut args.zip.iter().map(PathBuf::as_path)),
+    };
+    let outcomes = crate::with_session(args, |session| {
+        let dedup = find_duplicates(args, &queue)?;
+        Ok((search_all(args, session, &queue, &dedup), dedup))
+    });
+    let (outcomes, dedup) = match outcomes {
+        Ok(outcomes) => outcomes,
+        Err(error) => {
+            return fail(
+                error,
+                &mut queue.iter().map(|entry| entry.archive.as_path()),
+            );
+        }
+    };
+
+    let mut results = Vec::with_capacity(outcomes.len());
+    let mut worst = Status::Found;
+    for (index, entry) in queue.iter().enumerate() {
+        let zip = &entry.archive;
+        let source = dedup.copies[index].unwrap_or(index);
+        let mut result = if let Some(record) = dedup.skipped[source].get() {
+            let mut result = RunResult::new(record.status, zip, 0, Duration::ZERO);
+            result.reason =
+                Some("already attacked with the same plan in an earlier run".to_owned());
+            result.duplicate_of = Some(record.archive.display().to_string());
+            result
+        } else {
+            outcome_result(zip, &outcomes[source], started_at)
+        };
+        if source != index {
+            result.candidates_tried = 0;
+            result.elapsed_seconds = 0.0;
+            result.stages = &[];
+            result.duplicate_of = Some(queue[source].archive.display().to_string());
+        }
+        if severity(result.status) > severity(worst) {
+            worst = result.status;
+        }
+        results.push(result);
+    }
+    crate::write_result(args, &results);
+    write_summary(args, &results, &dedup, &outcomes);
+
+    let found = results
+        .iter()
+        .filter(|result| result.status == Status::Found)
+        .count();
+    println!(
+        "{}",
+        tr(
+            Message::Cracked,
+            &[
+                ("found", &found),
+                ("total", &results.len()),
+                ("elapsed", &format!("{:.2?}", started_at.elapsed())),
+            ],
+        )
+    );
+    worst.exit_code()
+}
+
+fn outcome_result<'a>(
+    zip: &Path,
+    outcome: &'a Option<Result<Search>>,
+    started_at: Instant,
+) -> RunResult<'a> {
+    match outcome {
+        Some(Ok(search)) => crate::run_result(zip, search),
+        Some(Err(error)) => crate::error_result(zip, error, started_at.elapsed()),
+        None => {
+            let mut result = RunResult::new(Status::Interrupted, zip, 0, Duration::ZERO);
+            result.reason = Some("interrupted before this archive was started".to_owned());
+            result
+        }
+    }
+}
+
+/// Appends the run to `--summary`; like the result file, a failure is only reported.
+fn write_summary(
+    args: &Args,
+    results: &[RunResult],
+    dedup: &Dedup,
+    outcomes: &[Option<Result<Search>>],
+) {
+    let Some(path) = &args.summary else {
+        return;
+    };
+    let finished_at = summary::now();
+    let rows: Vec<Row> = results
+        .iter()
+        .enumerate()
+        .map(|(index, result)| {
+            let source = dedup.copies[index].unwrap_or(index);
+            let scheme = match &outcomes[source] {
+                Some(Ok(search)) => Some(search.target.entries[search.target.index].encryption),
+                _ => None,
+            };
+            Row {
+                archive: result.archive.clone(),
+                entry: result.entry.map(str::to_owned),
+                scheme: scheme.map(|scheme| scheme.to_string()),
+                status: result.status,
+                password: result
+                    .password
+                    .map(|password| crate::shown(args, password).to_owned()),
+                stage: result
+                    .provenance
+                    .map(|provenance| provenance.source.clone()),
+                elapsed_seconds: result.elapsed_seconds,
+                finished_at,
+            }
+        })
+        .collect();
+    if let Err(error) = summary::append(path, rows) {
+        eprintln!("Error: {error:#}");
+    }
+}
+
+/// Several archives, a manifest, deduplication, or a summary make a batch, even of one archive.
+pub fn is_batch(args: &Args) -> bool {
+    args.zip.len() > 1
+        || args.manifest.is_some()
+        || args.dedup
+        || args.dedup_file.is_some()
+        || args.summary.is_some()
+}
+
+/// Post-recovery actions and per-run files are tied to a single archive.
+pub fn validate(args: &Args) -> Result<()> {
+    if args.parallel_archives == 0 {
+        bail!("--parallel-archives must be at least 1");
+    }
+    let single_only = [
+        ("--checkpoint", args.checkpoint.is_some()),
+        ("--session", args.session.is_some()),
+        ("--status-file", args.status_file.is_some()),
+        ("--preview", args.preview.is_some()),
+        ("--sniff", args.sniff),
+        ("--extract", args.extract.is_some()),
+        ("--copy", args.copy),
+        ("--timings", args.timings),
+        ("--survivors-out", args.survivors_out.is_some()),
+        ("--survivors-in", args.survivors_in.is_some()),
+    ];
+    if let Some((flag, _)) = single_only.iter().find(|(_, given)| *given) {
+        bail!("{flag} needs a single --zip");
+    }
+    Ok(())
+}
+
+/// With `--dedup`, hashes every archive and matches it against the entries before it. An
+/// archive that cannot be read is left to fail in the attack itself.
+fn find_duplicates(args: &Args, queue: &[Entry]) -> Result<Dedup> {
+    let mut dedup = Dedup {
+        digests: vec![None; queue.len()],
+        copies: vec![None; queue.len()],
+        recorded: Vec::new(),
+        skipped: queue.iter().map(|_| OnceLock::new()).collect(),
+    };
+    if !args.dedup && args.dedup_file.is_none() {
+        return Ok(dedup);
+    }
+    if let Some(path) = &args.dedup_file {
+        dedup.recorded = dedup::load(path)?;
+        dedup
+            .recorded
+            .retain(|record| dedup::is_conclusive(record.status));
+    }
+    dedup.digests = queue
+        .iter()
+        .map(|entry| sha256_file(&spool::resolve(&entry.archive)).ok())
+        .collect();
+    for (index, entry) in queue.iter().enumerate() {
+        let Some(digest) = &dedup.digests[index] else {
+            continue;
+        };
+        dedup.copies[index] = (0..index).find(|&earlier| {
+            dedup.digests[earlier].as_ref() == Some(digest) && queue[earlier].same_plan(entry)
+        });
+    }
+    Ok(dedup)
+}
+
+/// Hands queue entries out in order to `--parallel-archives` runners, each with an equal share
+/// of the threads. Entries never started because of an interrupt are left as `None`, and so are
+/// duplicates.
+fn search_all(
+    args: &Args,
+    session: &Session,
+    queue: &[Entry],
+    dedup: &Dedup,
+) -> Vec<Option<Result<Search>>> {
+    let runners = args.parallel_archives.min(queue.len());
+    let threads = (args.threads / runners).max(1);
+    let next = AtomicUsize::new(0);
+    let outcomes: Vec<OnceLock<Result<Search>>> = queue.iter().map(|_| OnceLock::new()).collect();
+
+    thread::scope(|scope| {
+        for _ in 0..runners {
+            scope.spawn(|| {
+                while !control::interrupted() {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(entry) = queue.get(index) else {
+                        return;
+                    };
+                    if let Some(original) = dedup.copies[index] {
+                        println!(
+                            "{}: skipped, same contents as {}",
+                            entry.archive.display(),
+                            queue[original].archive.display()
+                        );
+                        continue;
+                    }
+                    if let Some(outcome) = attack_entry(args, session, entry, threads, dedup, index)
+                    {
+                        let _ = outcomes[index].set(outcome);
+                    }
+                }
+            });
+        }
+    });
+    outcomes.into_iter().map(OnceLock::into_inner).collect()
+}
+
+/// Attacks one entry with its overrides applied, loading its own stages if it has hints,
+/// unless `--dedup-file` shows an earlier run already did. `None` means it was skipped.
+fn attack_entry(
+    args: &Args,
+    session: &Session,
+    entry: &Entry,
+    threads: usize,
+    dedup: &Dedup,
+    index: usize,
+) -> Option<Result<Search>> {
+    let zip = &entry.archive;
+    let entry_args = entry.apply(args);
+    let args = entry_args.as_ref().unwrap_or(args);
+    let entry_session;
+    let session = if entry.has_hints() {
+        match crate::load_stages(args) {
+            Ok(stages) => {
+                entry_session = session.with_stages(stages);
+                &entry_session
+            }
+            Err(error) => {
+                let outcome = Err(error);
+                crate::report_outcome(session, zip, Instant::now(), &outcome);
+                print_outcome(args, zip, &outcome);
+                return Some(outcome);
+            }
+        }
+    } else {
+        session
+    };
+
+    let plan = dedup::plan(args.target.as_deref(), &session.stages);
+    if let Some(digest) = &dedup.digests[index]
+        && let Some(record) = dedup
+            .recorded
+            .iter()
+            .rev()
+            .find(|record| &record.sha256 == digest && record.plan == plan)
+    {
+        println!(
+            "{}: skipped, same contents as {} in an earlier run",
+            zip.display(),
+            record.archive.display()
+        );
+        let _ = dedup.skipped[index].set(record.clone());
+        return None;
+    }
+
+    let outcome = crate::search(args, session, zip, threads);
+    print_outcome(args, zip, &outcome);
+    if let Ok(search) = &outcome {
+        crate::record_found(args, zip, search);
+        if let Some(path) = &args.dedup_file
+            && let Some(digest) = &dedup.digests[index]
+            && dedup::is_conclusive(search.status)
+        {
+            let record = Record::new(digest.clone(), plan, zip, search.status);
+            if let Err(error) = dedup::append(path, &record) {
+                eprintln!("Error: {error:#}");
+            }
+        }
+    }
+    Some(outcome)
+}
+
+fn print_outcome(args: &Args, zip: &Path, outcome: &Result<Search>) {
+    match outcome {
+        Ok(search) => match &search.password {
+            Some(password) => {
+                let provenance = search
+                    .provenance
+                    .as_ref()
+                    .map(|provenance| format!(", {provenance}"))
+                    .unwrap_or_default();
+                let strength = search
+                    .strength
+                    .as_ref()
+                    .map(|strength| format!(", strength {}/4", strength.score))
+                    .unwrap_or_default();
+                println!(
+                    "{}",
+                    tr(
+                        Message::BatchFound,
+                        &[
+                            ("archive", &zip.display()),
+                            ("password", &crate::shown(args, password)),
+                            ("count", &search.tried),
+                            ("elapsed", &format!("{:.2?}", search.elapsed)),
+                            ("details", &provenance),
+                            ("strength", &strength),
+                        ],
+                    )
+                );
+            }
+            None => println!("{}: {}", zip.display(), search.failure_summary()),
+        },
+        Err(error) => eprintln!("{}: error: {error:#}", zip.display()),
+    }
+}
+
+/// The batch exits with its worst outcome.
+fn severity(status: Status) -> u8 {
+    match status {
+        Status::Found => 0,
+        Status::NotFound => 1,
+        Status::BudgetExhausted => 2,
+        Status::Interrupted => 3,
+        Status::Error => 4,
+    }
+}
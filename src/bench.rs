@@ -0,0 +1,194 @@
+use std::{
+    hint::black_box,
+    io::Cursor,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Result, ensure};
+use clap::ValueEnum;
+
+use crate::archive;
+use crate::fixture::{self, Method, Scheme};
+use crate::hugepages;
+use crate::kdf::{self, AES_ITERATIONS as ITERATIONS, LANES};
+use crate::units;
+use crate::verify::{Backend, Verifier};
+
+/// AES-256 derives two 32-byte keys and a 2-byte password verifier.
+const KEY_LEN: usize = 66;
+const SALT: [u8; 16] = *b"unzippoo-bench!!";
+/// Large enough to outrun the TLB with 4 KiB pages but not with 2 MiB ones.
+const LOOKUP_BYTES: usize = 256 << 20;
+/// Backends within this fraction of the fastest count as tied, which favours the default.
+const TIE: f64 = 0.05;
+
+#[derive(clap::Args, Debug)]
+pub struct BenchArgs {
+    /// How long to measure each implementation (e.g. `5s`).
+    #[arg(long, value_name = "DURATION", default_value = "2s", value_parser = units::parse_duration)]
+    duration: Duration,
+}
+
+/// Compares the scalar `pbkdf2` crate against the multi-buffer derivation on one core, then
+/// every backend on ZipCrypto and AES targets.
+pub fn run(args: &BenchArgs) -> Result<()> {
+    let passwords: Vec<String> = (0..LANES).map(|n| format!("candidate{n}")).collect();
+    let lanes: [&[u8]; LANES] = std::array::from_fn(|lane| passwords[lane].as_bytes());
+
+    let derived = kdf::derive_lanes::<KEY_LEN>(&lanes, &SALT, ITERATIONS);
+    for (password, key) in lanes.iter().zip(&derived) {
+        ensure!(
+            *key == scalar(password),
+            "Multi-buffer PBKDF2 disagrees with the pbkdf2 crate"
+        );
+    }
+
+    println!(
+        "PBKDF2-HMAC-SHA1, {ITERATIONS} iterations, {KEY_LEN}-byte keys (AES-256), one thread:"
+    );
+    let scalar_rate = measure(args.duration, 1, || {
+        black_box(scalar(black_box(lanes[0])));
+    });
+    println!("  scalar (pbkdf2 crate):     {scalar_rate:>10.0} keys/s");
+    let lanes_rate = measure(args.duration, LANES as u64, || {
+        black_box(kdf::derive_lanes::<KEY_LEN>(
+            black_box(&lanes),
+            &SALT,
+            ITERATIONS,
+        ));
+    });
+    println!("  multi-buffer ({LANES} lanes):   {lanes_rate:>10.0} keys/s");
+    println!("  speedup: {:.2}x", lanes_rate / scalar_rate);
+
+    println!();
+    println!("Fast check and confirmation of wrong candidates, one thread:");
+    let mut fastest = Vec::new();
+    for scheme in [Scheme::Zipcrypto, Scheme::Aes256] {
+        let mut rates = Vec::new();
+        for &backend in Backend::value_variants() {
+            let name = backend_name(backend);
+            match backend_rate(scheme, backend, args.duration) {
+                Ok(rate) => {
+                    println!(
+                        "  {:<10} {name:<8} {rate:>12.0} candidates/s",
+                        scheme.encryption().to_string()
+                    );
+                    rates.push((backend, rate));
+                }
+                Err(error) => println!(
+                    "  {:<10} {name:<8} unavailable ({error:#})",
+                    scheme.encryption().to_string()
+                ),
+            }
+        }
+        // Value order puts the default first, so it wins ties.
+        let best = rates.iter().map(|&(_, rate)| rate).fold(0.0, f64::max);
+        if let Some(&(backend, _)) = rates.iter().find(|&&(_, rate)| rate >= best * (1.0 - TIE)) {
+            fastest.push((scheme, backend));
+        }
+    }
+    println!();
+    println!(
+        "Random lookups in a {} MiB index, one thread:",
+        LOOKUP_BYTES >> 20
+    );
+    let small_rate = lookup_rate(false, args.duration);
+    println!("  4 KiB pages:               {small_rate:>10.0} lookups/s");
+    let mut huge_speedup = None;
+    if hugepages::available() {
+        let huge_rate = lookup_rate(true, args.duration);
+        println!("  2 MiB pages:               {huge_rate:>10.0} lookups/s");
+        println!("  speedup: {:.2}x", huge_rate / small_rate);
+        huge_speedup = Some(huge_rate / small_rate);
+    } else {
+        println!("  2 MiB pages:               unavailable (transparent huge pages are disabled)");
+    }
+
+    println!();
+    match fastest.as_slice() {
+        [(_, zipcrypto), (_, aes)] if zipcrypto == aes => {
+            println!("Recommendation: --backend {}", backend_name(*aes));
+        }
+        [(_, zipcrypto), (_, aes)] => println!(
+            "Recommendation: --backend {} for ZipCrypto archives, --backend {} for AES",
+            backend_name(*zipcrypto),
+            backend_name(*aes)
+        ),
+        _ => {}
+    }
+    if let Some(speedup) = huge_speedup.filter(|&speedup| speedup > 1.0 + TIE) {
+        println!("With large wordlists, add --huge-pages ({speedup:.2}x faster lookups)");
+    }
+    Ok(())
+}
+
+fn backend_name(backend: Backend) -> String {
+    backend
+        .to_possible_value()
+        .expect("no backend is skipped")
+        .get_name()
+        .to_owned()
+}
+
+/// Pushes batches of wrong candidates through `backend` the way a search worker does.
+fn backend_rate(scheme: Scheme, backend: Backend, duration: Duration) -> Result<f64> {
+    let bytes = fixture::build(scheme, Method::Deflate, "bench-password", 1)?;
+    let mut reader = Cursor::new(bytes);
+    let entries = archive::read_central_directory(&mut reader)?.entries;
+    let entry = &entries[archive::select_target(&entries, None, &[])?];
+    let verifier = Verifier::new(
+        archive::extract_entry_archive(&mut reader, entry)?,
+        entry,
+        backend,
+    )?;
+
+    let batch_size = verifier.batch_size();
+    let passwords: Vec<String> = (0..batch_size).map(|n| format!("candidate{n}")).collect();
+    let batch: Vec<&str> = passwords.iter().map(String::as_str).collect();
+    Ok(measure(duration, batch_size as u64, || {
+        for (candidate, passed) in batch.iter().zip(verifier.check_batch(black_box(&batch))) {
+            if passed {
+                black_box(verifier.confirm(candidate).unwrap_or(false));
+            }
+        }
+    }))
+}
+
+fn scalar(password: &[u8]) -> [u8; KEY_LEN] {
+    let mut key = [0u8; KEY_LEN];
+    pbkdf2::pbkdf2_hmac::<sha1::Sha1>(password, &SALT, ITERATIONS, &mut key);
+    key
+}
+
+/// Reads scattered words of a wordlist-sized index, the access pattern of workers spread
+/// across a large list, with the memory advised for or against huge pages.
+fn lookup_rate(huge: bool, duration: Duration) -> f64 {
+    let len = LOOKUP_BYTES / size_of::<u64>();
+    let mut index: Vec<u64> = Vec::with_capacity(len);
+    let spare = index.spare_capacity_mut();
+    hugepages::advise(spare.as_ptr().cast(), size_of_val(spare), huge);
+    index.extend(0..len as u64);
+    let mut state = 0x9e37_79b9_7f4a_7c15u64;
+    let mut sum = 0u64;
+    let rate = measure(duration, 1024, || {
+        for _ in 0..1024 {
+            state = state
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            sum = sum.wrapping_add(index[(state >> 33) as usize % len]);
+        }
+    });
+    black_box(sum);
+    rate
+}
+
+/// Runs `derive` repeatedly for `duration` and returns keys (or candidates) per second.
+fn measure(duration: Duration, keys_per_call: u64, mut derive: impl FnMut()) -> f64 {
+    let started = Instant::now();
+    let mut keys = 0;
+    while started.elapsed() < duration {
+        derive();
+        keys += keys_per_call;
+    }
+    keys as f64 / started.elapsed().as_secs_f64()
+}
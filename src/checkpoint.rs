@@ -0,0 +1,331 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::report;
+use crate::search::Stage;
+use crate::units;
+use crate::vault::{self, Sealed, Vault};
+
+/// Bumped whenever the layout below or the candidate numbering of any generator changes.
+pub const FORMAT_VERSION: u32 = 3;
+
+/// How often `--checkpoint-interval` saves progress: after so much time, or after so many
+/// candidates.
+#[derive(Clone, Copy, Debug)]
+pub enum Interval {
+    Time(Duration),
+    Candidates(u64),
+}
+
+/// Parses `30s`, `15m`, and the other durations `--time-limit` takes, or a candidate count
+/// such as `100000c`.
+pub fn parse_interval(value: &str) -> Result<Interval, String> {
+    let interval = match value.trim().strip_suffix('c') {
+        Some(count) => Interval::Candidates(count.trim().parse().map_err(|_| {
+            format!("invalid checkpoint interval \"{value}\" (expected e.g. 30s or 100000c)")
+        })?),
+        None => Interval::Time(units::parse_duration(value)?),
+    };
+    if matches!(
+        interval,
+        Interval::Time(Duration::ZERO) | Interval::Candidates(0)
+    ) {
+        return Err("checkpoint interval must be above zero".to_owned());
+    }
+    Ok(interval)
+}
+
+/// A stage as it is numbered: enough to tell whether a checkpoint still fits the attack.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StageKey {
+    pub source: String,
+    pub keyspace: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub version: u32,
+    pub archive: String,
+    pub entry: String,
+    pub stages: Vec<StageKey>,
+    /// Every candidate with a lower global index has been tested.
+    pub next_index: u64,
+    pub updated_at: u64,
+    /// Unkeyed SHA-256 checksum of the checkpoint without this field, when it is not sealed with
+    /// `--store-key`. It catches corruption, not edits, since anyone can recompute it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sha256: Option<String>,
+}
+
+impl Checkpoint {
+    fn digest(&self) -> String {
+        let mut value = serde_json::to_value(self).expect("checkpoints serialize");
+        if let Some(fields) = value.as_object_mut() {
+            fields.remove("sha256");
+        }
+        Sha256::digest(value.to_string())
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    }
+}
+
+pub fn stage_keys(stages: &[Stage]) -> Vec<StageKey> {
+    stages
+        .iter()
+        .map(|stage| StageKey {
+            source: stage.source.clone(),
+            keyspace: stage.generator.len(),
+        })
+        .collect()
+}
+
+/// Reads a checkpoint and confirms it was written for this entry and attack.
+pub fn load(path: &Path, entry: &str, stages: &[StageKey]) -> Result<u64> {
+    let checkpoint = read(path, stages)?;
+    if checkpoint.entry != entry {
+        bail!(
+            "Checkpoint {} targets entry \"{}\", not \"{entry}\"",
+            path.display(),
+            checkpoint.entry
+        );
+    }
+    Ok(checkpoint.next_index)
+}
+
+/// Reads a checkpoint written by this format version for an attack numbered like `stages`,
+/// refusing one whose checksum or seal shows it was damaged since it was written.
+pub fn read(path: &Path, stages: &[StageKey]) -> Result<Checkpoint> {
+    read_with(path, stages, vault::installed())
+}
+
+fn read_with(path: &Path, stages: &[StageKey], vault: Option<&Vault>) -> Result<Checkpoint> {
+    let raw = std::fs::read(path)
+        .with_context(|| format!("Failed to read checkpoint: {}", path.display()))?;
+    let value: Value = serde_json::from_slice(&raw)
+        .with_context(|| format!("Checkpoint is not valid JSON: {}", path.display()))?;
+    let sealed = value.get("encrypted").is_some();
+    let mut checkpoint: Checkpoint = match vault {
+        Some(vault) if sealed => {
+            let sealed: Sealed = serde_json::from_value(value)?;
+            let plaintext = vault.unseal(&sealed).with_context(|| {
+                format!("Checkpoint {} failed its integrity check", path.display())
+            })?;
+            serde_json::from_slice(&plaintext)?
+        }
+        None if sealed => bail!(
+            "Checkpoint {} is encrypted; pass --store-key",
+            path.display()
+        ),
+        // Accepting a plain checkpoint would let anyone swap in one of their own.
+        Some(_) => bail!(
+            "Checkpoint {} is not encrypted, but --store-key was given",
+            path.display()
+        ),
+        None => serde_json::from_value(value)
+            .with_context(|| format!("Checkpoint is not valid: {}", path.display()))?,
+    };
+
+    if checkpoint.version != FORMAT_VERSION {
+        bail!(
+            "Checkpoint {} uses format version {}, but this build reads version {FORMAT_VERSION}",
+            path.display(),
+            checkpoint.version
+        );
+    }
+    if !sealed && checkpoint.sha256.take() != Some(checkpoint.digest()) {
+        bail!(
+            "Checkpoint {} does not match its checksum; it was truncated or corrupted since it \
+             was written",
+            path.display()
+        );
+    }
+    if checkpoint.stages != stages {
+        bail!(
+            "Checkpoint {} was written for different candidate sources; run with the same \
+             wordlists, charset, and length to resume or replay",
+            path.display()
+        );
+    }
+    Ok(checkpoint)
+}
+
+/// Saves progress once per [`Interval`], plus once more when the run ends. With
+/// `--store-key` each save is sealed, which hides what is being attacked and authenticates the
+/// contents; otherwise a SHA-256 checksum catches truncation and corruption, though not a
+/// deliberate edit.
+pub struct Checkpointer {
+    path: PathBuf,
+    checkpoint: Checkpoint,
+    interval: Interval,
+    last_write: Instant,
+    last_index: u64,
+}
+
+impl Checkpointer {
+    /// Starts counting the interval at `next_index`, where a resumed run picks up.
+    pub fn new(
+        path: PathBuf,
+        interval: Interval,
+        archive: String,
+        entry: String,
+        stages: Vec<StageKey>,
+        next_index: u64,
+    ) -> Self {
+        Self {
+            path,
+            checkpoint: Checkpoint {
+                version: FORMAT_VERSION,
+                archive,
+                entry,
+                stages,
+                next_index,
+                updated_at: 0,
+                sha256: None,
+            },
+            interval,
+            last_write: Instant::now(),
+            last_index: next_index,
+        }
+    }
+
+    pub fn record(&mut self, next_index: u64) {
+        self.checkpoint.next_index = next_index;
+        let due = match self.interval {
+            Interval::Time(interval) => self.last_write.elapsed() >= interval,
+            Interval::Candidates(count) => next_index.saturating_sub(self.last_index) >= count,
+        };
+        if due {
+            self.save();
+        }
+    }
+
+    pub fn finish(&mut self, next_index: u64) {
+        self.checkpoint.next_index = next_index;
+        self.save();
+    }
+
+    fn save(&mut self) {
+        self.save_with(vault::installed());
+    }
+
+    fn save_with(&mut self, vault: Option<&Vault>) {
+        self.checkpoint.updated_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs());
+        self.checkpoint.sha256 = None;
+        let written = match vault {
+            Some(vault) => serde_json::to_vec(&self.checkpoint)
+                .map_err(anyhow::Error::from)
+                .and_then(|plaintext| vault.seal(&plaintext))
+                .and_then(|sealed| report::write_json_atomically(&self.path, &sealed)),
+            None => {
+                self.checkpoint.sha256 = Some(self.checkpoint.digest());
+                report::write_json_atomically(&self.path, &self.checkpoint)
+            }
+        };
+        if let Err(error) = written {
+            eprintln!(
+                "Failed to write checkpoint {}: {error:#}",
+                self.path.display()
+            );
+        }
+        self.last_write = Instant::now();
+        self.last_index = self.checkpoint.next_index;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use zeroize::Zeroizing;
+
+    use super::*;
+
+    fn stages() -> Vec<StageKey> {
+        vec![
+            StageKey {
+                source: "wordlist rockyou.txt".to_owned(),
+                keyspace: 1000,
+            },
+            StageKey {
+                source: "charset ?d (length 4)".to_owned(),
+                keyspace: 10_000,
+            },
+        ]
+    }
+
+    /// A checkpoint for `stages()` saved at `next_index` to a fresh file named after `test`.
+    fn saved(test: &str, next_index: u64, vault: Option<&Vault>) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "unzippoo-checkpoint-{}-{test}.json",
+            std::process::id()
+        ));
+        let mut checkpointer = Checkpointer::new(
+            path.clone(),
+            Interval::Candidates(1),
+            "evidence.zip".to_owned(),
+            "secret.txt".to_owned(),
+            stages(),
+            0,
+        );
+        checkpointer.checkpoint.next_index = next_index;
+        checkpointer.save_with(vault);
+        path
+    }
+
+    fn read_error(path: &Path, stages: &[StageKey], vault: Option<&Vault>) -> String {
+        format!("{:#}", read_with(path, stages, vault).unwrap_err())
+    }
+
+    #[test]
+    fn loads_what_was_saved() {
+        let path = saved("round-trip", 4321, None);
+        assert_eq!(load(&path, "secret.txt", &stages()).unwrap(), 4321);
+        let error = load(&path, "other.txt", &stages()).unwrap_err();
+        assert!(format!("{error:#}").contains("targets entry \"secret.txt\""));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn refuses_an_edited_next_index() {
+        let path = saved("edited", 4321, None);
+        let mut value: Value = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        value["next_index"] = 9999.into();
+        std::fs::write(&path, value.to_string()).unwrap();
+        assert!(read_error(&path, &stages(), None).contains("does not match its checksum"));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn refuses_different_stages() {
+        let path = saved("stages", 4321, None);
+        let mut other = stages();
+        other[1].keyspace += 1;
+        assert!(read_error(&path, &other, None).contains("different candidate sources"));
+        assert!(read_error(&path, &stages()[..1], None).contains("different candidate sources"));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn sealed_checkpoints_need_the_vault_and_plain_ones_are_refused_with_it() {
+        let vault = Vault::from_secret(Zeroizing::new("correct horse".to_owned())).unwrap();
+        let plain = saved("plain", 4321, None);
+        assert!(read_error(&plain, &stages(), Some(&vault)).contains("is not encrypted"));
+
+        let sealed = saved("sealed", 4321, Some(&vault));
+        let raw = std::fs::read_to_string(&sealed).unwrap();
+        assert!(!raw.contains("secret.txt"));
+        let checkpoint = read_with(&sealed, &stages(), Some(&vault)).unwrap();
+        assert_eq!(checkpoint.next_index, 4321);
+        assert!(read_error(&sealed, &stages(), None).contains("is encrypted"));
+        std::fs::remove_file(plain).unwrap();
+        std::fs::remove_file(sealed).unwrap();
+    }
+}
@@ -0,0 +1,69 @@
+use std::{
+    io::{self, Write},
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Result, bail};
+
+use crate::control;
+
+/// Clipboard helpers in order of preference; the first one that runs wins.
+fn helpers() -> Vec<(&'static str, &'static [&'static str])> {
+    if cfg!(target_os = "macos") {
+        vec![("pbcopy", &[])]
+    } else if cfg!(windows) {
+        vec![("clip.exe", &[])]
+    } else {
+        let mut helpers: Vec<(&'static str, &'static [&'static str])> = Vec::new();
+        if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            helpers.push(("wl-copy", &[]));
+        }
+        helpers.push(("xclip", &["-selection", "clipboard"]));
+        helpers.push(("xsel", &["--clipboard", "--input"]));
+        // WSL exposes the Windows clipboard through clip.exe on the PATH.
+        helpers.push(("clip.exe", &[]));
+        helpers
+    }
+}
+
+/// Places `text` on the system clipboard, returning the name of the helper used.
+pub fn copy(text: &str) -> Result<&'static str> {
+    for (program, helper_args) in helpers() {
+        match run_helper(program, helper_args, text) {
+            Ok(()) => return Ok(program),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => continue,
+            Err(error) => bail!("{program} failed: {error}"),
+        }
+    }
+    bail!("no clipboard helper found (install wl-copy, xclip, or xsel)")
+}
+
+fn run_helper(program: &str, helper_args: &[&str], text: &str) -> io::Result<()> {
+    let mut child = Command::new(program)
+        .args(helper_args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes())?;
+    }
+    let status = child.wait()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!("exited with {status}")))
+    }
+}
+
+/// Waits out `timeout` (or until interrupted), then empties the clipboard.
+pub fn clear_after(timeout: Duration) -> Result<()> {
+    let deadline = Instant::now() + timeout;
+    while Instant::now() < deadline && !control::interrupted() {
+        thread::sleep(Duration::from_millis(100).min(deadline - Instant::now()));
+    }
+    copy("")?;
+    Ok(())
+}
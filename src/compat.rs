@@ -0,0 +1,298 @@
+use std::ffi::{OsStr, OsString};
+
+use anyhow::{Context, Result, bail};
+
+/// fcrackzip's default `-c` when only `-b` is given.
+const DEFAULT_CHARSET: &str = "aA1!";
+/// fcrackzip's `!` class.
+const FCRACKZIP_SYMBOLS: &str = "!:$%&/()=?{[]}+*~#";
+
+/// Translates fcrackzip's command line (`-b -c a1 -l 4-7 -u secret.zip`) into unzippoo's.
+pub fn translate_fcrackzip(args: &[OsString]) -> Result<Vec<OsString>> {
+    let mut brute = false;
+    let mut dictionary = false;
+    let mut charset: Option<String> = None;
+    let mut length: Option<String> = None;
+    let mut init_password: Option<OsString> = None;
+    let mut verbose = false;
+    let mut files: Vec<OsString> = Vec::new();
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let Some(text) = arg
+            .to_str()
+            .filter(|text| text.starts_with('-') && text.len() > 1)
+        else {
+            files.push(arg.clone());
+            continue;
+        };
+        if text == "--" {
+            files.extend(iter.by_ref().cloned());
+            break;
+        }
+
+        // Long options and clusters of short flags such as `-bu` or `-bc a1`.
+        let flags: Vec<String> = match text.strip_prefix("--") {
+            Some(long) => vec![long.to_owned()],
+            None => text[1..].chars().map(String::from).collect(),
+        };
+        let mut flags = flags.into_iter().peekable();
+        while let Some(flag) = flags.next() {
+            let mut value = |name: &str| -> Result<OsString> {
+                // An option's value is the rest of the cluster, or the next argument.
+                let rest: String = flags.by_ref().collect();
+                if !rest.is_empty() {
+                    return Ok(rest.into());
+                }
+                iter.next()
+                    .cloned()
+                    .with_context(|| format!("fcrackzip option {name} needs a value"))
+            };
+            match flag.as_str() {
+                "b" | "brute-force" => brute = true,
+                "D" | "dictionary" => dictionary = true,
+                "c" | "charset" => charset = Some(utf8(value("-c")?, "-c")?),
+                "l" | "length" => length = Some(utf8(value("-l")?, "-l")?),
+                "p" | "init-password" => init_password = Some(value("-p")?),
+                // Every hit is fully decrypted and CRC-checked, which is what -u asked for.
+                "u" | "use-unzip" => {}
+                "v" | "verbose" => verbose = true,
+                "m" | "method" => {
+                    value("-m")?;
+                }
+                "2" | "modulo" => bail!("fcrackzip's -2/--modulo is not supported"),
+                "B" | "benchmark" => bail!("fcrackzip's -B/--benchmark is not supported"),
+                "V" | "validate" => bail!("fcrackzip's -V/--validate is not supported"),
+                "h" | "help" => bail!(
+                    "supported fcrackzip options: -b, -D, -c SET, -l MIN[-MAX], -p FILE, -u, -v, -m"
+                ),
+                other => bail!("unknown fcrackzip option \"{other}\""),
+            }
+        }
+    }
+
+    let zip = match files.len() {
+        0 => bail!("no archive given"),
+        1 => files.remove(0),
+        _ => bail!("only one archive per run is supported"),
+    };
+    let mut native: Vec<OsString> = vec!["--zip".into(), zip];
+    if verbose {
+        native.push("--verbose".into());
+    }
+
+    if dictionary {
+        if brute {
+            bail!("-b and -D are mutually exclusive");
+        }
+        let wordlist = init_password.context("-D needs a dictionary file given with -p")?;
+        native.extend(["--wordlist".into(), wordlist]);
+    } else {
+        if init_password.is_some() {
+            bail!("starting brute force from a -p password is not supported; use -l instead");
+        }
+        let charset = expand_charset(charset.as_deref().unwrap_or(DEFAULT_CHARSET))?;
+        native.extend(["--charset".into(), charset.into()]);
+        if let Some(length) = length {
+            native.extend(["--length".into(), length.into()]);
+        }
+    }
+    Ok(native)
+}
+
+fn utf8(value: OsString, name: &str) -> Result<String> {
+    value
+        .into_string()
+        .map_err(|value| anyhow::anyhow!("invalid {name} value {:?}", OsStr::new(&value)))
+}
+
+/// Maps fcrackzip's class letters onto an unzippoo charset spec.
+fn expand_charset(spec: &str) -> Result<String> {
+    let mut expanded = String::new();
+    let mut chars = spec.chars();
+    while let Some(class) = chars.next() {
+        match class {
+            'a' => expanded.push_str("?l"),
+            'A' => expanded.push_str("?u"),
+            '1' => expanded.push_str("?d"),
+            '!' => expanded.push_str(&FCRACKZIP_SYMBOLS.replace('?', "??")),
+            // Everything after `:` is taken literally.
+            ':' => {
+                expanded.extend(
+                    chars
+                        .by_ref()
+                        .flat_map(|c| if c == '?' { vec!['?', '?'] } else { vec![c] }),
+                );
+            }
+            other => bail!("unknown fcrackzip charset class \"{other}\""),
+        }
+    }
+    Ok(expanded)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use clap::Parser;
+
+    use super::*;
+    use crate::{Args, Cli};
+
+    fn translate(line: &str) -> Result<Vec<String>> {
+        let args: Vec<OsString> = line.split_whitespace().map(OsString::from).collect();
+        let native = translate_fcrackzip(&args)?;
+        Ok(native
+            .into_iter()
+            .map(|arg| arg.into_string().unwrap())
+            .collect())
+    }
+
+    /// The crack arguments `native` parses into, as main would run them.
+    fn parsed(native: &[String]) -> Args {
+        let argv = std::iter::once("unzippoo").chain(native.iter().map(String::as_str));
+        Cli::try_parse_from(argv).unwrap().crack.unwrap()
+    }
+
+    #[test]
+    fn translates_supported_invocations() {
+        let symbols = "!:$%&/()=??{[]}+*~#";
+        let cases: [(&str, Vec<String>); 9] = [
+            (
+                "-b -c a1 -l 4-7 -u secret.zip",
+                [
+                    "--zip",
+                    "secret.zip",
+                    "--charset",
+                    "?l?d",
+                    "--length",
+                    "4-7",
+                ]
+                .map(String::from)
+                .to_vec(),
+            ),
+            (
+                "-bu -ca1 -l4-7 secret.zip",
+                [
+                    "--zip",
+                    "secret.zip",
+                    "--charset",
+                    "?l?d",
+                    "--length",
+                    "4-7",
+                ]
+                .map(String::from)
+                .to_vec(),
+            ),
+            (
+                "-b -l 3 secret.zip",
+                vec![
+                    "--zip".into(),
+                    "secret.zip".into(),
+                    "--charset".into(),
+                    format!("?l?u?d{symbols}"),
+                    "--length".into(),
+                    "3".into(),
+                ],
+            ),
+            (
+                "--brute-force --charset A! --length 2-4 secret.zip",
+                vec![
+                    "--zip".into(),
+                    "secret.zip".into(),
+                    "--charset".into(),
+                    format!("?u{symbols}"),
+                    "--length".into(),
+                    "2-4".into(),
+                ],
+            ),
+            (
+                "-b -c 1:xy? -l 2 secret.zip",
+                [
+                    "--zip",
+                    "secret.zip",
+                    "--charset",
+                    "?dxy??",
+                    "--length",
+                    "2",
+                ]
+                .map(String::from)
+                .to_vec(),
+            ),
+            (
+                "-D -p rockyou.txt -u secret.zip",
+                ["--zip", "secret.zip", "--wordlist", "rockyou.txt"]
+                    .map(String::from)
+                    .to_vec(),
+            ),
+            (
+                "--dictionary --init-password words.txt -v secret.zip",
+                [
+                    "--zip",
+                    "secret.zip",
+                    "--verbose",
+                    "--wordlist",
+                    "words.txt",
+                ]
+                .map(String::from)
+                .to_vec(),
+            ),
+            (
+                "-Dup words.txt secret.zip",
+                ["--zip", "secret.zip", "--wordlist", "words.txt"]
+                    .map(String::from)
+                    .to_vec(),
+            ),
+            (
+                "-b -m 1 -c a -l 1 -- -odd.zip",
+                ["--zip", "-odd.zip", "--charset", "?l", "--length", "1"]
+                    .map(String::from)
+                    .to_vec(),
+            ),
+        ];
+        for (foreign, expected) in cases {
+            assert_eq!(translate(foreign).unwrap(), expected, "{foreign}");
+        }
+    }
+
+    #[test]
+    fn translated_arguments_run_the_same_attack() {
+        let brute = parsed(&translate("-b -c a1 -l 4-7 -u secret.zip").unwrap());
+        assert_eq!(brute.zip, [PathBuf::from("secret.zip")]);
+        assert!(brute.wordlist.is_empty());
+        let charset = brute.charset.unwrap();
+        assert_eq!(charset.chars.len(), 36);
+        assert_eq!(charset.chars[..3], ['a', 'b', 'c']);
+        assert_eq!(brute.length, (4, 7));
+        assert!(!brute.verbose);
+
+        let dictionary = parsed(&translate("-D -p rockyou.txt -v secret.zip").unwrap());
+        assert_eq!(dictionary.zip, [PathBuf::from("secret.zip")]);
+        assert_eq!(dictionary.wordlist, [PathBuf::from("rockyou.txt")]);
+        assert!(dictionary.charset.is_none());
+        assert!(dictionary.verbose);
+    }
+
+    #[test]
+    fn refuses_what_it_cannot_translate() {
+        for (foreign, error) in [
+            ("-b", "no archive given"),
+            ("-b a.zip b.zip", "only one archive"),
+            ("-b -D -p words.txt a.zip", "mutually exclusive"),
+            ("-D a.zip", "-D needs a dictionary file"),
+            (
+                "-b -p aaaa a.zip",
+                "starting brute force from a -p password",
+            ),
+            ("-b -c a -l", "-l needs a value"),
+            ("-b -c q a.zip", "unknown fcrackzip charset class \"q\""),
+            ("-2 1/2 a.zip", "-2/--modulo is not supported"),
+            ("-B", "-B/--benchmark is not supported"),
+            ("-V a.zip", "-V/--validate is not supported"),
+            ("-x a.zip", "unknown fcrackzip option \"x\""),
+        ] {
+            let message = format!("{:#}", translate(foreign).unwrap_err());
+            assert!(message.contains(error), "{foreign}: {message}");
+        }
+    }
+}
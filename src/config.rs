@@ -0,0 +1,70 @@
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+/// Settings read from `$UNZIPPOO_CONFIG`, or `unzippoo/config.json` under the user's config
+/// directory. A missing file is the same as an empty one.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// `@NAME` aliases, each a local path or a URL to fetch into the cache on first use.
+    pub wordlists: BTreeMap<String, String>,
+    #[serde(skip)]
+    dir: PathBuf,
+}
+
+impl Config {
+    /// Relative alias paths are taken relative to the config file; `~/` is the home directory.
+    pub fn alias_path(&self, target: &str) -> PathBuf {
+        match target.strip_prefix("~/").zip(std::env::var_os("HOME")) {
+            Some((rest, home)) => PathBuf::from(home).join(rest),
+            None => self.dir.join(target),
+        }
+    }
+}
+
+pub fn is_url(target: &str) -> bool {
+    ["http://", "https://", "ftp://", "file://"]
+        .iter()
+        .any(|scheme| target.starts_with(scheme))
+}
+
+pub fn load() -> Result<Config> {
+    let Some(path) = path() else {
+        return Ok(Config::default());
+    };
+    let bytes = match fs::read(&path) {
+        Ok(bytes) => bytes,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Config::default()),
+        Err(error) => {
+            return Err(error)
+                .with_context(|| format!("Failed to read config file: {}", path.display()));
+        }
+    };
+    let mut config: Config = serde_json::from_slice(&bytes)
+        .with_context(|| format!("Invalid config file: {}", path.display()))?;
+    config.dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+    Ok(config)
+}
+
+fn path() -> Option<PathBuf> {
+    let env = |name| std::env::var_os(name).filter(|value| !value.is_empty());
+    if let Some(path) = env("UNZIPPOO_CONFIG") {
+        return Some(PathBuf::from(path));
+    }
+    let base = if cfg!(windows) {
+        env("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        env("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"))
+    } else {
+        env("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env("HOME").map(|home| PathBuf::from(home).join(".config")))
+    };
+    base.map(|base| base.join("unzippoo").join("config.json"))
+}
@@ -0,0 +1,356 @@
+use std::{
+    path::PathBuf,
+    sync::{
+        Condvar, Mutex,
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+    },
+    thread,
+    time::Duration,
+};
+
+use anyhow::{Result, bail};
+
+/// How often parked workers wake to notice an interrupt the signal handler could not signal.
+const INTERRUPT_POLL: Duration = Duration::from_millis(100);
+
+/// How often `--stop-file` is looked for.
+const STOP_FILE_POLL: Duration = Duration::from_secs(1);
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+static STOP_FILE_SEEN: AtomicBool = AtomicBool::new(false);
+
+/// Whether SIGINT, SIGTERM, or `--stop-file` has asked the run to wind down.
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// Whether it was `--stop-file` that did.
+pub fn stopped_by_file() -> bool {
+    STOP_FILE_SEEN.load(Ordering::SeqCst)
+}
+
+/// Winds the run down like the first SIGINT once `path` appears, for schedulers that cannot
+/// send signals. The file is removed once noticed, so the next run starts normally; one that
+/// is already there is refused rather than taken as a stale request.
+pub fn watch_stop_file(path: PathBuf) -> Result<()> {
+    if path.exists() {
+        bail!(
+            "Stop file {} already exists; remove it before starting",
+            path.display()
+        );
+    }
+    thread::spawn(move || {
+        while !interrupted() {
+            thread::sleep(STOP_FILE_POLL);
+            if path.exists() {
+                eprintln!("Stop file {} appeared; stopping", path.display());
+                STOP_FILE_SEEN.store(true, Ordering::SeqCst);
+                INTERRUPTED.store(true, Ordering::SeqCst);
+                let _ = std::fs::remove_file(&path);
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Shared run-state that lets workers be paused, resumed, or throttled without losing their place.
+pub struct Governor {
+    paused: AtomicBool,
+    active_limit: AtomicUsize,
+    active: AtomicUsize,
+    waiters: AtomicUsize,
+    stalls: AtomicU64,
+    lock: Mutex<()>,
+    changed: Condvar,
+}
+
+/// Held by a worker while it tests a candidate; dropping it frees the slot for another worker.
+pub struct Permit<'a> {
+    governor: &'a Governor,
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        self.governor.active.fetch_sub(1, Ordering::SeqCst);
+        if self.governor.waiters.load(Ordering::SeqCst) > 0 {
+            let _guard = self.governor.lock.lock().expect("poisoned governor");
+            self.governor.changed.notify_all();
+        }
+    }
+}
+
+impl Governor {
+    pub fn new(workers: usize) -> Self {
+        Self {
+            paused: AtomicBool::new(false),
+            active_limit: AtomicUsize::new(workers),
+            active: AtomicUsize::new(0),
+            waiters: AtomicUsize::new(0),
+            stalls: AtomicU64::new(0),
+            lock: Mutex::new(()),
+            changed: Condvar::new(),
+        }
+    }
+
+    /// Times a worker has had to wait for its turn, paused or throttled.
+    pub fn stalls(&self) -> u64 {
+        self.stalls.load(Ordering::Relaxed)
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    pub fn pause(&self) -> bool {
+        let _guard = self.lock.lock().expect("poisoned governor");
+        !self.paused.swap(true, Ordering::SeqCst)
+    }
+
+    pub fn resume(&self) -> bool {
+        let _guard = self.lock.lock().expect("poisoned governor");
+        let was_paused = self.paused.swap(false, Ordering::SeqCst);
+        self.changed.notify_all();
+        was_paused
+    }
+
+    pub fn toggle(&self) -> bool {
+        if self.is_paused() {
+            self.resume();
+            false
+        } else {
+            self.pause();
+            true
+        }
+    }
+
+    pub fn active_limit(&self) -> usize {
+        self.active_limit.load(Ordering::SeqCst)
+    }
+
+    /// Caps how many candidates may be tested at once; extra workers park until it rises.
+    pub fn set_active_limit(&self, limit: usize) {
+        let _guard = self.lock.lock().expect("poisoned governor");
+        self.active_limit.store(limit.max(1), Ordering::SeqCst);
+        self.changed.notify_all();
+    }
+
+    fn try_acquire(&self) -> bool {
+        if self.paused.load(Ordering::SeqCst) {
+            return false;
+        }
+        let limit = self.active_limit.load(Ordering::SeqCst);
+        self.active
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |active| {
+                (active < limit).then_some(active + 1)
+            })
+            .is_ok()
+    }
+
+    /// Blocks the calling worker while the run is paused or throttled below its turn.
+    ///
+    /// Gives up and returns `None` if the run is interrupted while waiting.
+    pub fn acquire(&self) -> Option<Permit<'_>> {
+        if !self.try_acquire() {
+            self.stalls.fetch_add(1, Ordering::Relaxed);
+            let mut guard = self.lock.lock().expect("poisoned governor");
+            self.waiters.fetch_add(1, Ordering::SeqCst);
+            while !self.try_acquire() {
+                if interrupted() {
+                    self.waiters.fetch_sub(1, Ordering::SeqCst);
+                    return None;
+                }
+                guard = self
+                    .changed
+                    .wait_timeout(guard, INTERRUPT_POLL)
+                    .expect("poisoned governor")
+                    .0;
+            }
+            self.waiters.fetch_sub(1, Ordering::SeqCst);
+        }
+        Some(Permit { governor: self })
+    }
+}
+
+#[cfg(unix)]
+pub use unix::{install_interrupt_handler, spawn_control_socket, spawn_keypress_listener};
+
+#[cfg(not(unix))]
+pub fn install_interrupt_handler() {}
+
+#[cfg(unix)]
+mod unix {
+    use std::{
+        io::{self, BufRead, BufReader, IsTerminal, Read, Write},
+        os::unix::{
+            fs::FileTypeExt,
+            net::{UnixListener, UnixStream},
+        },
+        path::{Path, PathBuf},
+        sync::{Arc, OnceLock, atomic::Ordering},
+        thread,
+    };
+
+    use anyhow::{Context, Result, bail};
+
+    use super::{Governor, INTERRUPTED};
+
+    static ORIGINAL_TERMIOS: OnceLock<libc::termios> = OnceLock::new();
+
+    /// Restores the terminal mode changed by [`spawn_keypress_listener`] when dropped.
+    pub struct KeypressGuard;
+
+    impl Drop for KeypressGuard {
+        fn drop(&mut self) {
+            restore_terminal();
+        }
+    }
+
+    fn restore_terminal() {
+        if let Some(original) = ORIGINAL_TERMIOS.get() {
+            unsafe {
+                libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, original);
+            }
+        }
+    }
+
+    extern "C" fn handle_interrupt(signal: libc::c_int) {
+        // The first signal lets workers finish their candidate and the run report normally;
+        // a second one means the user wants out now.
+        if !INTERRUPTED.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        restore_terminal();
+        unsafe {
+            libc::signal(signal, libc::SIG_DFL);
+            libc::raise(signal);
+        }
+    }
+
+    /// Routes SIGINT and SIGTERM to [`super::interrupted`] instead of killing the process.
+    pub fn install_interrupt_handler() {
+        let handler = handle_interrupt as extern "C" fn(libc::c_int) as libc::sighandler_t;
+        unsafe {
+            libc::signal(libc::SIGINT, handler);
+            libc::signal(libc::SIGTERM, handler);
+        }
+    }
+
+    /// Puts the terminal into unbuffered mode and toggles pause whenever space is pressed.
+    ///
+    /// Returns `None` when stdin is not a TTY.
+    pub fn spawn_keypress_listener(governor: Arc<Governor>) -> Option<KeypressGuard> {
+        let stdin = std::io::stdin();
+        if !stdin.is_terminal() {
+            return None;
+        }
+
+        let mut termios = unsafe { std::mem::zeroed::<libc::termios>() };
+        if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut termios) } != 0 {
+            return None;
+        }
+        let original = *ORIGINAL_TERMIOS.get_or_init(|| termios);
+
+        let mut raw = original;
+        raw.c_lflag &= !(libc::ICANON | libc::ECHO);
+        raw.c_cc[libc::VMIN] = 1;
+        raw.c_cc[libc::VTIME] = 0;
+        if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) } != 0 {
+            return None;
+        }
+
+        thread::spawn(move || {
+            let mut byte = [0u8; 1];
+            let mut stdin = stdin.lock();
+            while let Ok(1) = stdin.read(&mut byte) {
+                if byte[0] == b' ' {
+                    if governor.toggle() {
+                        eprintln!("Paused - press space to resume");
+                    } else {
+                        eprintln!("Resumed");
+                    }
+                }
+            }
+        });
+
+        Some(KeypressGuard)
+    }
+
+    /// Listens on a Unix socket for `pause`, `resume`, and `status` commands.
+    pub fn spawn_control_socket(path: &Path, governor: Arc<Governor>) -> Result<ControlSocket> {
+        // Only a socket left behind by an earlier run is cleared; anything else is a mistake.
+        match std::fs::symlink_metadata(path) {
+            Ok(meta) if meta.file_type().is_socket() => {
+                std::fs::remove_file(path).with_context(|| {
+                    format!("Failed to remove stale control socket: {}", path.display())
+                })?;
+            }
+            Ok(_) => bail!(
+                "{} exists and is not a socket; pick another --control-socket path",
+                path.display()
+            ),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {}
+            Err(error) => {
+                return Err(error).with_context(|| {
+                    format!("Failed to inspect control socket: {}", path.display())
+                });
+            }
+        }
+        let listener = UnixListener::bind(path)
+            .with_context(|| format!("Failed to bind control socket: {}", path.display()))?;
+
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let governor = Arc::clone(&governor);
+                thread::spawn(move || handle_client(stream, &governor));
+            }
+        });
+
+        Ok(ControlSocket {
+            path: path.to_path_buf(),
+        })
+    }
+
+    fn handle_client(stream: UnixStream, governor: &Governor) {
+        let Ok(mut writer) = stream.try_clone() else {
+            return;
+        };
+        for line in BufReader::new(stream).lines() {
+            let Ok(line) = line else {
+                return;
+            };
+            let reply = match line.trim() {
+                "pause" => {
+                    if governor.pause() {
+                        eprintln!("Paused via control socket");
+                    }
+                    "ok paused"
+                }
+                "resume" => {
+                    if governor.resume() {
+                        eprintln!("Resumed via control socket");
+                    }
+                    "ok running"
+                }
+                "status" if governor.is_paused() => "paused",
+                "status" => "running",
+                "" => continue,
+                _ => "error unknown command (expected pause, resume, or status)",
+            };
+            if writeln!(writer, "{reply}").is_err() {
+                return;
+            }
+        }
+    }
+
+    /// Removes the socket file once the run finishes.
+    pub struct ControlSocket {
+        path: PathBuf,
+    }
+
+    impl Drop for ControlSocket {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
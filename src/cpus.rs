@@ -0,0 +1,80 @@
+use std::sync::OnceLock;
+#[cfg(target_os = "linux")]
+use std::{fs, path::Path};
+
+/// The CPUs this process may keep busy: the affinity mask and the quota of its own cgroup,
+/// which `num_cpus` honours, capped further by any quota on a cgroup above it, such as a
+/// systemd slice with `CPUQuota=` or a container runtime's parent cgroup.
+pub fn available() -> usize {
+    static AVAILABLE: OnceLock<usize> = OnceLock::new();
+    *AVAILABLE.get_or_init(|| {
+        let cpus = num_cpus::get();
+        quota().map_or(cpus, |quota| quota.clamp(1, cpus))
+    })
+}
+
+/// The tightest cgroup CPU quota from this process's cgroup up to the root, in whole CPUs
+/// rounded up, when one is set. Cgroups are looked for where they are mounted by default.
+#[cfg(target_os = "linux")]
+fn quota() -> Option<usize> {
+    let membership = fs::read_to_string("/proc/self/cgroup").ok()?;
+    let unified = Path::new("/sys/fs/cgroup");
+    if unified.join("cgroup.controllers").exists() {
+        let path = membership
+            .lines()
+            .find_map(|line| line.strip_prefix("0::"))?;
+        return tightest(unified, path, |dir| {
+            let max = fs::read_to_string(dir.join("cpu.max")).ok()?;
+            let mut fields = max.split_whitespace();
+            let quota = fields.next()?.parse().ok()?;
+            Some((quota, fields.next()?.parse().ok()?))
+        });
+    }
+    let path = membership.lines().find_map(|line| {
+        let mut fields = line.splitn(3, ':');
+        let controllers = fields.nth(1)?;
+        controllers
+            .split(',')
+            .any(|controller| controller == "cpu")
+            .then(|| fields.next())?
+    })?;
+    let mount = ["/sys/fs/cgroup/cpu", "/sys/fs/cgroup/cpu,cpuacct"]
+        .into_iter()
+        .map(Path::new)
+        .find(|mount| mount.join("cpu.cfs_quota_us").exists())?;
+    tightest(mount, path, |dir| {
+        let read = |name| fs::read_to_string(dir.join(name)).ok()?.trim().parse().ok();
+        // An unlimited cgroup has a quota of -1, which fails to parse as unsigned.
+        Some((read("cpu.cfs_quota_us")?, read("cpu.cfs_period_us")?))
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn quota() -> Option<usize> {
+    None
+}
+
+/// Walks from `path` under `mount` up to `mount` itself, taking the smallest `quota / period`.
+/// Inside a cgroup namespace the path is relative to a root the mount already is, so a path
+/// that is not there starts the walk at the mount.
+#[cfg(target_os = "linux")]
+fn tightest(
+    mount: &Path,
+    path: &str,
+    limit: impl Fn(&Path) -> Option<(u64, u64)>,
+) -> Option<usize> {
+    let mut dir = mount.join(path.trim_start_matches('/'));
+    if !dir.is_dir() {
+        dir = mount.to_path_buf();
+    }
+    let mut tightest: Option<usize> = None;
+    loop {
+        if let Some((quota, period)) = limit(&dir).filter(|&(_, period)| period > 0) {
+            let cpus = quota.div_ceil(period) as usize;
+            tightest = Some(tightest.map_or(cpus, |tightest| tightest.min(cpus)));
+        }
+        if dir == mount || !dir.pop() {
+            return tightest;
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::checkpoint;
+use crate::report::Status;
+use crate::search::Stage;
+
+/// One line of the `--dedup-file`: archive contents that were attacked to the end with a plan.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Record {
+    pub sha256: String,
+    pub plan: String,
+    pub archive: PathBuf,
+    pub status: Status,
+    pub recorded_at: u64,
+}
+
+impl Record {
+    pub fn new(sha256: String, plan: String, archive: &Path, status: Status) -> Self {
+        Self {
+            sha256,
+            plan,
+            archive: archive.to_path_buf(),
+            status,
+            recorded_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |since| since.as_secs()),
+        }
+    }
+}
+
+/// Only a recovery or an exhausted keyspace is worth remembering; anything else may go
+/// differently next time.
+pub fn is_conclusive(status: Status) -> bool {
+    matches!(status, Status::Found | Status::NotFound)
+}
+
+/// Identifies the attack: the target entry and every stage as it is numbered.
+pub fn plan(target: Option<&str>, stages: &[Stage]) -> String {
+    let key = serde_json::to_vec(&(target, checkpoint::stage_keys(stages)))
+        .expect("stage keys serialize");
+    Sha256::digest(&key)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Every record so far; a file that does not exist yet holds none.
+pub fn load(path: &Path) -> Result<Vec<Record>> {
+    let raw = match fs::read_to_string(path) {
+        Ok(raw) => raw,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(error) => {
+            return Err(error)
+                .with_context(|| format!("Failed to read dedup file: {}", path.display()));
+        }
+    };
+    raw.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(number, line)| {
+            serde_json::from_str(line).with_context(|| {
+                format!(
+                    "Invalid record on line {} of {}",
+                    number + 1,
+                    path.display()
+                )
+            })
+        })
+        .collect()
+}
+
+/// Appends one record as a single JSON line, like the potfile.
+pub fn append(path: &Path, record: &Record) -> Result<()> {
+    let mut line = serde_json::to_vec(record)?;
+    line.push(b'\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(&line))
+        .with_context(|| format!("Failed to append to dedup file: {}", path.display()))
+}
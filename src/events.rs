@@ -0,0 +1,224 @@
+use std::{
+    fs::OpenOptions,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{
+        Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result, bail};
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::report::Status;
+use crate::search::Provenance;
+use crate::service::Journal;
+
+/// How often a running stage reports progress.
+pub const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// One JSON object per line.
+    Jsonl,
+    /// One systemd journal entry per event, its fields as `UNZIPPOO_*` journal fields.
+    Journal,
+}
+
+/// Something an orchestrator may want to react to as it happens.
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event<'a> {
+    StageStarted {
+        archive: &'a Path,
+        stage: usize,
+        stages: usize,
+        source: &'a str,
+        keyspace: u64,
+    },
+    Progress {
+        archive: &'a Path,
+        stage: usize,
+        tried: u64,
+        rate: f64,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        eta_seconds: Option<f64>,
+        /// With `--counts`, the share of the wordlist's total count tried so far.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        coverage: Option<f64>,
+    },
+    CandidateFound {
+        archive: &'a Path,
+        entry: &'a str,
+        password: &'a str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        provenance: Option<&'a Provenance>,
+    },
+    Error {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        archive: Option<&'a Path>,
+        message: String,
+    },
+    Finished {
+        archive: &'a Path,
+        status: Status,
+        tried: u64,
+        elapsed_seconds: f64,
+    },
+}
+
+#[derive(Serialize)]
+struct Line<'a> {
+    #[serde(flatten)]
+    event: &'a Event<'a>,
+    time: f64,
+}
+
+enum Sink {
+    Lines(Mutex<Box<dyn Write + Send>>),
+    Journal(Journal),
+}
+
+/// The `--events` stream, shared by every archive of a run.
+pub struct Events {
+    sink: Sink,
+    destination: Option<PathBuf>,
+    warned: AtomicBool,
+}
+
+impl Events {
+    /// Writes to `path` (a file or named pipe, which blocks until a reader opens it), or stdout;
+    /// the journal has a socket of its own.
+    pub fn open(format: Format, path: Option<&Path>) -> Result<Self> {
+        let sink = match (format, path) {
+            (Format::Journal, Some(_)) => bail!("--events journal cannot be sent elsewhere"),
+            (Format::Journal, None) => Sink::Journal(Journal::open()?),
+            (Format::Jsonl, Some(path)) => Sink::Lines(Mutex::new(Box::new(
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .with_context(|| format!("Failed to open event stream: {}", path.display()))?,
+            ))),
+            (Format::Jsonl, None) => Sink::Lines(Mutex::new(Box::new(io::stdout()))),
+        };
+        Ok(Self {
+            destination: match sink {
+                Sink::Journal(_) => Some(PathBuf::from("the journal")),
+                Sink::Lines(_) => path.map(Path::to_path_buf),
+            },
+            sink,
+            warned: AtomicBool::new(false),
+        })
+    }
+
+    /// Writes one event and flushes it; a consumer that went away is reported once, not fatal.
+    pub fn emit(&self, event: &Event) {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0.0, |since| since.as_secs_f64());
+        let line = Line { event, time };
+        let written = match &self.sink {
+            Sink::Lines(out) => {
+                serde_json::to_vec(&line)
+                    .map_err(io::Error::from)
+                    .and_then(|mut line| {
+                        line.push(b'\n');
+                        let mut out = out.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                        out.write_all(&line)?;
+                        out.flush()
+                    })
+            }
+            Sink::Journal(journal) => {
+                journal.send(&event.summary(), event.priority(), &journal_fields(&line))
+            }
+        };
+        if let Err(error) = written
+            && !self.warned.swap(true, Ordering::Relaxed)
+        {
+            let destination = self
+                .destination
+                .as_ref()
+                .map_or("stdout".to_owned(), |path| path.display().to_string());
+            eprintln!("Failed to write event stream to {destination}: {error}");
+        }
+    }
+}
+
+impl Event<'_> {
+    /// The journal's `MESSAGE`, for reading without the fields.
+    fn summary(&self) -> String {
+        match self {
+            Event::StageStarted {
+                archive,
+                stage,
+                stages,
+                source,
+                ..
+            } => format!(
+                "{}: stage {stage}/{stages} started: {source}",
+                archive.display()
+            ),
+            Event::Progress {
+                archive,
+                stage,
+                tried,
+                rate,
+                ..
+            } => format!(
+                "{}: stage {stage}, {tried} candidates tried, {rate:.0}/s",
+                archive.display()
+            ),
+            Event::CandidateFound { archive, entry, .. } => {
+                format!("{}: password found for {entry}", archive.display())
+            }
+            Event::Error {
+                archive: Some(archive),
+                message,
+            } => format!("{}: {message}", archive.display()),
+            Event::Error {
+                archive: None,
+                message,
+            } => message.clone(),
+            Event::Finished {
+                archive,
+                status,
+                tried,
+                ..
+            } => format!(
+                "{}: finished ({}) after {tried} candidates",
+                archive.display(),
+                status.as_str()
+            ),
+        }
+    }
+
+    fn priority(&self) -> u8 {
+        match self {
+            Event::Error { .. } => 3,
+            Event::CandidateFound { .. } => 5,
+            _ => 6,
+        }
+    }
+}
+
+/// Every field of the JSON line as `UNZIPPOO_NAME`; nested values stay JSON.
+fn journal_fields(line: &Line) -> Vec<(String, String)> {
+    let Ok(Value::Object(fields)) = serde_json::to_value(line) else {
+        return Vec::new();
+    };
+    fields
+        .into_iter()
+        .map(|(name, value)| {
+            let value = match value {
+                Value::String(text) => text,
+                other => other.to_string(),
+            };
+            (format!("UNZIPPOO_{}", name.to_ascii_uppercase()), value)
+        })
+        .collect()
+}
@@ -0,0 +1,470 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Cursor, Read, Seek, Write},
+    path::{Component, Path, PathBuf},
+};
+
+use anyhow::{Context, Result, bail};
+use clap::ValueEnum;
+use zip::{ZipArchive, result::ZipError};
+
+use crate::archive::{self, Encryption, Entry};
+use crate::{paths, spool};
+
+/// What to do when an entry's destination already exists.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum DuplicatePolicy {
+    Skip,
+    Overwrite,
+    Rename,
+    Error,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct ExtractOptions {
+    /// Ceiling on decompressed bytes written across all entries.
+    pub max_size: Option<u64>,
+    pub on_duplicate: DuplicatePolicy,
+}
+
+enum Outcome {
+    Extracted(PathBuf),
+    Skipped(String),
+    Failed(String),
+    /// The destination exists and the policy is to stop.
+    Conflict,
+}
+
+/// Extracts every entry under `dest`, refusing anything that would land outside it.
+pub fn extract_all(
+    archive_path: &Path,
+    entries: &[Entry],
+    password: &str,
+    dest: &Path,
+    options: ExtractOptions,
+) -> Result<()> {
+    fs::create_dir_all(paths::extended(dest))
+        .with_context(|| format!("Failed to create extraction directory: {}", dest.display()))?;
+    let root = paths::extended(dest).canonicalize()?;
+    let mut input = spool::open_archive(archive_path)?;
+
+    println!();
+    println!("Extracting to {}:", root.display());
+    let mut budget = options.max_size;
+    let mut extracted = 0;
+    for entry in entries {
+        if budget == Some(0) {
+            println!("  {}: skipped (extraction size limit reached)", entry.name);
+            continue;
+        }
+        let outcome = extract_entry(&mut input, entry, password, &root, options, &mut budget);
+        match outcome {
+            Outcome::Extracted(path) => {
+                extracted += 1;
+                let shown = path.strip_prefix(&root).unwrap_or(&path);
+                if shown == Path::new(&entry.name) {
+                    println!("  {}: extracted", entry.name);
+                } else {
+                    println!("  {}: extracted as {}", entry.name, shown.display());
+                }
+            }
+            Outcome::Skipped(reason) => println!("  {}: skipped ({reason})", entry.name),
+            Outcome::Failed(reason) => println!("  {}: failed ({reason})", entry.name),
+            Outcome::Conflict => {
+                println!("  {}: failed (destination already exists)", entry.name);
+                bail!("Destination for \"{}\" already exists", entry.name);
+            }
+        }
+    }
+    println!("Extracted {extracted} of {} entries", entries.len());
+    Ok(())
+}
+
+fn extract_entry(
+    input: &mut File,
+    entry: &Entry,
+    password: &str,
+    root: &Path,
+    options: ExtractOptions,
+    budget: &mut Option<u64>,
+) -> Outcome {
+    let relative = match sanitize_name(&entry.name) {
+        Ok(relative) => relative,
+        Err(reason) => return Outcome::Skipped(reason),
+    };
+    let mut dest = root.join(&relative);
+
+    if entry.is_dir() {
+        return match create_parent_dirs(root, &dest).and_then(|()| fs::create_dir_all(&dest)) {
+            Ok(()) => Outcome::Extracted(dest),
+            Err(error) => Outcome::Failed(error.to_string()),
+        };
+    }
+    if let Err(error) = create_parent_dirs(root, &dest) {
+        return Outcome::Failed(error.to_string());
+    }
+
+    if fs::symlink_metadata(&dest).is_ok() {
+        match options.on_duplicate {
+            DuplicatePolicy::Skip => return Outcome::Skipped("already exists".to_owned()),
+            DuplicatePolicy::Error => return Outcome::Conflict,
+            DuplicatePolicy::Rename => dest = next_free_name(&dest),
+            // Remove rather than truncate so an existing symlink is never written through.
+            DuplicatePolicy::Overwrite => {
+                if let Err(error) = fs::remove_file(&dest) {
+                    return Outcome::Failed(format!("cannot replace existing file: {error}"));
+                }
+            }
+        }
+    }
+
+    let entry_archive = match archive::extract_entry_archive(input, entry) {
+        Ok(bytes) => bytes,
+        Err(error) => return Outcome::Failed(format!("{error:#}")),
+    };
+    let mut zip = match ZipArchive::new(Cursor::new(entry_archive)) {
+        Ok(zip) => zip,
+        Err(error) => return Outcome::Failed(error.to_string()),
+    };
+    let opened = if entry.encryption == Encryption::None {
+        zip.by_index(0)
+    } else {
+        zip.by_index_decrypt(0, password.as_bytes())
+    };
+    let mut reader = match opened {
+        Ok(reader) => reader,
+        Err(ZipError::InvalidPassword) => {
+            return Outcome::Failed("encrypted with a different password".to_owned());
+        }
+        Err(error) => return Outcome::Failed(error.to_string()),
+    };
+
+    if entry.is_symlink() {
+        let mut link = Vec::new();
+        if let Err(error) = reader.by_ref().take(4096).read_to_end(&mut link) {
+            return Outcome::Failed(error.to_string());
+        }
+        let link = PathBuf::from(String::from_utf8_lossy(&link).into_owned());
+        return create_symlink(root, &dest, &link);
+    }
+
+    let result = (|| -> io::Result<u64> {
+        let mut output = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&dest)?;
+        let limit = budget.unwrap_or(u64::MAX);
+        // Trust the bytes actually produced, not the sizes claimed in the headers.
+        let written = io::copy(
+            &mut reader.by_ref().take(limit.saturating_add(1)),
+            &mut output,
+        )?;
+        if written > limit {
+            return Err(io::Error::new(
+                io::ErrorKind::FileTooLarge,
+                "exceeds --max-extract-size",
+            ));
+        }
+        output.flush()?;
+        #[cfg(unix)]
+        if let Some(mode) = entry.unix_mode {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&dest, fs::Permissions::from_mode(mode & 0o777))?;
+        }
+        Ok(written)
+    })();
+
+    match result {
+        Ok(written) => {
+            if let Some(remaining) = budget {
+                *remaining -= written;
+            }
+            Outcome::Extracted(dest)
+        }
+        Err(error) => {
+            let _ = fs::remove_file(&dest);
+            if error.kind() == io::ErrorKind::FileTooLarge {
+                *budget = Some(0);
+            }
+            Outcome::Failed(error.to_string())
+        }
+    }
+}
+
+/// The entries whose names match any of `patterns`, where `*` matches any run of characters
+/// (including `/`) and `?` any one character.
+pub fn select(entries: &[Entry], patterns: &[String]) -> Result<Vec<Entry>> {
+    let selected: Vec<Entry> = entries
+        .iter()
+        .filter(|entry| {
+            patterns
+                .iter()
+                .any(|pattern| wildcard_match(pattern, &entry.name))
+        })
+        .cloned()
+        .collect();
+    if selected.is_empty() {
+        bail!("No entries match --extract-entry {}", patterns.join(", "));
+    }
+    Ok(selected)
+}
+
+pub fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    // Greedy with backtracking to the most recent `*`.
+    let (mut p, mut n) = (0, 0);
+    let mut star = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    p = star_p + 1;
+                    n = star_n + 1;
+                    star = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Streams the entry named `name` to stdout. A reader that stops early, like `head`, is not
+/// an error.
+pub fn cat(archive_path: &Path, entries: &[Entry], name: &str, password: &str) -> Result<()> {
+    let entry = entries
+        .iter()
+        .find(|entry| !entry.is_dir() && entry.name == name)
+        .ok_or_else(|| anyhow::anyhow!("No entry named \"{name}\" in the archive"))?;
+    let mut input = spool::open_archive(archive_path)?;
+    let mut stdout = io::stdout().lock();
+    let result = read_entry(&mut input, entry, password, &mut stdout, u64::MAX)
+        .and_then(|_| Ok(stdout.flush()?));
+    match result {
+        Err(error)
+            if error
+                .downcast_ref::<io::Error>()
+                .is_some_and(|error| error.kind() == io::ErrorKind::BrokenPipe) =>
+        {
+            Ok(())
+        }
+        result => result,
+    }
+}
+
+/// Decrypts and decompresses `entry` into `output`, stopping after `limit` bytes.
+pub fn read_entry<R: Read + Seek>(
+    input: &mut R,
+    entry: &Entry,
+    password: &str,
+    output: &mut dyn Write,
+    limit: u64,
+) -> Result<u64> {
+    let entry_archive = archive::extract_entry_archive(input, entry)?;
+    let mut zip = ZipArchive::new(Cursor::new(entry_archive))?;
+    let opened = if entry.encryption == Encryption::None {
+        zip.by_index(0)
+    } else {
+        zip.by_index_decrypt(0, password.as_bytes())
+    };
+    let mut reader = match opened {
+        Ok(reader) => reader,
+        Err(ZipError::InvalidPassword) => {
+            bail!("\"{}\" is encrypted with a different password", entry.name)
+        }
+        Err(error) => return Err(error.into()),
+    };
+    Ok(io::copy(&mut reader.by_ref().take(limit), output)?)
+}
+
+/// Turns an entry name into a relative path, rejecting absolute paths and `..` traversal.
+fn sanitize_name(name: &str) -> Result<PathBuf, String> {
+    let name = name.replace('\\', "/");
+    let bytes = name.as_bytes();
+    if name.starts_with('/') || (bytes.len() >= 2 && bytes[1] == b':') {
+        return Err("absolute path".to_owned());
+    }
+    if name.contains('\0') {
+        return Err("NUL byte in name".to_owned());
+    }
+
+    let mut relative = PathBuf::new();
+    for part in name.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => return Err("path traversal".to_owned()),
+            part => relative.push(part),
+        }
+    }
+    if relative.as_os_str().is_empty() {
+        return Err("empty name".to_owned());
+    }
+    Ok(relative)
+}
+
+/// Creates the parent directories of `dest`, then confirms no symlink planted by an earlier
+/// entry redirected them outside `root`.
+fn create_parent_dirs(root: &Path, dest: &Path) -> io::Result<()> {
+    let Some(parent) = dest.parent() else {
+        return Ok(());
+    };
+    fs::create_dir_all(parent)?;
+    if parent.canonicalize()?.starts_with(root) {
+        Ok(())
+    } else {
+        Err(io::Error::other("parent directory escapes destination"))
+    }
+}
+
+/// Whether `link`, read from `dest`'s directory, lands inside `root` on disk: the deepest part
+/// that exists is canonicalized, so symlinks extracted earlier are followed, and the rest may not
+/// climb with `..` through directories a later entry could still make a symlink.
+fn resolves_inside(root: &Path, dest: &Path, link: &Path) -> bool {
+    let Some(parent) = dest.parent() else {
+        return false;
+    };
+    let target = parent.join(link);
+    let components: Vec<Component> = target.components().collect();
+    for existing in (1..=components.len()).rev() {
+        let Ok(base) = components[..existing]
+            .iter()
+            .collect::<PathBuf>()
+            .canonicalize()
+        else {
+            continue;
+        };
+        let rest = &components[existing..];
+        return rest
+            .iter()
+            .all(|component| matches!(component, Component::Normal(_)))
+            && base
+                .join(rest.iter().collect::<PathBuf>())
+                .starts_with(root);
+    }
+    false
+}
+
+fn next_free_name(dest: &Path) -> PathBuf {
+    let stem = dest
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let extension = dest
+        .extension()
+        .map(|extension| format!(".{}", extension.to_string_lossy()))
+        .unwrap_or_default();
+    (1..)
+        .map(|n| dest.with_file_name(format!("{stem} ({n}){extension}")))
+        .find(|candidate| fs::symlink_metadata(candidate).is_err())
+        .expect("unbounded search always finds a free name")
+}
+
+fn create_symlink(root: &Path, dest: &Path, link: &Path) -> Outcome {
+    if link.is_absolute() {
+        return Outcome::Skipped("symlink to absolute path".to_owned());
+    }
+    if !resolves_inside(root, dest, link) {
+        return Outcome::Skipped("symlink escapes destination".to_owned());
+    }
+
+    #[cfg(unix)]
+    {
+        match std::os::unix::fs::symlink(link, dest) {
+            Ok(()) => Outcome::Extracted(dest.to_path_buf()),
+            Err(error) => Outcome::Failed(error.to_string()),
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = dest;
+        Outcome::Skipped("symlinks are not supported on this platform".to_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitize_name_keeps_names_inside_the_destination() {
+        assert_eq!(
+            sanitize_name("a/./b//c.txt"),
+            Ok(PathBuf::from("a/b/c.txt"))
+        );
+        assert_eq!(sanitize_name("a\\b.txt"), Ok(PathBuf::from("a/b.txt")));
+        for (name, reason) in [
+            ("/etc/passwd", "absolute path"),
+            ("\\windows\\system32", "absolute path"),
+            ("C:evil.txt", "absolute path"),
+            ("c:/evil.txt", "absolute path"),
+            ("../evil.txt", "path traversal"),
+            ("a/../../evil.txt", "path traversal"),
+            ("a\\..\\evil.txt", "path traversal"),
+            ("a\0b", "NUL byte in name"),
+            ("./", "empty name"),
+        ] {
+            assert_eq!(sanitize_name(name), Err(reason.to_owned()), "{name}");
+        }
+    }
+
+    #[test]
+    fn wildcard_match_spans_directories() {
+        assert!(wildcard_match("*.txt", "docs/readme.txt"));
+        assert!(wildcard_match("docs/*", "docs/a/b"));
+        assert!(wildcard_match("?.zip", "a.zip"));
+        assert!(wildcard_match("*a*b*", "xaybz"));
+        assert!(wildcard_match("*", ""));
+        assert!(!wildcard_match("?.zip", "ab.zip"));
+        assert!(!wildcard_match("*.txt", "readme.txt.gz"));
+        assert!(!wildcard_match("docs/*", "other/docs/a"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn create_symlink_follows_links_already_extracted() {
+        let dir = std::env::temp_dir().join(format!("unzippoo-symlinks-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        let root = dir.canonicalize().unwrap();
+        let extracted = |outcome: Outcome| matches!(outcome, Outcome::Extracted(_));
+        let skipped = |outcome: Outcome, reason: &str| matches!(outcome, Outcome::Skipped(actual) if actual == reason);
+
+        assert!(extracted(create_symlink(
+            &root,
+            &root.join("sub/b"),
+            Path::new("..")
+        )));
+        assert!(extracted(create_symlink(
+            &root,
+            &root.join("ok"),
+            Path::new("sub/b/sub")
+        )));
+        assert!(extracted(create_symlink(
+            &root,
+            &root.join("later"),
+            Path::new("x/y")
+        )));
+        for (name, link, reason) in [
+            ("abs", "/etc", "symlink to absolute path"),
+            ("up", "../outside", "symlink escapes destination"),
+            ("sub/up", "../..", "symlink escapes destination"),
+            ("chained", "sub/b/..", "symlink escapes destination"),
+            ("twice", "ok/../..", "symlink escapes destination"),
+            ("pending", "x/..", "symlink escapes destination"),
+        ] {
+            let outcome = create_symlink(&root, &root.join(name), Path::new(link));
+            assert!(skipped(outcome, reason), "{name} -> {link}");
+            assert!(fs::symlink_metadata(root.join(name)).is_err(), "{name}");
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,260 @@
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{self, Read},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{config, paths, report};
+
+/// Lists `unzippoo fetch` knows by name.
+const CATALOG: &[(&str, &str)] = &[
+    (
+        "rockyou",
+        "https://github.com/brannondorsey/naive-hashcat/releases/download/data/rockyou.txt",
+    ),
+    (
+        "seclists-top1m",
+        "https://raw.githubusercontent.com/danielmiessler/SecLists/master/Passwords/Common-Credentials/10-million-password-list-top-1000000.txt",
+    ),
+];
+
+const MANIFEST: &str = "manifest.json";
+
+#[derive(clap::Args, Debug)]
+pub struct FetchArgs {
+    /// Name to store the list under and refer to it by as `@NAME`.
+    name: String,
+
+    /// Download from this URL instead of the built-in catalog entry for NAME.
+    #[arg(long)]
+    url: Option<String>,
+
+    /// Published SHA-256 of the list; the download is rejected if it doesn't match.
+    #[arg(long, value_name = "HEX")]
+    sha256: Option<String>,
+
+    /// Download again even if the list is already cached.
+    #[arg(long)]
+    force: bool,
+}
+
+/// What the cache remembers about one downloaded list.
+#[derive(Debug, Serialize, Deserialize)]
+struct Cached {
+    url: String,
+    file: String,
+    sha256: String,
+}
+
+pub fn run(args: &FetchArgs) -> Result<()> {
+    if args.name.is_empty() || args.name.contains(['/', '\\']) || args.name.starts_with('.') {
+        bail!("Invalid wordlist name \"{}\"", args.name);
+    }
+    let config = config::load()?;
+    let configured = config
+        .wordlists
+        .get(&args.name)
+        .filter(|target| config::is_url(target));
+    let url = match (&args.url, configured) {
+        (Some(url), _) | (None, Some(url)) => url.as_str(),
+        (None, None) => CATALOG
+            .iter()
+            .find(|(name, _)| *name == args.name)
+            .map(|(_, url)| *url)
+            .with_context(|| {
+                let names: Vec<&str> = CATALOG.iter().map(|(name, _)| *name).collect();
+                format!(
+                    "Unknown wordlist \"{}\"; pass --url, add it to the config file, or use \
+                     one of: {}",
+                    args.name,
+                    names.join(", ")
+                )
+            })?,
+    };
+    let (path, downloaded) = fetch(&args.name, url, args.sha256.as_deref(), args.force)?;
+    if !downloaded {
+        println!(
+            "@{} is already cached at {} (use --force to download again)",
+            args.name,
+            path.display()
+        );
+    }
+    Ok(())
+}
+
+/// Resolves `@NAME` through the config file's aliases, then the cache; other paths are
+/// returned unchanged. An alias pointing at a URL is fetched the first time it is used.
+pub fn resolve_wordlist(path: &Path) -> Result<PathBuf> {
+    let Some(name) = path.to_str().and_then(|path| path.strip_prefix('@')) else {
+        return Ok(path.to_path_buf());
+    };
+    let config = config::load()?;
+    match config.wordlists.get(name) {
+        Some(url) if config::is_url(url) => Ok(fetch(name, url, None, false)?.0),
+        Some(target) => Ok(config.alias_path(target)),
+        None => {
+            let dir = cache_dir()?;
+            let manifest = load_manifest(&dir)?;
+            let cached = manifest.get(name).with_context(|| {
+                format!(
+                    "Wordlist @{name} is neither configured nor cached; run `unzippoo fetch {name}`"
+                )
+            })?;
+            Ok(dir.join(&cached.file))
+        }
+    }
+}
+
+/// Every wordlist available without a download: the config file's local aliases and the lists
+/// already in the cache.
+pub fn local_wordlists() -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    if let Ok(config) = config::load() {
+        paths.extend(
+            config
+                .wordlists
+                .values()
+                .filter(|target| !config::is_url(target))
+                .map(|target| config.alias_path(target)),
+        );
+    }
+    if let Ok(dir) = cache_dir()
+        && let Ok(manifest) = load_manifest(&dir)
+    {
+        paths.extend(manifest.values().map(|cached| dir.join(&cached.file)));
+    }
+    paths
+}
+
+/// Downloads `url` into the cache as `@name` unless that URL is cached already, returning the
+/// cached path and whether it was downloaded now.
+fn fetch(name: &str, url: &str, expected: Option<&str>, force: bool) -> Result<(PathBuf, bool)> {
+    let dir = cache_dir()?;
+    let mut manifest = load_manifest(&dir)?;
+    if let Some(cached) = manifest.get(name)
+        && !force
+        && cached.url == url
+        && dir.join(&cached.file).exists()
+    {
+        return Ok((dir.join(&cached.file), false));
+    }
+
+    fs::create_dir_all(&dir)
+        .with_context(|| format!("Failed to create cache directory: {}", dir.display()))?;
+    let file = format!("{name}.list");
+    let dest = dir.join(&file);
+    let temp = dir.join(format!(".{file}.{}.part", std::process::id()));
+    println!("Downloading {url}");
+    let downloaded = download(url, &temp).and_then(|()| {
+        let sha256 = sha256_file(&temp)?;
+        if let Some(expected) = expected
+            && !expected.eq_ignore_ascii_case(&sha256)
+        {
+            bail!("Checksum mismatch for {url}: expected {expected}, got {sha256}");
+        }
+        fs::rename(&temp, &dest)?;
+        Ok(sha256)
+    });
+    let sha256 = match downloaded {
+        Ok(sha256) => sha256,
+        Err(error) => {
+            let _ = fs::remove_file(&temp);
+            return Err(error);
+        }
+    };
+
+    if expected.is_none() {
+        eprintln!("No published SHA-256 was given, so the download of {url} could not be checked");
+    }
+    println!("Saved @{name} to {} (sha256 {sha256})", dest.display());
+    manifest.insert(
+        name.to_owned(),
+        Cached {
+            url: url.to_owned(),
+            file,
+            sha256,
+        },
+    );
+    report::write_json_atomically(&dir.join(MANIFEST), &manifest)?;
+    Ok((dest, true))
+}
+
+/// `$UNZIPPOO_CACHE_DIR`, or the platform's per-user cache directory.
+fn cache_dir() -> Result<PathBuf> {
+    let env = |name| std::env::var_os(name).filter(|value| !value.is_empty());
+    if let Some(dir) = env("UNZIPPOO_CACHE_DIR") {
+        return Ok(PathBuf::from(dir));
+    }
+    let base = if cfg!(windows) {
+        env("LOCALAPPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        env("HOME").map(|home| PathBuf::from(home).join("Library/Caches"))
+    } else {
+        env("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env("HOME").map(|home| PathBuf::from(home).join(".cache")))
+    };
+    base.map(|base| base.join("unzippoo").join("wordlists"))
+        .context("Cannot locate a cache directory; set UNZIPPOO_CACHE_DIR")
+}
+
+fn load_manifest(dir: &Path) -> Result<BTreeMap<String, Cached>> {
+    let path = dir.join(MANIFEST);
+    match fs::read(&path) {
+        Ok(bytes) => serde_json::from_slice(&bytes)
+            .with_context(|| format!("Corrupt wordlist cache manifest: {}", path.display())),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+        Err(error) => Err(error)
+            .with_context(|| format!("Failed to read wordlist cache manifest: {}", path.display())),
+    }
+}
+
+/// Downloads with whichever of curl or wget is installed.
+fn download(url: &str, dest: &Path) -> Result<()> {
+    let helpers: [(&str, &[&str]); 2] = [
+        (
+            "curl",
+            &["--fail", "--location", "--retry", "3", "--output"],
+        ),
+        ("wget", &["--tries=3", "--output-document"]),
+    ];
+    for (program, helper_args) in helpers {
+        let status = Command::new(program)
+            .args(helper_args)
+            .arg(dest)
+            .arg(url)
+            .stdin(Stdio::null())
+            .status();
+        match status {
+            Ok(status) if status.success() => return Ok(()),
+            Ok(status) => bail!("{program} failed to download {url} ({status})"),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => continue,
+            Err(error) => bail!("{program} failed: {error}"),
+        }
+    }
+    bail!("Downloading needs curl or wget on the PATH")
+}
+
+pub fn sha256_file(path: &Path) -> Result<String> {
+    let mut file = File::open(paths::extended(path))?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 1 << 16];
+    loop {
+        let read = file.read(&mut buf)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buf[..read]);
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect())
+}
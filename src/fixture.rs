@@ -0,0 +1,218 @@
+use std::{
+    fs::OpenOptions,
+    io::{self, Cursor, Write},
+    path::PathBuf,
+};
+
+use anyhow::{Context, Result, bail};
+use clap::ValueEnum;
+use sha2::{Digest, Sha256};
+use zip::{
+    AesMode, AesSalt, CompressionMethod, DateTime, ZipWriter, unstable::write::FileOptionsExt,
+    write::SimpleFileOptions,
+};
+
+use crate::archive::Encryption;
+
+/// How a test archive's entries are encrypted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Scheme {
+    Zipcrypto,
+    Aes128,
+    Aes192,
+    Aes256,
+}
+
+impl Scheme {
+    pub const ALL: [Scheme; 4] = [
+        Scheme::Zipcrypto,
+        Scheme::Aes128,
+        Scheme::Aes192,
+        Scheme::Aes256,
+    ];
+
+    /// How the central directory reader should see it.
+    pub fn encryption(self) -> Encryption {
+        match self {
+            Scheme::Zipcrypto => Encryption::ZipCrypto,
+            Scheme::Aes128 => Encryption::Aes { strength: 1 },
+            Scheme::Aes192 => Encryption::Aes { strength: 2 },
+            Scheme::Aes256 => Encryption::Aes { strength: 3 },
+        }
+    }
+}
+
+/// How a test archive's entries are compressed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Method {
+    Stored,
+    Deflate,
+}
+
+impl Method {
+    pub const ALL: [Method; 2] = [Method::Stored, Method::Deflate];
+}
+
+#[derive(clap::Args, Debug)]
+pub struct MakeTestArchiveArgs {
+    /// Where to write the archive.
+    #[arg(short, long, value_name = "FILE", default_value = "test.zip")]
+    output: PathBuf,
+
+    /// Encryption of every entry.
+    #[arg(long, value_enum, default_value_t = Scheme::Aes256)]
+    scheme: Scheme,
+
+    /// Password every entry is encrypted with.
+    #[arg(long)]
+    password: String,
+
+    /// Number of text entries, named entry1.txt, entry2.txt, and so on.
+    #[arg(long, default_value_t = 1)]
+    entries: usize,
+
+    /// Compression of every entry.
+    #[arg(long, value_enum, default_value_t = Method::Deflate)]
+    method: Method,
+
+    /// Replace FILE if it already exists.
+    #[arg(long)]
+    force: bool,
+}
+
+pub fn run(args: &MakeTestArchiveArgs) -> Result<()> {
+    let bytes = build(args.scheme, args.method, &args.password, args.entries)?;
+    let mut file = match OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .create_new(!args.force)
+        .open(&args.output)
+    {
+        Ok(file) => file,
+        Err(error) if error.kind() == io::ErrorKind::AlreadyExists => bail!(
+            "{} already exists; pass --force to replace it",
+            args.output.display()
+        ),
+        Err(error) => {
+            return Err(error)
+                .with_context(|| format!("Failed to create {}", args.output.display()));
+        }
+    };
+    file.write_all(&bytes)
+        .with_context(|| format!("Failed to write {}", args.output.display()))?;
+    println!(
+        "Wrote {}: {} {} entr{}, {} bytes",
+        args.output.display(),
+        args.entries,
+        args.scheme.encryption(),
+        if args.entries == 1 { "y" } else { "ies" },
+        bytes.len()
+    );
+    Ok(())
+}
+
+pub fn entry_name(number: usize) -> String {
+    format!("entry{number}.txt")
+}
+
+/// Text that deflate can shrink, different for every entry.
+pub fn entry_content(number: usize) -> Vec<u8> {
+    (0..64)
+        .map(|line| format!("unzippoo test entry {number}, line {line}\n"))
+        .collect::<String>()
+        .into_bytes()
+}
+
+/// An archive of `entries` text files encrypted with `password`. The same arguments always
+/// give the same bytes: timestamps are fixed and AES salts are derived from the password.
+pub fn build(scheme: Scheme, method: Method, password: &str, entries: usize) -> Result<Vec<u8>> {
+    if password.is_empty() {
+        bail!("Test archives need a non-empty password");
+    }
+    if entries == 0 {
+        bail!("Test archives need at least one entry");
+    }
+    let modified = DateTime::from_date_and_time(2020, 1, 1, 0, 0, 0)
+        .map_err(|error| anyhow::anyhow!("Invalid fixture timestamp: {error}"))?;
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    for number in 1..=entries {
+        let name = entry_name(number);
+        let options = SimpleFileOptions::default()
+            .compression_method(match method {
+                Method::Stored => CompressionMethod::Stored,
+                Method::Deflate => CompressionMethod::Deflated,
+            })
+            .last_modified_time(modified);
+        let options = match scheme {
+            Scheme::Zipcrypto => options.with_deprecated_encryption(password.as_bytes())?,
+            Scheme::Aes128 | Scheme::Aes192 | Scheme::Aes256 => {
+                let (mode, salt_len) = match scheme {
+                    Scheme::Aes128 => (AesMode::Aes128, 8),
+                    Scheme::Aes192 => (AesMode::Aes192, 12),
+                    _ => (AesMode::Aes256, 16),
+                };
+                let salt = Sha256::new()
+                    .chain_update(b"unzippoo fixture salt")
+                    .chain_update(password)
+                    .chain_update(&name)
+                    .finalize();
+                options.with_aes_encryption_and_salt(
+                    password.as_bytes(),
+                    AesSalt::try_new(mode, &salt[..salt_len])?,
+                )
+            }
+        };
+        writer.start_file(name, options)?;
+        writer.write_all(&entry_content(number))?;
+    }
+    Ok(writer.finish()?.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use zip::ZipArchive;
+
+    use super::*;
+    use crate::archive;
+    use crate::verify::{Backend, Verifier};
+
+    #[test]
+    fn every_fixture_opens_with_its_password_only() {
+        for scheme in Scheme::ALL {
+            for method in Method::ALL {
+                let label = format!("{scheme:?} {method:?}");
+                let bytes = build(scheme, method, "hunter2", 2).unwrap();
+                assert_eq!(
+                    bytes,
+                    build(scheme, method, "hunter2", 2).unwrap(),
+                    "{label}"
+                );
+
+                let mut reader = Cursor::new(&bytes);
+                let entries = archive::read_central_directory(&mut reader)
+                    .unwrap()
+                    .entries;
+                assert_eq!(entries.len(), 2, "{label}");
+                for (number, entry) in (1..).zip(&entries) {
+                    assert_eq!(entry.name, entry_name(number), "{label}");
+                    assert_eq!(entry.encryption, scheme.encryption(), "{label}");
+                    let entry_archive = archive::extract_entry_archive(&mut reader, entry).unwrap();
+                    let verifier = Verifier::new(entry_archive, entry, Backend::Cpu).unwrap();
+                    assert!(verifier.confirm("hunter2").unwrap(), "{label}");
+                    assert!(!verifier.confirm("hunter3").unwrap(), "{label}");
+                }
+
+                let mut zip = ZipArchive::new(Cursor::new(&bytes)).unwrap();
+                let mut contents = Vec::new();
+                zip.by_index_decrypt(1, b"hunter2")
+                    .unwrap()
+                    .read_to_end(&mut contents)
+                    .unwrap();
+                assert_eq!(contents, entry_content(2), "{label}");
+            }
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use std::collections::HashSet;
+
+use anyhow::{Result, bail};
+
+use crate::generate::Candidates;
+
+/// More variants than this would take gigabytes to hold.
+const MAX_VARIANTS: usize = 20_000_000;
+
+/// US keyboard rows, unshifted and shifted, for shift slips and neighbouring keys.
+const ROWS: [(&str, &str); 4] = [
+    ("`1234567890-=", "~!@#$%^&*()_+"),
+    ("qwertyuiop[]\\", "QWERTYUIOP{}|"),
+    ("asdfghjkl;'", "ASDFGHJKL:\""),
+    ("zxcvbnm,./", "ZXCVBNM<>?"),
+];
+
+/// Every string within `distance` edits of a remembered password, nearest first. An edit is a
+/// substitution, insertion, deletion, swap of adjacent characters, or a shift or caps lock slip.
+pub fn variants(password: &str, distance: u32) -> Result<Candidates> {
+    let mut alphabet: Vec<char> = (' '..='~').collect();
+    for c in password.chars() {
+        if !alphabet.contains(&c) {
+            alphabet.push(c);
+        }
+    }
+
+    let mut seen = HashSet::from([password.to_owned()]);
+    let mut variants = Candidates::default();
+    variants.push(password);
+    let mut level = 0..1;
+    for _ in 0..distance {
+        let start = variants.len();
+        for index in level.clone() {
+            let word: Vec<char> = variants.get(index).chars().collect();
+            edits(&word, &alphabet, |variant| {
+                if !seen.contains(&variant) {
+                    variants.push(&variant);
+                    seen.insert(variant);
+                }
+            });
+            if variants.len() > MAX_VARIANTS {
+                bail!(
+                    "More than {MAX_VARIANTS} candidates are within {distance} edits of the \
+                     remembered password; lower --distance"
+                );
+            }
+        }
+        level = start..variants.len();
+    }
+
+    Ok(variants)
+}
+
+/// Calls `emit` with every single edit of `word`, the typing slips people make most often first.
+fn edits(word: &[char], alphabet: &[char], mut emit: impl FnMut(String)) {
+    let replaced = |at: usize, c: char| {
+        let mut chars = word.to_vec();
+        chars[at] = c;
+        chars.into_iter().collect::<String>()
+    };
+
+    if word.iter().any(|c| c.is_alphabetic()) {
+        emit(word.iter().map(|&c| toggle_case(c)).collect());
+    }
+    for (at, &c) in word.iter().enumerate() {
+        if let Some(shifted) = shifted(c) {
+            emit(replaced(at, shifted));
+        }
+    }
+    for at in 1..word.len() {
+        let mut chars = word.to_vec();
+        chars.swap(at - 1, at);
+        emit(chars.into_iter().collect());
+    }
+    for (at, &c) in word.iter().enumerate() {
+        for neighbour in neighbours(c) {
+            emit(replaced(at, neighbour));
+        }
+    }
+    for at in 0..word.len() {
+        emit(word[..at].iter().chain(&word[at + 1..]).collect());
+    }
+    for at in 0..=word.len() {
+        for &c in alphabet {
+            emit(word[..at].iter().chain([&c]).chain(&word[at..]).collect());
+        }
+    }
+    for (at, &original) in word.iter().enumerate() {
+        for &c in alphabet.iter().filter(|&&c| c != original) {
+            emit(replaced(at, c));
+        }
+    }
+}
+
+/// What caps lock turns `c` into.
+fn toggle_case(c: char) -> char {
+    if c.is_lowercase() {
+        c.to_uppercase().next().unwrap_or(c)
+    } else {
+        c.to_lowercase().next().unwrap_or(c)
+    }
+}
+
+/// The other character on `c`'s key.
+fn shifted(c: char) -> Option<char> {
+    ROWS.iter().find_map(|(lower, upper)| {
+        let position = |row: &str| row.chars().position(|key| key == c);
+        position(lower)
+            .and_then(|at| upper.chars().nth(at))
+            .or_else(|| position(upper).and_then(|at| lower.chars().nth(at)))
+    })
+}
+
+/// The keys left and right of `c` on the same row, with the same shift state.
+fn neighbours(c: char) -> Vec<char> {
+    for (lower, upper) in ROWS {
+        for row in [lower, upper] {
+            let keys: Vec<char> = row.chars().collect();
+            if let Some(at) = keys.iter().position(|&key| key == c) {
+                return [at.checked_sub(1), Some(at + 1)]
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|at| keys.get(at).copied())
+                    .collect();
+            }
+        }
+    }
+    Vec::new()
+}
@@ -0,0 +1,479 @@
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+    hash::{BuildHasher, RandomState},
+    sync::Arc,
+};
+
+use anyhow::{Result, bail};
+use clap::ValueEnum;
+
+use crate::affix::Affixed;
+use crate::phrase::Phrases;
+use crate::wordlist::Words;
+
+const LOWER: &str = "abcdefghijklmnopqrstuvwxyz";
+const UPPER: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const DIGITS: &str = "0123456789";
+const SYMBOLS: &str = " !\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~";
+
+/// A source of candidates addressable by index, so any slice of it can go to any worker.
+pub enum Generator {
+    /// Shared with the stage that affixes the same list.
+    Wordlist(Arc<Words>),
+    Affixed(Affixed),
+    BruteForce(BruteForce),
+    /// Generated up front, such as `--almost` near misses.
+    List(Candidates),
+    Phrases(Phrases),
+}
+
+impl Generator {
+    pub fn len(&self) -> u64 {
+        match self {
+            Generator::Wordlist(words) => words.len() as u64,
+            Generator::Affixed(affixed) => affixed.len(),
+            Generator::BruteForce(brute) => brute.len,
+            Generator::List(candidates) => candidates.len() as u64,
+            Generator::Phrases(phrases) => phrases.len(),
+        }
+    }
+
+    /// Returns candidate `index`, using `buf` as scratch space when it has to be built.
+    pub fn candidate<'a>(&'a self, index: u64, buf: &'a mut String) -> &'a str {
+        match self {
+            Generator::Wordlist(words) => words.get(index as usize),
+            Generator::Affixed(affixed) => {
+                affixed.write(index, buf);
+                buf
+            }
+            Generator::BruteForce(brute) => {
+                brute.write(index, buf);
+                buf
+            }
+            Generator::List(candidates) => candidates.get(index as usize),
+            Generator::Phrases(phrases) => {
+                phrases.write(index, buf);
+                buf
+            }
+        }
+    }
+}
+
+/// Candidates packed back to back in one buffer, in the order they were pushed.
+#[derive(Default)]
+pub struct Candidates {
+    arena: String,
+    ends: Vec<usize>,
+}
+
+impl Candidates {
+    pub fn push(&mut self, candidate: &str) {
+        self.arena.push_str(candidate);
+        self.ends.push(self.arena.len());
+    }
+
+    pub fn len(&self) -> usize {
+        self.ends.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ends.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> &str {
+        let start = index
+            .checked_sub(1)
+            .map_or(0, |previous| self.ends[previous]);
+        &self.arena[start..self.ends[index]]
+    }
+
+    /// Drops the candidates `keep` rejects.
+    pub fn retain(&mut self, mut keep: impl FnMut(&str) -> bool) {
+        let mut kept = Self::default();
+        for index in 0..self.len() {
+            if keep(self.get(index)) {
+                kept.push(self.get(index));
+            }
+        }
+        *self = kept;
+    }
+}
+
+/// `--dedupe`: which candidates count as repeats of one already queued earlier in the run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Dedupe {
+    Off,
+    Exact,
+    /// Strings that differ only in case; this skips passwords that do, too.
+    CaseInsensitive,
+}
+
+/// The candidates kept so far, or those to exclude, as 128-bit fingerprints so a large
+/// wordlist costs 16 bytes a line rather than a copy of itself.
+pub struct Seen {
+    mode: Dedupe,
+    hashers: [RandomState; 2],
+    fingerprints: HashSet<u128>,
+}
+
+impl Seen {
+    pub fn new(mode: Dedupe) -> Self {
+        Self {
+            mode,
+            hashers: [RandomState::new(), RandomState::new()],
+            fingerprints: HashSet::new(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.mode != Dedupe::Off
+    }
+
+    /// Whether `candidate` is new, recording it if so. Always true with `--dedupe off`.
+    pub fn insert(&mut self, candidate: &str) -> bool {
+        match self.fingerprint(candidate) {
+            Some(fingerprint) => self.fingerprints.insert(fingerprint),
+            None => true,
+        }
+    }
+
+    /// Whether `candidate` was recorded, without recording it. Always false with `--dedupe off`.
+    pub fn contains(&self, candidate: &str) -> bool {
+        self.fingerprint(candidate)
+            .is_some_and(|fingerprint| self.fingerprints.contains(&fingerprint))
+    }
+
+    fn fingerprint(&self, candidate: &str) -> Option<u128> {
+        let fingerprint = |key: &str| {
+            let [low, high] = &self.hashers;
+            u128::from(low.hash_one(key)) | u128::from(high.hash_one(key)) << 64
+        };
+        match self.mode {
+            Dedupe::Off => None,
+            Dedupe::Exact => Some(fingerprint(candidate)),
+            Dedupe::CaseInsensitive => Some(fingerprint(&candidate.to_lowercase())),
+        }
+    }
+}
+
+/// Every string over `charset` with a length in `min_len..=max_len`, shortest first, wrapped
+/// in whatever fragments of the password are known.
+pub struct BruteForce {
+    charset: Vec<char>,
+    /// The order `charset` is walked in at each position, once trained by
+    /// [`BruteForce::ordered`].
+    orders: Vec<Vec<char>>,
+    /// Lengths of the brute-forced part, excluding the fragments.
+    min_len: usize,
+    max_len: usize,
+    hints: Hints,
+    len: u64,
+}
+
+impl BruteForce {
+    /// `min_len` and `max_len` are lengths of the whole password, fragments included.
+    pub fn new(charset: Vec<char>, min_len: usize, max_len: usize, hints: Hints) -> Result<Self> {
+        if charset.is_empty() {
+            bail!("Character set is empty");
+        }
+        if min_len == 0 || min_len > max_len {
+            bail!("Invalid length range {min_len}-{max_len}");
+        }
+        let fixed = hints.len();
+        if max_len < fixed {
+            bail!("The known fragments ({fixed} characters) are longer than --length allows");
+        }
+        let (min_len, max_len) = (min_len.saturating_sub(fixed), max_len - fixed);
+        let base = charset.len() as u64;
+        let mut len: u64 = 0;
+        for length in min_len..=max_len {
+            let count = u32::try_from(length)
+                .ok()
+                .and_then(|length| base.checked_pow(length))
+                .and_then(|count| count.checked_mul(hints.slots(length)))
+                .and_then(|count| len.checked_add(count));
+            match count {
+                Some(total) => len = total,
+                None => bail!("Brute-force keyspace is too large; narrow the charset or length"),
+            }
+        }
+        Ok(Self {
+            charset,
+            orders: Vec::new(),
+            min_len,
+            max_len,
+            hints,
+            len,
+        })
+    }
+
+    /// Walks position `i` of the brute-forced part in `orders[i]`, a permutation of the charset,
+    /// instead of charset order. Every candidate is still generated exactly once.
+    pub fn ordered(mut self, orders: Vec<Vec<char>>) -> Self {
+        debug_assert!(orders.len() >= self.max_len);
+        self.orders = orders;
+        self
+    }
+
+    /// Within one length, each position of the `contains` fragment is a block of its own.
+    fn write(&self, mut index: u64, buf: &mut String) {
+        let base = self.charset.len() as u64;
+        let mut length = self.min_len;
+        let mut count = base.pow(length as u32);
+        while index >= count * self.hints.slots(length) && length < self.max_len {
+            index -= count * self.hints.slots(length);
+            length += 1;
+            count *= base;
+        }
+        let slot = index / count;
+        index %= count;
+
+        buf.clear();
+        buf.push_str(&self.hints.prefix);
+        let mut place = count / base;
+        for position in 0..length {
+            if position as u64 == slot {
+                buf.push_str(&self.hints.contains);
+            }
+            let digit = (index / place) as usize;
+            buf.push(match self.orders.get(position) {
+                Some(order) => order[digit],
+                None => self.charset[digit],
+            });
+            index %= place;
+            place = (place / base).max(1);
+        }
+        if slot == length as u64 {
+            buf.push_str(&self.hints.contains);
+        }
+        buf.push_str(&self.hints.suffix);
+    }
+}
+
+/// Orders `charset` for each of `positions` positions by how often `passwords` have each
+/// character there, most common first, so brute force reaches the habits of the environment
+/// they came from early. Positions count from the start of the brute-forced part, so the
+/// `hints` fragments are taken out of each password first. Ties, and positions past the longest
+/// password, fall back to how often the character appears anywhere and then to charset order.
+pub fn trained_orders(
+    charset: &[char],
+    passwords: &[String],
+    hints: &Hints,
+    positions: usize,
+) -> Vec<Vec<char>> {
+    let mut overall: HashMap<char, u64> = HashMap::new();
+    let mut at: Vec<HashMap<char, u64>> = vec![HashMap::new(); positions];
+    for password in passwords {
+        for (position, c) in hints.brute_forced_part(password).chars().enumerate() {
+            *overall.entry(c).or_default() += 1;
+            if let Some(counts) = at.get_mut(position) {
+                *counts.entry(c).or_default() += 1;
+            }
+        }
+    }
+    at.iter()
+        .map(|counts| {
+            let mut order = charset.to_vec();
+            // Stable, so charset order breaks the remaining ties.
+            order.sort_by_key(|c| {
+                Reverse((
+                    counts.get(c).copied().unwrap_or(0),
+                    overall.get(c).copied().unwrap_or(0),
+                ))
+            });
+            order
+        })
+        .collect()
+}
+
+/// Fragments the user remembers from `--known-prefix`, `--known-suffix`, and `--known-contains`.
+#[derive(Clone, Debug, Default)]
+pub struct Hints {
+    pub prefix: String,
+    pub suffix: String,
+    pub contains: String,
+}
+
+impl Hints {
+    pub fn is_empty(&self) -> bool {
+        self.prefix.is_empty() && self.suffix.is_empty() && self.contains.is_empty()
+    }
+
+    /// Whether a wordlist candidate fits every known fragment, without them overlapping.
+    pub fn matches(&self, candidate: &str) -> bool {
+        let Some(middle) = candidate
+            .strip_prefix(self.prefix.as_str())
+            .and_then(|rest| rest.strip_suffix(self.suffix.as_str()))
+        else {
+            return false;
+        };
+        middle.contains(self.contains.as_str())
+    }
+
+    /// What of `password` brute force would have generated: it without the prefix, the suffix,
+    /// and the first `contains` fragment between them, whichever of those it has.
+    fn brute_forced_part(&self, password: &str) -> String {
+        let rest = password
+            .strip_prefix(self.prefix.as_str())
+            .unwrap_or(password);
+        let rest = rest.strip_suffix(self.suffix.as_str()).unwrap_or(rest);
+        match rest.find(self.contains.as_str()) {
+            Some(at) if !self.contains.is_empty() => {
+                format!("{}{}", &rest[..at], &rest[at + self.contains.len()..])
+            }
+            _ => rest.to_owned(),
+        }
+    }
+
+    /// Characters the fragments take up.
+    pub fn len(&self) -> usize {
+        [&self.prefix, &self.suffix, &self.contains]
+            .iter()
+            .map(|fragment| fragment.chars().count())
+            .sum()
+    }
+
+    /// Places the `contains` fragment can go among `length` brute-forced characters.
+    fn slots(&self, length: usize) -> u64 {
+        if self.contains.is_empty() {
+            1
+        } else {
+            length as u64 + 1
+        }
+    }
+}
+
+impl std::fmt::Display for Hints {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let fragments = [
+            ("prefix", &self.prefix),
+            ("suffix", &self.suffix),
+            ("containing", &self.contains),
+        ];
+        let mut first = true;
+        for (name, fragment) in fragments {
+            if !fragment.is_empty() {
+                write!(f, "{}{name} {fragment:?}", if first { "" } else { ", " })?;
+                first = false;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Charset {
+    pub chars: Vec<char>,
+    /// The spec as given on the command line, for display.
+    pub spec: String,
+}
+
+/// Expands a charset spec: literal characters plus `?l`, `?u`, `?d`, `?s`, `?a` classes
+/// (`??` for a literal `?`). Duplicates are dropped, keeping first occurrence order.
+pub fn parse_charset(spec: &str) -> Result<Charset, String> {
+    let mut charset: Vec<char> = Vec::new();
+    let mut push = |chars: &str| {
+        for c in chars.chars() {
+            if !charset.contains(&c) {
+                charset.push(c);
+            }
+        }
+    };
+
+    let mut chars = spec.chars();
+    while let Some(c) = chars.next() {
+        if c != '?' {
+            push(c.encode_utf8(&mut [0; 4]));
+            continue;
+        }
+        match chars.next() {
+            Some('l') => push(LOWER),
+            Some('u') => push(UPPER),
+            Some('d') => push(DIGITS),
+            Some('s') => push(SYMBOLS),
+            Some('a') => {
+                push(LOWER);
+                push(UPPER);
+                push(DIGITS);
+                push(SYMBOLS);
+            }
+            Some('?') => push("?"),
+            Some(other) => return Err(format!("unknown charset class \"?{other}\"")),
+            None => return Err("charset ends with a lone \"?\" (use \"??\" for a literal)".into()),
+        }
+    }
+    if charset.is_empty() {
+        return Err("charset is empty".to_owned());
+    }
+    Ok(Charset {
+        chars: charset,
+        spec: spec.to_owned(),
+    })
+}
+
+/// Parses `N` or `MIN-MAX` password lengths.
+pub fn parse_length(value: &str) -> Result<(usize, usize), String> {
+    let invalid = || format!("invalid length \"{value}\" (expected e.g. 6 or 4-8)");
+    let (min, max) = match value.split_once('-') {
+        Some((min, max)) => (min.trim(), max.trim()),
+        None => (value.trim(), value.trim()),
+    };
+    let min: usize = min.parse().map_err(|_| invalid())?;
+    let max: usize = max.parse().map_err(|_| invalid())?;
+    if min == 0 || min > max {
+        return Err(invalid());
+    }
+    Ok((min, max))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recovered(passwords: &[&str]) -> Vec<String> {
+        passwords
+            .iter()
+            .map(|&password| password.to_owned())
+            .collect()
+    }
+
+    #[test]
+    fn trained_orders_rank_each_position() {
+        let charset: Vec<char> = "abc".chars().collect();
+        let orders = trained_orders(
+            &charset,
+            &recovered(&["cb", "ca", "b"]),
+            &Hints::default(),
+            3,
+        );
+        assert_eq!(orders[0], ['c', 'b', 'a']);
+        assert_eq!(orders[1], ['b', 'a', 'c']);
+        // Past the longest password, only the overall counts order it.
+        assert_eq!(orders[2], ['b', 'c', 'a']);
+    }
+
+    #[test]
+    fn trained_orders_skip_the_known_fragments() {
+        let charset: Vec<char> = "abc".chars().collect();
+        let hints = Hints {
+            prefix: "ab".to_owned(),
+            suffix: "!".to_owned(),
+            contains: "x".to_owned(),
+        };
+        let passwords = recovered(&["abcxa!", "abcb", "abxca!"]);
+        let orders = trained_orders(&charset, &passwords, &hints, 2);
+        assert_eq!(orders[0], ['c', 'a', 'b']);
+        assert_eq!(orders[1], ['a', 'b', 'c']);
+
+        let brute = BruteForce::new(charset, 6, 6, hints)
+            .unwrap()
+            .ordered(orders);
+        let mut buf = String::new();
+        // The most common trained password comes first.
+        brute.write(0, &mut buf);
+        assert_eq!(buf, "abxca!");
+        brute.write(3, &mut buf);
+        assert_eq!(buf, "abxaa!");
+    }
+}
@@ -0,0 +1,60 @@
+use std::{
+    fs, mem,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// Transparent huge pages are 2 MiB on every platform that has them.
+#[cfg(target_os = "linux")]
+const HUGE_PAGE: usize = 2 << 20;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Makes candidate sources loaded from now on ask for huge pages.
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Whether the kernel backs advised memory with transparent huge pages: `false` when they are
+/// disabled or the platform has none.
+pub fn available() -> bool {
+    fs::read_to_string("/sys/kernel/mm/transparent_hugepage/enabled")
+        .is_ok_and(|mode| !mode.contains("[never]"))
+}
+
+/// A copy of `items` in freshly advised memory, so its pages are faulted in huge rather than
+/// left for the kernel to collapse later.
+pub fn relocate<T: Copy>(items: &[T]) -> Vec<T> {
+    let mut copy = Vec::with_capacity(items.len());
+    let spare = copy.spare_capacity_mut();
+    advise(spare.as_ptr().cast(), mem::size_of_val(spare), true);
+    copy.extend_from_slice(items);
+    copy
+}
+
+/// Asks for (or, for comparison, against) huge pages over the whole 2 MiB pages inside the
+/// range. Best effort: smaller ranges and refusals are ignored.
+#[cfg(target_os = "linux")]
+pub fn advise(ptr: *const u8, len: usize, huge: bool) {
+    let start = (ptr as usize).next_multiple_of(HUGE_PAGE);
+    let end = (ptr as usize + len) / HUGE_PAGE * HUGE_PAGE;
+    if start >= end {
+        return;
+    }
+    let advice = if huge {
+        libc::MADV_HUGEPAGE
+    } else {
+        libc::MADV_NOHUGEPAGE
+    };
+    // SAFETY: the range lies within one live allocation, and the advice does not change its
+    // contents.
+    unsafe {
+        libc::madvise(start as *mut libc::c_void, end - start, advice);
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn advise(_ptr: *const u8, _len: usize, _huge: bool) {}
@@ -0,0 +1,140 @@
+use std::{env, fmt::Display, sync::OnceLock};
+
+use clap::ValueEnum;
+
+/// Languages of the run's outcome messages. Error messages and the result file stay English.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Lang {
+    /// Pick from `LC_ALL`, `LC_MESSAGES`, or `LANG`, falling back to English.
+    Auto,
+    En,
+    De,
+    Es,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Message {
+    PasswordFound,
+    InternalKeys,
+    Tried,
+    FoundBy,
+    NotFound,
+    Interrupted,
+    TimeLimitReached,
+    /// A run that ended without the password; `outcome` is one of the three above.
+    Failure,
+    /// `--cat`'s one-line report on stderr.
+    CatFound,
+    BatchFound,
+    Cracked,
+}
+
+static LANG: OnceLock<Lang> = OnceLock::new();
+
+/// Sets the language for the rest of the run; English until then.
+pub fn install(lang: Lang) {
+    let _ = LANG.set(match lang {
+        Lang::Auto => from_environment(),
+        lang => lang,
+    });
+}
+
+fn from_environment() -> Lang {
+    // The first of these that is set wins, as in POSIX locale lookup.
+    let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .into_iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|value| !value.is_empty())
+        .unwrap_or_default();
+    match locale.get(..2) {
+        Some("de") => Lang::De,
+        Some("es") => Lang::Es,
+        _ => Lang::En,
+    }
+}
+
+/// English, German, and Spanish, with `{name}` placeholders.
+fn templates(message: Message) -> [&'static str; 3] {
+    match message {
+        Message::PasswordFound => [
+            "Password found: {password}",
+            "Passwort gefunden: {password}",
+            "Contraseña encontrada: {password}",
+        ],
+        Message::InternalKeys => [
+            "Internal keys: {keys}",
+            "Interne Schlüssel: {keys}",
+            "Claves internas: {keys}",
+        ],
+        Message::Tried => [
+            "Tried {count} candidates in {elapsed}",
+            "{count} Kandidaten in {elapsed} getestet",
+            "Se probaron {count} candidatos en {elapsed}",
+        ],
+        Message::FoundBy => [
+            "Found by {provenance}",
+            "Gefunden durch {provenance}",
+            "Encontrada por {provenance}",
+        ],
+        Message::NotFound => [
+            "Password not found",
+            "Passwort nicht gefunden",
+            "Contraseña no encontrada",
+        ],
+        Message::Interrupted => ["Interrupted", "Abgebrochen", "Interrumpido"],
+        Message::TimeLimitReached => [
+            "Time limit reached",
+            "Zeitlimit erreicht",
+            "Límite de tiempo alcanzado",
+        ],
+        Message::Failure => [
+            "{outcome} ({count} candidates tried) after {elapsed}",
+            "{outcome} ({count} Kandidaten getestet) nach {elapsed}",
+            "{outcome} ({count} candidatos probados) tras {elapsed}",
+        ],
+        Message::CatFound => [
+            "Password found: {password} ({count} candidates in {elapsed})",
+            "Passwort gefunden: {password} ({count} Kandidaten in {elapsed})",
+            "Contraseña encontrada: {password} ({count} candidatos en {elapsed})",
+        ],
+        Message::BatchFound => [
+            "{archive}: password found: {password} ({count} candidates in {elapsed}{details}){strength}",
+            "{archive}: Passwort gefunden: {password} ({count} Kandidaten in {elapsed}{details}){strength}",
+            "{archive}: contraseña encontrada: {password} ({count} candidatos en {elapsed}{details}){strength}",
+        ],
+        Message::Cracked => [
+            "Cracked {found} of {total} archives in {elapsed}",
+            "{found} von {total} Archiven in {elapsed} geknackt",
+            "{found} de {total} archivos descifrados en {elapsed}",
+        ],
+    }
+}
+
+/// The message in the installed language, before placeholders are filled.
+pub fn text(message: Message) -> &'static str {
+    let [en, de, es] = templates(message);
+    match LANG.get() {
+        Some(Lang::De) => de,
+        Some(Lang::Es) => es,
+        _ => en,
+    }
+}
+
+/// The message with every `{name}` replaced by its value. Values are not scanned again, so a
+/// password that contains braces comes through as it is.
+pub fn tr(message: Message, args: &[(&str, &dyn Display)]) -> String {
+    let mut filled = String::new();
+    let mut rest = text(message);
+    while let Some((before, after)) = rest.split_once('{')
+        && let Some((name, after)) = after.split_once('}')
+    {
+        filled.push_str(before);
+        match args.iter().find(|(arg, _)| *arg == name) {
+            Some((_, value)) => filled.push_str(&value.to_string()),
+            None => filled.push_str(&format!("{{{name}}}")),
+        }
+        rest = after;
+    }
+    filled.push_str(rest);
+    filled
+}
@@ -0,0 +1,337 @@
+use hmac::{Hmac, KeyInit, Mac};
+use sha1::{Digest, Sha1};
+
+/// Passwords derived side by side; eight 32-bit lanes fill one AVX2 register (two with SSE2).
+pub const LANES: usize = 8;
+
+/// WinZip AES derives its keys with a fixed iteration count.
+pub const AES_ITERATIONS: u32 = 1000;
+pub const DIGEST_LEN: usize = 20;
+const BLOCK_LEN: usize = 64;
+const IV: [u32; 5] = [
+    0x6745_2301,
+    0xefcd_ab89,
+    0x98ba_dcfe,
+    0x1032_5476,
+    0xc3d2_e1f0,
+];
+
+type Lanes = [u32; LANES];
+type State = [Lanes; 5];
+
+/// HMAC-SHA1 keyed with one password per lane, with the inner and outer pad states computed
+/// once so each PBKDF2 iteration costs exactly two compressions per lane.
+pub struct HmacLanes {
+    inner: State,
+    outer: State,
+}
+
+impl HmacLanes {
+    pub fn new(passwords: &[&[u8]; LANES]) -> Self {
+        let mut inner_block = [[0u32; LANES]; 16];
+        let mut outer_block = [[0u32; LANES]; 16];
+        for (lane, password) in passwords.iter().enumerate() {
+            let mut key = [0u8; BLOCK_LEN];
+            if password.len() > BLOCK_LEN {
+                key[..DIGEST_LEN].copy_from_slice(&Sha1::digest(password));
+            } else {
+                key[..password.len()].copy_from_slice(password);
+            }
+            for (word, bytes) in key.chunks_exact(4).enumerate() {
+                let value = u32::from_be_bytes(bytes.try_into().expect("4-byte chunk"));
+                inner_block[word][lane] = value ^ 0x3636_3636;
+                outer_block[word][lane] = value ^ 0x5c5c_5c5c;
+            }
+        }
+        let mut inner = broadcast(IV);
+        let mut outer = broadcast(IV);
+        compress(&mut inner, &inner_block);
+        compress(&mut outer, &outer_block);
+        Self { inner, outer }
+    }
+
+    /// Computes PBKDF2 output block `index` (1-based) for every lane.
+    pub fn pbkdf2_block(&self, salt: &[u8], iterations: u32, index: u32) -> [[u8; 20]; LANES] {
+        let mut message = salt.to_vec();
+        message.extend_from_slice(&index.to_be_bytes());
+        let first = self.mac_shared(&message);
+        #[cfg(target_arch = "x86_64")]
+        let t = if std::arch::is_x86_feature_detected!("avx512vl") {
+            // SAFETY: the CPU was just checked for AVX-512VL.
+            unsafe { self.iterate_avx512(first, iterations) }
+        } else if std::arch::is_x86_feature_detected!("avx2") {
+            // SAFETY: the CPU was just checked for AVX2.
+            unsafe { self.iterate_avx2(first, iterations) }
+        } else {
+            self.iterate(first, iterations)
+        };
+        #[cfg(not(target_arch = "x86_64"))]
+        let t = self.iterate(first, iterations);
+        lane_bytes(&t)
+    }
+
+    /// The same loop compiled for AVX-512VL, which adds a single-instruction rotate.
+    #[cfg(target_arch = "x86_64")]
+    #[target_feature(enable = "avx512f,avx512vl")]
+    unsafe fn iterate_avx512(&self, first: State, iterations: u32) -> State {
+        self.iterate(first, iterations)
+    }
+
+    /// The same loop compiled for AVX2, where all eight lanes fit one register.
+    #[cfg(target_arch = "x86_64")]
+    #[target_feature(enable = "avx2")]
+    unsafe fn iterate_avx2(&self, first: State, iterations: u32) -> State {
+        self.iterate(first, iterations)
+    }
+
+    /// Runs the remaining PBKDF2 iterations, XOR-ing each HMAC output into the block.
+    #[inline(always)]
+    fn iterate(&self, first: State, iterations: u32) -> State {
+        let mut u = first;
+        let mut t = first;
+        for _ in 1..iterations {
+            u = self.mac_digest(&u);
+            for word in 0..5 {
+                for lane in 0..LANES {
+                    t[word][lane] ^= u[word][lane];
+                }
+            }
+        }
+        t
+    }
+
+    /// HMAC of a message shared by every lane, such as PBKDF2's salt and block index.
+    fn mac_shared(&self, message: &[u8]) -> State {
+        let mut state = self.inner;
+        let mut padded = message.to_vec();
+        padded.push(0x80);
+        while padded.len() % BLOCK_LEN != BLOCK_LEN - 8 {
+            padded.push(0);
+        }
+        padded.extend_from_slice(&(((BLOCK_LEN + message.len()) as u64) * 8).to_be_bytes());
+        for block in padded.chunks_exact(BLOCK_LEN) {
+            let mut words = [[0u32; LANES]; 16];
+            for (word, bytes) in block.chunks_exact(4).enumerate() {
+                words[word] = [u32::from_be_bytes(bytes.try_into().expect("4-byte chunk")); LANES];
+            }
+            compress(&mut state, &words);
+        }
+        self.outer_of(&state)
+    }
+
+    /// HMAC of each lane's own previous digest.
+    #[inline(always)]
+    fn mac_digest(&self, digest: &State) -> State {
+        let mut state = self.inner;
+        compress(&mut state, &digest_block(digest));
+        self.outer_of(&state)
+    }
+
+    #[inline(always)]
+    fn outer_of(&self, inner_digest: &State) -> State {
+        let mut state = self.outer;
+        compress(&mut state, &digest_block(inner_digest));
+        state
+    }
+}
+
+/// Computes PBKDF2-HMAC-SHA1 output block `index` (1-based) for a single password.
+pub fn pbkdf2_block(password: &[u8], salt: &[u8], iterations: u32, index: u32) -> [u8; 20] {
+    let prf = Hmac::<Sha1>::new_from_slice(password).expect("HMAC accepts keys of any length");
+    let mut mac = prf.clone();
+    mac.update(salt);
+    mac.update(&index.to_be_bytes());
+    let mut u: [u8; DIGEST_LEN] = mac.finalize().into_bytes().into();
+    let mut t = u;
+    for _ in 1..iterations {
+        let mut mac = prf.clone();
+        mac.update(&u);
+        u = mac.finalize().into_bytes().into();
+        for (t, u) in t.iter_mut().zip(u) {
+            *t ^= u;
+        }
+    }
+    t
+}
+
+/// Derives `N` bytes of PBKDF2-HMAC-SHA1 for each lane's password.
+pub fn derive_lanes<const N: usize>(
+    passwords: &[&[u8]; LANES],
+    salt: &[u8],
+    iterations: u32,
+) -> [[u8; N]; LANES] {
+    let hmac = HmacLanes::new(passwords);
+    let mut out = [[0u8; N]; LANES];
+    for (block, start) in (0..N).step_by(DIGEST_LEN).enumerate() {
+        let derived = hmac.pbkdf2_block(salt, iterations, block as u32 + 1);
+        let end = N.min(start + DIGEST_LEN);
+        for lane in 0..LANES {
+            out[lane][start..end].copy_from_slice(&derived[lane][..end - start]);
+        }
+    }
+    out
+}
+
+/// Each lane's digest as the big-endian bytes SHA-1 outputs.
+fn lane_bytes(state: &State) -> [[u8; DIGEST_LEN]; LANES] {
+    let mut out = [[0u8; DIGEST_LEN]; LANES];
+    for (lane, bytes) in out.iter_mut().enumerate() {
+        for word in 0..5 {
+            bytes[word * 4..word * 4 + 4].copy_from_slice(&state[word][lane].to_be_bytes());
+        }
+    }
+    out
+}
+
+fn broadcast(words: [u32; 5]) -> State {
+    words.map(|word| [word; LANES])
+}
+
+/// A single padded block holding a 20-byte digest that follows a 64-byte key block.
+#[inline(always)]
+fn digest_block(digest: &State) -> [Lanes; 16] {
+    let mut block = [[0u32; LANES]; 16];
+    block[..5].copy_from_slice(digest);
+    block[5] = [0x8000_0000; LANES];
+    block[15] = [((BLOCK_LEN + DIGEST_LEN) * 8) as u32; LANES];
+    block
+}
+
+/// The SHA-1 compression function, applied to every lane at once. Each step is a loop over
+/// lanes so the compiler can keep the whole state in vector registers.
+#[inline(always)]
+fn compress(state: &mut State, block: &[Lanes; 16]) {
+    let mut w = *block;
+    let [mut a, mut b, mut c, mut d, mut e] = *state;
+
+    macro_rules! rounds {
+        ($range:expr, $k:expr, $f:expr) => {
+            for t in $range {
+                if t >= 16 {
+                    for lane in 0..LANES {
+                        w[t % 16][lane] = (w[(t + 13) % 16][lane]
+                            ^ w[(t + 8) % 16][lane]
+                            ^ w[(t + 2) % 16][lane]
+                            ^ w[t % 16][lane])
+                            .rotate_left(1);
+                    }
+                }
+                for lane in 0..LANES {
+                    let f: u32 = $f(b[lane], c[lane], d[lane]);
+                    let temp = a[lane]
+                        .rotate_left(5)
+                        .wrapping_add(f)
+                        .wrapping_add(e[lane])
+                        .wrapping_add($k)
+                        .wrapping_add(w[t % 16][lane]);
+                    e[lane] = d[lane];
+                    d[lane] = c[lane];
+                    c[lane] = b[lane].rotate_left(30);
+                    b[lane] = a[lane];
+                    a[lane] = temp;
+                }
+            }
+        };
+    }
+
+    rounds!(0..20, 0x5a82_7999, |b: u32, c: u32, d: u32| (b & c)
+        | (!b & d));
+    rounds!(20..40, 0x6ed9_eba1, |b: u32, c: u32, d: u32| b ^ c ^ d);
+    rounds!(40..60, 0x8f1b_bcdc, |b: u32, c: u32, d: u32| (b & c)
+        | (b & d)
+        | (c & d));
+    rounds!(60..80, 0xca62_c1d6, |b: u32, c: u32, d: u32| b ^ c ^ d);
+
+    for (lane_state, value) in state.iter_mut().zip([a, b, c, d, e]) {
+        for lane in 0..LANES {
+            lane_state[lane] = lane_state[lane].wrapping_add(value[lane]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every implementation of the iteration loop this CPU can run, by name.
+    fn iterated(
+        hmac: &HmacLanes,
+        salt: &[u8],
+        iterations: u32,
+        index: u32,
+    ) -> Vec<(&'static str, [[u8; DIGEST_LEN]; LANES])> {
+        let mut message = salt.to_vec();
+        message.extend_from_slice(&index.to_be_bytes());
+        let first = hmac.mac_shared(&message);
+        let mut paths = vec![("fallback", lane_bytes(&hmac.iterate(first, iterations)))];
+        #[cfg(target_arch = "x86_64")]
+        {
+            if std::arch::is_x86_feature_detected!("avx2") {
+                // SAFETY: the CPU was just checked for AVX2.
+                let t = unsafe { hmac.iterate_avx2(first, iterations) };
+                paths.push(("avx2", lane_bytes(&t)));
+            }
+            if std::arch::is_x86_feature_detected!("avx512vl") {
+                // SAFETY: the CPU was just checked for AVX-512VL.
+                let t = unsafe { hmac.iterate_avx512(first, iterations) };
+                paths.push(("avx512", lane_bytes(&t)));
+            }
+        }
+        paths
+    }
+
+    fn assert_lanes_match_scalar(passwords: &[&[u8]; LANES], salt: &[u8], iterations: u32) {
+        let hmac = HmacLanes::new(passwords);
+        for index in 1..=2 {
+            let mut paths = iterated(&hmac, salt, iterations, index);
+            paths.push(("dispatched", hmac.pbkdf2_block(salt, iterations, index)));
+            for (path, lanes) in paths {
+                for (lane, password) in passwords.iter().enumerate() {
+                    assert_eq!(
+                        lanes[lane],
+                        pbkdf2_block(password, salt, iterations, index),
+                        "{path} lane {lane}, block {index}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn scalar_block_matches_pbkdf2_crate() {
+        let mut expected = [0u8; 40];
+        pbkdf2::pbkdf2_hmac::<Sha1>(b"hunter2", b"saltsalt", 7, &mut expected);
+        assert_eq!(pbkdf2_block(b"hunter2", b"saltsalt", 7, 1), expected[..20]);
+        assert_eq!(pbkdf2_block(b"hunter2", b"saltsalt", 7, 2), expected[20..]);
+    }
+
+    #[test]
+    fn every_lane_matches_scalar() {
+        let long = [b'x'; 65];
+        let longer = [0xa5u8; 200];
+        let passwords: [&[u8]; LANES] = [
+            b"hunter2",
+            b"",
+            &long,
+            &longer,
+            &long[..64],
+            b"p\xc3\xa4ssw\xc3\xb6rd",
+            b"a",
+            b"correct horse battery staple",
+        ];
+        assert_lanes_match_scalar(&passwords, b"0123456789abcdef", AES_ITERATIONS);
+        assert_lanes_match_scalar(&passwords, b"", 1);
+    }
+
+    #[test]
+    fn padded_lanes_do_not_disturb_filled_ones() {
+        // A partial batch, padded with empty passwords as the verifier pads it.
+        let passwords: [&[u8]; LANES] = [b"hunter2", b"letmein", b"", b"", b"", b"", b"", b""];
+        assert_lanes_match_scalar(&passwords, b"12345678", AES_ITERATIONS);
+
+        let derived = derive_lanes::<34>(&passwords, b"12345678", 3);
+        let mut expected = [0u8; 34];
+        pbkdf2::pbkdf2_hmac::<Sha1>(b"letmein", b"12345678", 3, &mut expected);
+        assert_eq!(derived[1], expected);
+    }
+}
@@ -1,149 +1,1856 @@
+mod affix;
+mod archive;
+mod auto;
+mod batch;
+mod bench;
+mod checkpoint;
+mod clipboard;
+mod compat;
+mod config;
+mod control;
+mod cpus;
+mod dedup;
+mod events;
+mod extract;
+mod fetch;
+mod fixture;
+mod fuzzy;
+mod generate;
+mod hugepages;
+mod i18n;
+mod kdf;
+mod manifest;
+mod numa;
+mod paths;
+mod phrase;
+mod potfile;
+mod preview;
+mod report;
+mod rewrite;
+mod sample;
+mod search;
+mod secret;
+mod selftest;
+mod service;
+mod sessions;
+mod sniff;
+mod spool;
+mod stats;
+mod status;
+mod strength;
+mod summary;
+mod survivors;
+mod throttle;
+mod timing;
+mod triage;
+mod tune;
+mod unicode;
+mod units;
+mod usage;
+mod vault;
+mod verify;
+mod wizard;
+mod wordlist;
+mod zipcrypto;
+
 use std::{
-    fs::File,
-    io::{BufRead, BufReader, Read},
+    ffi::OsString,
+    fs,
     path::{Path, PathBuf},
-    sync::{
-        Mutex,
-        atomic::{AtomicBool, Ordering},
-    },
-    time::Instant,
+    process::ExitCode,
+    sync::Arc,
+    time::{Duration, Instant},
 };
 
-use anyhow::{Context, Result, anyhow, bail};
-use clap::Parser;
-use rayon::prelude::*;
-use zip::ZipArchive;
-use zip::result::ZipError;
+use anyhow::{Context, Result, bail};
+use clap::{ArgGroup, CommandFactory, Parser, Subcommand, error::ErrorKind};
+use zeroize::Zeroizing;
+
+use crate::affix::{AffixSpec, Affixed, Position as AffixPosition};
+use crate::archive::Encryption;
+use crate::checkpoint::Checkpointer;
+use crate::control::Governor;
+use crate::events::{Event, Events};
+use crate::extract::{DuplicatePolicy, ExtractOptions};
+use crate::generate::{BruteForce, Candidates, Charset, Dedupe, Generator, Hints, Seen};
+use crate::i18n::{Lang, Message, tr};
+use crate::phrase::Phrases;
+use crate::report::{RunResult, Status};
+use crate::search::{Provenance, Searcher, Stage, StageStats};
+use crate::secret::SecretBuf;
+use crate::status::Progress;
+use crate::survivors::SurvivorLog;
+use crate::throttle::ThrottleConfig;
+use crate::timing::Timings;
+use crate::vault::Vault;
+use crate::verify::{Backend, Verifier};
+use crate::wordlist::{LineRange, Words};
+use crate::zipcrypto::Keys;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about = "Fast, parallel ZIP password brute forcer.")]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    crack: Option<Args>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Measure AES key derivation throughput of the scalar and multi-buffer implementations,
+    /// and candidate throughput of every backend, and recommend one.
+    Bench(bench::BenchArgs),
+    /// Download a well-known wordlist to the cache so it can be used as `--wordlist @NAME`.
+    Fetch(fetch::FetchArgs),
+    /// Write a small encrypted archive that is byte-for-byte the same every time for the same
+    /// options, for testing scripts and pipelines against a known password.
+    MakeTestArchive(fixture::MakeTestArchiveArgs),
+    /// Reconstruct a password of up to about nine characters from ZipCrypto internal keys.
+    RecoverPassword(zipcrypto::RecoverPasswordArgs),
+    /// Rewrite an archive with every entry encrypted under a new password using AES-256.
+    Rekey(rewrite::RekeyArgs),
+    /// Crack tiny generated archives of every supported encryption scheme and compression
+    /// method end to end, to check that this build works on this machine.
+    Selftest(selftest::SelftestArgs),
+    /// Write an unencrypted copy of an archive, keeping entry names, timestamps, and attributes.
+    Strip(rewrite::StripArgs),
+    /// Summarize recovered passwords from potfiles and result files: lengths, character
+    /// classes, base words, and the stages that found them.
+    Stats(stats::StatsArgs),
+    /// Find every archive-like file under a directory and report which are encrypted and how,
+    /// as a prioritized queue, without attacking any of them.
+    Triage(triage::TriageArgs),
+    /// Inspect an archive, ask what you remember about the password, and run a matching attack.
+    Wizard(wizard::WizardArgs),
+}
+
+#[derive(clap::Args, Clone, Debug)]
+#[command(group(ArgGroup::new("source").required(true).multiple(true).args(["wordlist", "phrase_words", "charset", "almost", "password", "survivors_in", "auto"])))]
 struct Args {
-    /// Path to the password-protected ZIP file.
-    #[arg(short = 'z', long, value_name = "FILE")]
-    zip: PathBuf,
+    /// Path to the password-protected ZIP file, or `-` to read it from stdin; repeat to attack
+    /// several archives in one run.
+    #[arg(
+        short = 'z',
+        long,
+        value_name = "FILE",
+        required_unless_present = "manifest"
+    )]
+    zip: Vec<PathBuf>,
+
+    /// Queue the archives listed in a JSON or CSV file, each with an optional target entry,
+    /// known fragments, and priority; higher priorities are attacked first.
+    #[arg(long, value_name = "FILE")]
+    manifest: Option<PathBuf>,
+
+    /// With several archives, how many to attack at once; the threads are divided among them.
+    #[arg(long, value_name = "N", default_value_t = 1)]
+    parallel_archives: usize,
+
+    /// Hash every archive and attack only one of each set with identical contents; the others
+    /// share its outcome. Runs even a single --zip as a batch.
+    #[arg(long)]
+    dedup: bool,
+
+    /// Remember archives attacked to the end (found or exhausted) by content hash and plan, and
+    /// skip them in later runs with the same plan. Implies --dedup.
+    #[arg(long, value_name = "FILE")]
+    dedup_file: Option<PathBuf>,
 
-    /// Wordlist containing one password candidate per line.
+    /// Append a row per archive (scheme, outcome, password, the stage that found it, and
+    /// duration) to FILE with both a .json and a .csv extension. Runs even a single --zip as a
+    /// batch.
+    #[arg(long, value_name = "FILE")]
+    summary: Option<PathBuf>,
+
+    /// The password, if already known: it is confirmed and then used for --cat, --preview,
+    /// --sniff, or --extract without searching.
+    #[arg(long, value_name = "PASSWORD")]
+    password: Option<String>,
+
+    /// A password you remember that doesn't work; candidates within --distance edits of it are
+    /// tried before anything else.
+    #[arg(long, value_name = "PASSWORD")]
+    almost: Option<String>,
+
+    /// Edits (typos, swaps, shift slips) --almost candidates may be away from the password.
+    #[arg(long, value_name = "N", default_value_t = 1, requires = "almost")]
+    distance: u32,
+
+    /// Wordlist containing one password candidate per line (or `@NAME` for a fetched list);
+    /// repeat to try several in order.
     #[arg(short = 'w', long, value_name = "FILE")]
-    wordlist: PathBuf,
+    wordlist: Vec<PathBuf>,
+
+    /// Only the candidates on these lines of each wordlist, as `FIRST-LAST` or `FIRST-` (e.g.
+    /// `1000001-2000000`), to split a list across machines or pick up where a run stopped.
+    #[arg(long, value_name = "RANGE", value_parser = wordlist::parse_lines, requires = "wordlist")]
+    lines: Option<LineRange>,
+
+    /// Wordlists carry a count on every line, as `count word` (like `uniq -c` prints) or
+    /// `word<TAB>count`; the most frequent words are tried first.
+    #[arg(long, requires = "wordlist")]
+    counts: bool,
+
+    /// After the wordlists, try the NFC and NFD forms and the case variants (including Turkish
+    /// dotted and dotless i) of their non-ASCII candidates.
+    #[arg(long)]
+    unicode_variants: bool,
+
+    /// Skip candidates of the listed stages (wordlists, --almost, Unicode variants) that repeat
+    /// one queued earlier, either exactly or in any case.
+    #[arg(long, value_enum, default_value_t = Dedupe::Off)]
+    dedupe: Dedupe,
+
+    /// Skip wordlist, --almost, and Unicode variant candidates that are in these wordlists,
+    /// such as a list already tried to the end, so only what a newer list adds is tested.
+    #[arg(long, value_name = "FILE", num_args = 1..)]
+    exclude_wordlist: Vec<PathBuf>,
+
+    /// Skip wordlist, --almost, and Unicode variant candidates recovered in these potfiles or
+    /// result files.
+    #[arg(long, value_name = "FILE", num_args = 1..)]
+    exclude_potfile: Vec<PathBuf>,
 
-    /// Specific file path inside the archive to validate (defaults to first non-directory).
+    /// Once the wordlists are exhausted, try every word again followed by each year in this
+    /// range (e.g. `1990-2030`), in four- and two-digit forms.
+    #[arg(long, value_name = "RANGE", value_parser = affix::parse_years)]
+    affix_years: Option<(u32, u32)>,
+
+    /// Also append every run of this many digits, as `N` or `MIN-MAX` (e.g. `1-3`).
+    #[arg(long, value_name = "RANGE", value_parser = generate::parse_length)]
+    affix_digits: Option<(usize, usize)>,
+
+    /// Also append each of these symbols, alone and after each year or digit run.
+    #[arg(long, value_name = "CHARS")]
+    affix_symbols: Option<String>,
+
+    /// Which end of the words the affixes go on.
+    #[arg(long, value_enum, default_value_t = AffixPosition::Suffix)]
+    affix_position: AffixPosition,
+
+    /// Word list (or `@NAME`) to build multi-word passphrases from, after the wordlists.
+    #[arg(long, value_name = "FILE")]
+    phrase_words: Option<PathBuf>,
+
+    /// Number of words in a passphrase, as `N` or `MIN-MAX`.
+    #[arg(long, value_name = "RANGE", default_value = "2-3", value_parser = generate::parse_length, requires = "phrase_words")]
+    phrase_count: (usize, usize),
+
+    /// Characters to join passphrase words with; words run together are always tried too.
+    #[arg(
+        long,
+        value_name = "CHARS",
+        default_value = " -_",
+        requires = "phrase_words"
+    )]
+    separators: String,
+
+    /// Brute force every string over this charset (literals plus `?l ?u ?d ?s ?a` classes).
+    #[arg(short = 'c', long, value_name = "SET", value_parser = generate::parse_charset)]
+    charset: Option<Charset>,
+
+    /// Password lengths to brute force, as `N` or `MIN-MAX`.
+    #[arg(short = 'l', long, value_name = "RANGE", default_value = "1-6", value_parser = generate::parse_length)]
+    length: (usize, usize),
+
+    /// Order brute force by the passwords recovered in these potfiles or result files: at
+    /// each position, the characters they use there most are tried first.
+    #[arg(long, value_name = "FILE", num_args = 1.., requires = "charset")]
+    train: Vec<PathBuf>,
+
+    /// The password is known to start with this; brute force only fills in the rest, and
+    /// wordlist candidates without it are skipped.
+    #[arg(long, value_name = "TEXT")]
+    known_prefix: Option<String>,
+
+    /// The password is known to end with this.
+    #[arg(long, value_name = "TEXT")]
+    known_suffix: Option<String>,
+
+    /// The password is known to contain this somewhere between the prefix and suffix.
+    #[arg(long, value_name = "TEXT")]
+    known_contains: Option<String>,
+
+    /// Specific file path inside the archive to validate (defaults to first verifiable entry).
     #[arg(short = 't', long, value_name = "PATH")]
     target: Option<String>,
 
-    /// Number of worker threads to use (defaults to available logical cores).
-    #[arg(long, default_value_t = num_cpus::get())]
+    /// Never pick an entry whose name matches this pattern (`*` and `?` wildcards) as the
+    /// target, such as a huge video; repeat to skip several.
+    #[arg(long, value_name = "PATTERN", conflicts_with = "target")]
+    skip_entry: Vec<String>,
+
+    /// Number of worker threads to use (defaults to the logical cores this process may use,
+    /// within CPU affinity and cgroup quotas).
+    #[arg(long, default_value_t = cpus::available())]
     threads: usize,
+
+    /// Implementation of the per-candidate fast check; `simd` derives AES keys eight at a time.
+    #[arg(long, value_enum, default_value_t = Backend::Cpu)]
+    backend: Backend,
+
+    /// Worker placement on multi-socket machines: `auto` pins workers to every NUMA node with
+    /// a copy of the target per node, `interleave` spreads memory across nodes, and `node:N`
+    /// keeps the run on node N.
+    #[arg(long, value_name = "POLICY", value_parser = numa::parse_policy)]
+    numa: Option<numa::Policy>,
+
+    /// Largest piped archive or decoded wordlist to keep in memory (e.g. `1G`); larger ones are
+    /// spilled to a temp file that vanishes when the run ends.
+    #[arg(long, value_name = "SIZE", value_parser = units::parse_size, default_value = "256M")]
+    memory_limit: u64,
+
+    /// Back wordlist indexes and decoded wordlists with transparent huge pages, which cuts TLB
+    /// misses when workers index into large lists.
+    #[arg(long)]
+    huge_pages: bool,
+
+    /// Checkpoint and stop cleanly, as on the first Ctrl-C, once this file appears.
+    #[arg(long, value_name = "PATH")]
+    stop_file: Option<PathBuf>,
+
+    /// Unix socket accepting `pause`, `resume`, and `status` commands while the run is active.
+    #[cfg(unix)]
+    #[arg(long, value_name = "PATH")]
+    control_socket: Option<PathBuf>,
+
+    /// CPU temperature (e.g. `85C`) above which workers are parked until the system cools down.
+    #[arg(long, value_name = "TEMP", value_parser = throttle::parse_temperature)]
+    thermal_limit: Option<f64>,
+
+    /// Load average per logical CPU (e.g. `0.8`) the run may push the system to before backing off.
+    #[arg(long, value_name = "FRACTION", value_parser = throttle::parse_load_ceiling)]
+    load_ceiling: Option<f64>,
+
+    /// Number of consecutive candidates a worker claims at a time.
+    #[arg(long, default_value_t = 1, value_name = "N")]
+    chunk_size: usize,
+
+    /// Calibrate thread count (up to --threads) and chunk size against the target before running.
+    #[arg(long)]
+    auto_tune: bool,
+
+    /// Times to retry a candidate whose test failed with a transient error, such as running out
+    /// of memory.
+    #[arg(long, value_name = "N", default_value_t = 2)]
+    retries: u32,
+
+    /// Once every stage has run, test the candidates that were skipped after errors again.
+    #[arg(long)]
+    retry_skipped: bool,
+
+    /// On success, list the archive and show the first N decrypted bytes of the target entry.
+    #[arg(long, value_name = "N")]
+    preview: Option<usize>,
+
+    /// On success, decrypt every entry and report its file type and entropy, flagging content
+    /// that looks encrypted again.
+    #[arg(long)]
+    sniff: bool,
+
+    /// On success, write this entry, decrypted and decompressed, to stdout; everything else is
+    /// printed to stderr.
+    #[arg(long, value_name = "ENTRY", conflicts_with_all = ["preview", "sniff", "extract", "copy", "auto_tune", "resume"])]
+    cat: Option<String>,
+
+    /// On success, extract the archive into this directory.
+    #[arg(long, value_name = "DIR")]
+    extract: Option<PathBuf>,
+
+    /// Only extract entries whose names match this pattern (`*` and `?` wildcards); repeat to
+    /// extract several.
+    #[arg(long, value_name = "PATTERN", requires = "extract")]
+    extract_entry: Vec<String>,
+
+    /// Stop extracting once this many decompressed bytes have been written (e.g. `4G`).
+    #[arg(long, value_name = "SIZE", value_parser = units::parse_size)]
+    max_extract_size: Option<u64>,
+
+    /// What to do when an extracted file already exists.
+    #[arg(long, value_enum, default_value_t = DuplicatePolicy::Skip)]
+    on_duplicate: DuplicatePolicy,
+
+    /// On success, copy the password to the system clipboard.
+    #[arg(long)]
+    copy: bool,
+
+    /// Clear the clipboard again after this long (e.g. `30s`); the program waits until then.
+    #[arg(long, value_name = "DURATION", value_parser = units::parse_duration, requires = "copy")]
+    copy_timeout: Option<Duration>,
+
+    /// Plan an escalating attack that fits this budget (e.g. `4h`): common passwords, the
+    /// wordlists, the words with digits appended, then as long a brute force as time allows.
+    #[arg(
+        long,
+        value_name = "DURATION",
+        value_parser = units::parse_duration,
+        conflicts_with_all = ["charset", "time_limit", "survivors_in"]
+    )]
+    auto: Option<Duration>,
+
+    /// Give up once the search has run this long (e.g. `30m`, `4h`).
+    #[arg(long, value_name = "DURATION", value_parser = units::parse_duration)]
+    time_limit: Option<Duration>,
+
+    /// Start the attack even when it is projected to take more than 10 years on this machine.
+    #[arg(long)]
+    force: bool,
+
+    /// Atomically write the outcome (password or failure reason) to this file as JSON.
+    #[arg(long, value_name = "FILE")]
+    result_file: Option<PathBuf>,
+
+    /// Append each recovered password, with its archive and the stage that found it, to this
+    /// file as a JSON line (see `unzippoo stats`).
+    #[arg(long, value_name = "FILE")]
+    potfile: Option<PathBuf>,
+
+    /// Encrypt the potfile, result file, and checkpoints with a key from `keyring` (the OS
+    /// keyring) or `env:NAME` (a passphrase in that variable), and redact passwords from the
+    /// output.
+    #[arg(long, value_name = "SOURCE", value_parser = vault::parse_key_source)]
+    store_key: Option<vault::KeySource>,
+
+    /// Write recovered passwords only to the result file, never to the terminal or --events.
+    #[arg(long, requires = "result_file")]
+    no_echo_password: bool,
+
+    /// With --store-key, still print recovered passwords.
+    #[arg(long, requires = "store_key")]
+    reveal: bool,
+
+    /// Periodically record how far the search got, so it can be continued with --resume.
+    #[arg(long, value_name = "FILE")]
+    checkpoint: Option<PathBuf>,
+
+    /// Continue from the --checkpoint file instead of starting over.
+    #[arg(long, requires = "checkpoint")]
+    resume: bool,
+
+    /// How often to save the checkpoint: a duration such as `30s`, or a candidate count such
+    /// as `100000c`.
+    #[arg(long, value_name = "INTERVAL", default_value = "10s", value_parser = checkpoint::parse_interval)]
+    checkpoint_interval: checkpoint::Interval,
+
+    /// Keep the checkpoint under this name in the sessions directory and resume it when it
+    /// exists. Without --session or --checkpoint, a name is derived from the archive and the
+    /// attack.
+    #[arg(long, value_name = "NAME", value_parser = sessions::parse_name, conflicts_with_all = ["checkpoint", "survivors_in"])]
+    session: Option<String>,
+
+    /// Start over even when a session for this attack already exists.
+    #[arg(long, conflicts_with = "checkpoint")]
+    no_auto_resume: bool,
+
+    /// Emit stage_started, progress, candidate_found, error, and finished events as they happen.
+    #[arg(long, value_name = "FORMAT")]
+    events: Option<events::Format>,
+
+    /// Write --events to FILE, such as a named pipe, instead of stdout.
+    #[arg(long, value_name = "FILE", requires = "events")]
+    events_to: Option<PathBuf>,
+
+    /// Rewrite a small JSON document with phase, progress, rate, and ETA every few seconds.
+    #[arg(long, value_name = "FILE")]
+    status_file: Option<PathBuf>,
+
+    /// On success, estimate the password's strength (zxcvbn-style) and include it in the report.
+    #[arg(long)]
+    analyze_password: bool,
+
+    /// Measure fast-check and verification latency and report the slowest candidates.
+    #[arg(long)]
+    timings: bool,
+
+    /// Check the archives and every candidate source, print each stage's keyspace and projected
+    /// duration, and exit without attacking.
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Language of the outcome messages; `auto` follows the locale. Error messages, the
+    /// result file, and events stay English.
+    #[arg(long, value_enum, default_value_t = Lang::En)]
+    lang: Lang,
+
+    /// Print the candidate at `--candidate-index` of the attack this checkpoint was written for,
+    /// where it came from, and whether it is the password, then exit.
+    #[arg(long, value_name = "CHECKPOINT", requires = "candidate_index")]
+    replay: Option<PathBuf>,
+
+    /// Global index of the candidate to replay, as counted by checkpoints.
+    #[arg(long, value_name = "N", requires = "replay")]
+    candidate_index: Option<u64>,
+
+    /// Test a random sample of each stage (e.g. `1%`), report the rate, repeats, and chance of a
+    /// hit, and exit.
+    #[arg(long, value_name = "FRACTION", value_parser = units::parse_fraction)]
+    sample: Option<f64>,
+
+    /// Write every candidate that passes the fast check to this file, one JSON line each.
+    #[arg(long, value_name = "FILE")]
+    survivors_out: Option<PathBuf>,
+
+    /// Skip candidate generation and the fast check, and fully verify only the candidates a
+    /// --survivors-out file recorded.
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["wordlist", "phrase_words", "charset", "almost", "password", "checkpoint"])]
+    survivors_in: Option<PathBuf>,
+
+    /// Report extra diagnostics, such as wordlist loading statistics, on stderr.
+    #[arg(short = 'v', long)]
+    verbose: bool,
+
+    /// Parse the remaining arguments with fcrackzip's syntax (must come first).
+    #[arg(long)]
+    fcrackzip_compat: bool,
 }
 
-fn main() -> Result<()> {
-    let args = Args::parse();
+/// What the search produced, independent of any post-recovery actions.
+struct Search {
+    status: Status,
+    timings: Option<Timings>,
+    provenance: Option<Provenance>,
+    strength: Option<strength::Strength>,
+    /// Wiped when the search is dropped.
+    password: Option<Zeroizing<String>>,
+    target: Target,
+    stages: Vec<StageStats>,
+    tried: u64,
+    elapsed: Duration,
+    resources: usage::Resources,
+}
 
-    if args.threads == 0 {
-        bail!("--threads must be at least 1");
+impl Search {
+    fn describe_failure(&self) -> &'static str {
+        i18n::text(match self.status {
+            Status::Interrupted => Message::Interrupted,
+            Status::BudgetExhausted => Message::TimeLimitReached,
+            _ => Message::NotFound,
+        })
     }
 
-    rayon::ThreadPoolBuilder::new()
-        .num_threads(args.threads)
-        .build_global()
-        .context("Unable to configure thread pool")?;
+    /// The outcome of a run that ended without the password, with how far it got.
+    fn failure_summary(&self) -> String {
+        tr(
+            Message::Failure,
+            &[
+                ("outcome", &self.describe_failure()),
+                ("count", &self.tried),
+                ("elapsed", &format!("{:.2?}", self.elapsed)),
+            ],
+        )
+    }
+}
 
-    let archive_bytes = std::fs::read(&args.zip)
-        .with_context(|| format!("Failed to read archive: {}", args.zip.display()))?;
+fn parse_args() -> Cli {
+    let argv: Vec<OsString> = std::env::args_os().collect();
+    if argv.get(1).is_none_or(|arg| arg != "--fcrackzip-compat") {
+        let cli = Cli::parse();
+        if cli.crack.as_ref().is_some_and(|args| args.fcrackzip_compat) {
+            Cli::command()
+                .error(
+                    ErrorKind::ArgumentConflict,
+                    "--fcrackzip-compat must be the first argument",
+                )
+                .exit();
+        }
+        return cli;
+    }
 
-    let candidates = load_wordlist(&args.wordlist)?;
-    if candidates.is_empty() {
-        bail!("Wordlist is empty");
+    match compat::translate_fcrackzip(&argv[2..]) {
+        Ok(native) => {
+            let mut cli = Cli::parse_from(argv[..1].iter().cloned().chain(native));
+            if let Some(args) = &mut cli.crack {
+                args.fcrackzip_compat = true;
+            }
+            cli
+        }
+        Err(error) => Cli::command()
+            .error(ErrorKind::ValueValidation, format!("{error:#}"))
+            .exit(),
     }
+}
 
-    let found = AtomicBool::new(false);
-    let winning_password: Mutex<Option<String>> = Mutex::new(None);
+fn main() -> ExitCode {
+    let cli = parse_args();
+    let mut args = match cli.command {
+        Some(Command::Wizard(wizard)) => match wizard::plan(&wizard) {
+            Ok(Some(argv)) => {
+                Cli::parse_from(std::iter::once(OsString::from("unzippoo")).chain(argv))
+                    .crack
+                    .expect("the wizard builds crack arguments")
+            }
+            Ok(None) => return ExitCode::SUCCESS,
+            Err(error) => {
+                eprintln!("Error: {error:?}");
+                return Status::Error.exit_code();
+            }
+        },
+        Some(command) => {
+            let outcome = match &command {
+                Command::Bench(bench) => bench::run(bench),
+                Command::Fetch(fetch) => fetch::run(fetch),
+                Command::MakeTestArchive(fixture) => fixture::run(fixture),
+                Command::RecoverPassword(recover) => zipcrypto::recover_password(recover),
+                Command::Rekey(rekey) => rewrite::rekey(rekey),
+                Command::Selftest(selftest) => selftest::run(selftest),
+                Command::Stats(stats) => stats::run(stats),
+                Command::Strip(strip) => rewrite::strip(strip),
+                Command::Triage(triage) => triage::run(triage),
+                Command::Wizard(_) => unreachable!("the wizard is handled above"),
+            };
+            return match outcome {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(error) => {
+                    eprintln!("Error: {error:?}");
+                    Status::Error.exit_code()
+                }
+            };
+        }
+        None => cli
+            .crack
+            .expect("clap requires the crack arguments without a subcommand"),
+    };
+    spool::set_memory_limit(args.memory_limit);
+    // Before anything opens the archives, --dry-run and --replay included.
+    let _stdin = match spool::stdin_archive(&args.zip) {
+        Ok(spool) => spool,
+        Err(error) => {
+            eprintln!("Error: {error:?}");
+            return Status::Error.exit_code();
+        }
+    };
+    if let Some(budget) = args.auto
+        && let Err(error) = auto::plan(&mut args, budget)
+    {
+        eprintln!("Error: {error:?}");
+        return Status::Error.exit_code();
+    }
+    if args.dry_run {
+        return match dry_run(&args) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(error) => {
+                eprintln!("Error: {error:?}");
+                Status::Error.exit_code()
+            }
+        };
+    }
+    i18n::install(args.lang);
+    // Before --replay, which reads checkpoints sealed with it.
+    if let Some(source) = &args.store_key {
+        match Vault::open(source) {
+            Ok(opened) => vault::install(opened),
+            Err(error) => {
+                eprintln!("Error: {error:?}");
+                return Status::Error.exit_code();
+            }
+        }
+    }
+    if let (Some(path), Some(index)) = (&args.replay, args.candidate_index) {
+        return match replay(&args, path, index) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(error) => {
+                eprintln!("Error: {error:?}");
+                Status::Error.exit_code()
+            }
+        };
+    }
+    if let Some(fraction) = args.sample {
+        return match sample::run(&args, fraction) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(error) => {
+                eprintln!("Error: {error:?}");
+                Status::Error.exit_code()
+            }
+        };
+    }
+    if args.huge_pages {
+        if !hugepages::available() {
+            eprintln!(
+                "--huge-pages has no effect: transparent huge pages are disabled or unsupported"
+            );
+        }
+        hugepages::enable();
+    }
+    if let Some(policy) = args.numa
+        && let Err(error) = numa::apply_memory_policy(policy)
+    {
+        eprintln!("Error: {error:?}");
+        return Status::Error.exit_code();
+    }
+    control::install_interrupt_handler();
+    if let Some(path) = &args.stop_file
+        && let Err(error) = control::watch_stop_file(path.clone())
+    {
+        eprintln!("Error: {error:?}");
+        return Status::Error.exit_code();
+    }
+    if batch::is_batch(&args) {
+        return batch::run(&args);
+    }
+    let zip = &args.zip[0];
     let started_at = Instant::now();
 
-    candidates.par_iter().for_each(|candidate| {
-        if found.load(Ordering::Relaxed) {
-            return;
+    let search = match with_session(&args, |session| search(&args, session, zip, args.threads)) {
+        Ok(search) => search,
+        Err(error) => {
+            eprintln!("Error: {error:?}");
+            write_result(&args, &error_result(zip, &error, started_at.elapsed()));
+            return Status::Error.exit_code();
         }
+    };
+    write_result(&args, &run_result(zip, &search));
+    record_found(&args, zip, &search);
 
-        match password_matches(&archive_bytes, candidate, args.target.as_deref()) {
-            Ok(true) => {
-                found.store(true, Ordering::Relaxed);
-                let mut guard = winning_password.lock().expect("poisoned mutex");
-                *guard = Some(candidate.clone());
+    // With --cat, stdout carries the entry alone, so only a short report goes to stderr.
+    if args.cat.is_some() {
+        return cat_recovered(&args, zip, &search);
+    }
+    match &search.password {
+        Some(password) => {
+            let password = shown(&args, password);
+            if args.fcrackzip_compat {
+                println!("PASSWORD FOUND!!!!: pw == {password}");
+            } else {
+                println!("{}", tr(Message::PasswordFound, &[("password", &password)]));
+                if let Some(keys) = internal_keys(&search)
+                    && !is_redacted(&args)
+                {
+                    println!("{}", tr(Message::InternalKeys, &[("keys", &keys)]));
+                }
             }
-            Ok(false) => {}
-            Err(error) => {
-                eprintln!("Error while trying \"{candidate}\": {error}");
+            println!(
+                "{}",
+                tr(
+                    Message::Tried,
+                    &[
+                        ("count", &search.tried),
+                        ("elapsed", &format!("{:.2?}", search.elapsed)),
+                    ],
+                )
+            );
+            if let Some(provenance) = &search.provenance {
+                println!("{}", tr(Message::FoundBy, &[("provenance", provenance)]));
+            }
+            report::print_stage_summary(&search.stages);
+            usage::print(&search.resources, args.verbose);
+            if let Some(timings) = &search.timings {
+                timings.print(is_redacted(&args));
+            }
+            if let Some(strength) = &search.strength {
+                strength::print(strength, !is_redacted(&args));
+            }
+            if let Some(password) = &search.password
+                && let Err(error) = after_recovery(&args, zip, &search.target, password)
+            {
+                eprintln!("Error: {error:?}");
+                return Status::Error.exit_code();
+            }
+            if args.copy
+                && let Some(password) = &search.password
+            {
+                copy_password(password, args.copy_timeout);
             }
         }
-    });
+        None => {
+            println!("{}", search.failure_summary());
+            report::print_stage_summary(&search.stages);
+            usage::print(&search.resources, args.verbose);
+            if let Some(timings) = &search.timings {
+                timings.print(is_redacted(&args));
+            }
+        }
+    }
+    search.status.exit_code()
+}
+
+fn cat_recovered(args: &Args, zip: &Path, search: &Search) -> ExitCode {
+    let (Some(password), Some(name)) = (&search.password, &args.cat) else {
+        eprintln!("{}", search.failure_summary());
+        return search.status.exit_code();
+    };
+    eprintln!(
+        "{}",
+        tr(
+            Message::CatFound,
+            &[
+                ("password", &shown(args, password)),
+                ("count", &search.tried),
+                ("elapsed", &format!("{:.2?}", search.elapsed)),
+            ],
+        )
+    );
+    match extract::cat(zip, &search.target.entries, name, password) {
+        Ok(()) => search.status.exit_code(),
+        Err(error) => {
+            eprintln!("Error: {error:?}");
+            Status::Error.exit_code()
+        }
+    }
+}
+
+/// Passwords stay out of printed output and events with --no-echo-password, and while the
+/// stores are encrypted unless revealed.
+fn is_redacted(args: &Args) -> bool {
+    args.no_echo_password || (args.store_key.is_some() && !args.reveal)
+}
+
+fn shown<'a>(args: &Args, password: &'a str) -> &'a str {
+    secret::shown(is_redacted(args), password)
+}
 
-    if let Some(password) = winning_password
-        .into_inner()
-        .expect("poisoned mutex during teardown")
+/// For a ZipCrypto target, the cipher state the recovered password leaves behind, which
+/// decrypts the archive's other ZipCrypto entries on its own.
+fn internal_keys(search: &Search) -> Option<Keys> {
+    let password = search.password.as_ref()?;
+    (search.target.entries[search.target.index].encryption == Encryption::ZipCrypto)
+        .then(|| Keys::from_password(password.as_bytes()))
+}
+
+fn run_result<'a>(zip: &Path, search: &'a Search) -> RunResult<'a> {
+    let mut result = RunResult::new(search.status, zip, search.tried, search.elapsed);
+    result.password = search.password.as_deref().map(String::as_str);
+    result.internal_keys = internal_keys(search).map(|keys| keys.to_string());
+    result.entry = Some(&search.target.entries[search.target.index].name);
+    result.stages = &search.stages;
+    result.provenance = search.provenance.as_ref();
+    result.strength = search.strength.as_ref();
+    result.resources = Some(&search.resources);
+    result.reason = match search.status {
+        Status::NotFound => Some("all candidates tried".to_owned()),
+        Status::Interrupted if control::stopped_by_file() => {
+            Some("stopped by --stop-file".to_owned())
+        }
+        Status::Interrupted => Some("interrupted by signal".to_owned()),
+        Status::BudgetExhausted => Some("time limit reached".to_owned()),
+        Status::Found | Status::Error => None,
+    };
+    result
+}
+
+fn error_result<'a>(zip: &Path, error: &anyhow::Error, elapsed: Duration) -> RunResult<'a> {
+    let mut result = RunResult::new(Status::Error, zip, 0, elapsed);
+    result.reason = Some(format!("{error:#}"));
+    result
+}
+
+/// Writes `--result-file` if requested; a failure here is reported but does not mask the outcome.
+fn write_result(args: &Args, result: &impl serde::Serialize) {
+    if let Some(path) = &args.result_file
+        && let Err(error) = report::write_result_file(path, result)
+    {
+        eprintln!("Error: {error:#}");
+    }
+}
+
+/// Appends a recovered password to `--potfile`; like the result file, a failure is only reported.
+fn record_found(args: &Args, zip: &Path, search: &Search) {
+    if let Some(path) = &args.potfile
+        && let Some(password) = &search.password
+        && let Err(error) = potfile::append(
+            path,
+            zip,
+            &search.target.entries[search.target.index].name,
+            password,
+            search.provenance.as_ref(),
+        )
     {
-        println!("Password found: {password}");
+        eprintln!("Error: {error:#}");
+    }
+}
+
+/// What every archive of a run shares: the candidate stages and the governor their workers
+/// take permits from, so pausing and throttling apply to the run as a whole.
+struct Session {
+    stages: Vec<Stage>,
+    governor: Arc<Governor>,
+    events: Option<Arc<Events>>,
+}
+
+impl Session {
+    /// The same controls and event stream with other candidate stages.
+    fn with_stages(&self, stages: Vec<Stage>) -> Session {
+        Session {
+            stages,
+            governor: Arc::clone(&self.governor),
+            events: self.events.clone(),
+        }
+    }
+}
+
+/// Loads the stages and starts the run-wide controls, which stay up until `run` returns.
+fn with_session<T>(args: &Args, run: impl FnOnce(&Session) -> Result<T>) -> Result<T> {
+    if args.threads == 0 {
+        bail!("--threads must be at least 1");
+    }
+    if args.chunk_size == 0 {
+        bail!("--chunk-size must be at least 1");
+    }
+    let available = cpus::available();
+    if args.threads > available {
+        eprintln!(
+            "--threads {} is more than the {available} CPU{} this process may use (CPU affinity \
+             and cgroup quotas); the extra threads only take turns",
+            args.threads,
+            if available == 1 { "" } else { "s" }
+        );
+    }
+    if args.cat.is_some() && args.events.is_some() && args.events_to.is_none() {
+        bail!("--cat writes the entry to stdout; send --events elsewhere with --events-to");
+    }
+
+    let events = args
+        .events
+        .map(|format| Events::open(format, args.events_to.as_deref()).map(Arc::new))
+        .transpose()?;
+    let stages = match load_stages(args) {
+        Ok(stages) => stages,
+        Err(error) => {
+            if let Some(events) = &events {
+                events.emit(&Event::Error {
+                    archive: None,
+                    message: format!("{error:#}"),
+                });
+            }
+            return Err(error);
+        }
+    };
+    let governor = Arc::new(Governor::new(args.threads));
+    #[cfg(unix)]
+    let _control_socket = args
+        .control_socket
+        .as_deref()
+        .map(|path| control::spawn_control_socket(path, Arc::clone(&governor)))
+        .transpose()?;
+    #[cfg(unix)]
+    let _keypress_guard = control::spawn_keypress_listener(Arc::clone(&governor));
+    throttle::spawn_throttle(
+        ThrottleConfig {
+            thermal_limit: args.thermal_limit,
+            load_ceiling: args.load_ceiling,
+        },
+        Arc::clone(&governor),
+        args.threads,
+    );
+    service::ready();
+    run(&Session {
+        stages,
+        governor,
+        events,
+    })
+}
+
+/// Attacks one archive with up to `threads` workers, reporting how it ended to `--events`.
+fn search(args: &Args, session: &Session, zip: &Path, threads: usize) -> Result<Search> {
+    let started_at = Instant::now();
+    let outcome = attack(args, session, zip, threads);
+    report_outcome(session, zip, started_at, &outcome);
+    outcome
+}
+
+/// Ends an archive's `--events` with its error, if any, and how it finished.
+fn report_outcome(session: &Session, zip: &Path, started_at: Instant, outcome: &Result<Search>) {
+    service::status(&format!(
+        "{}: {}",
+        zip.display(),
+        match outcome {
+            Ok(search) => search.status,
+            Err(_) => Status::Error,
+        }
+        .as_str()
+    ));
+    if let Some(events) = &session.events {
+        let (status, tried) = match outcome {
+            Ok(search) => (search.status, search.tried),
+            Err(error) => {
+                events.emit(&Event::Error {
+                    archive: Some(zip),
+                    message: format!("{error:#}"),
+                });
+                (Status::Error, 0)
+            }
+        };
+        events.emit(&Event::Finished {
+            archive: zip,
+            status,
+            tried,
+            elapsed_seconds: started_at.elapsed().as_secs_f64(),
+        });
+    }
+}
+
+fn attack(args: &Args, session: &Session, zip: &Path, threads: usize) -> Result<Search> {
+    let stages = &session.stages;
+    let governor = &session.governor;
+    let target = load_target(zip, args.target.as_deref(), &args.skip_entry, args.backend)?;
+    let verifier = &target.verifier;
+
+    let (mut threads, mut chunk_size) = (threads, args.chunk_size);
+    if args.auto_tune {
+        let tuning = tune::auto_tune(verifier, &stages[0].generator, threads)?;
         println!(
-            "Tried {} candidates in {:.2?}",
-            candidates.len(),
-            started_at.elapsed()
+            "Auto-tune: {} threads, chunk size {} ({:.0} candidates/s)",
+            tuning.threads, tuning.chunk_size, tuning.rate
+        );
+        threads = tuning.threads;
+        chunk_size = tuning.chunk_size;
+    }
+
+    let started_at = Instant::now();
+    let deadline = args.time_limit.map(|limit| started_at + limit);
+    let entry_name = &target.entries[target.index].name;
+    let stage_keys = checkpoint::stage_keys(stages);
+    let plan = sessions::checkpoint(args, zip, &target.entries, entry_name, stages)?;
+    let mut next_index = match &plan {
+        Some(plan) if plan.resume => match (
+            checkpoint::load(&plan.path, entry_name, &stage_keys),
+            &plan.session,
+        ) {
+            (Ok(next_index), None) => {
+                eprintln!("Resuming from candidate {next_index}");
+                next_index
+            }
+            (Ok(next_index), Some(name)) => {
+                eprintln!(
+                    "Resuming session {name} from candidate {next_index}, kept in {} (pass \
+                     --no-auto-resume to start over)",
+                    plan.path.display()
+                );
+                next_index
+            }
+            (Err(error), Some(name)) if args.session.is_none() => {
+                eprintln!("Starting session {name} over: {error:#}");
+                0
+            }
+            (Err(error), _) => return Err(error),
+        },
+        _ => 0,
+    };
+    let mut checkpointer = plan.as_ref().map(|plan| {
+        Checkpointer::new(
+            plan.path.clone(),
+            args.checkpoint_interval,
+            zip.display().to_string(),
+            entry_name.clone(),
+            stage_keys,
+            next_index,
+        )
+    });
+    let progress = Arc::new(Progress::new(stages, next_index));
+    if args.status_file.is_some() && stages.len() > 1 {
+        progress.set_costs(tune::stage_costs(verifier, stages));
+    }
+    let status_writer = args.status_file.clone().map(|path| {
+        status::spawn_status_writer(
+            path,
+            zip.display().to_string(),
+            entry_name.clone(),
+            Arc::clone(&progress),
+            Arc::clone(governor),
+        )
+    });
+    let timings = args.timings.then(Timings::new);
+    if let Some(path) = &args.survivors_in
+        && let (Some(recorded), _) = survivors::load(path)?
+        && recorded != *entry_name
+    {
+        bail!(
+            "{} holds survivors for \"{recorded}\", not \"{entry_name}\"; pass --target",
+            path.display()
         );
-        Ok(())
+    }
+    let survivor_log = args
+        .survivors_out
+        .as_deref()
+        .map(|path| SurvivorLog::create(path, entry_name))
+        .transpose()?;
+    let placement = match args.numa {
+        Some(policy) => numa::Placement::new(policy, verifier)?,
+        None => None,
+    };
+    if !args.force && args.time_limit.is_none() && args.survivors_in.is_none() {
+        let entry = &target.entries[target.index];
+        ensure_feasible(args, stages, verifier, entry, next_index, threads)?;
+    }
+    let searcher = Searcher::new(
+        verifier,
+        governor,
+        &progress,
+        threads,
+        chunk_size,
+        deadline,
+        args.retries,
+    )
+    .with_timings(timings.as_ref())
+    .with_redaction(is_redacted(args))
+    .with_placement(placement.as_ref())
+    .with_survivors(survivor_log.as_ref());
+
+    let mut stats = Vec::with_capacity(stages.len());
+    let mut last_progress = (Instant::now(), progress.tried());
+    if let Some((first, first_start)) = search::locate(stages, next_index) {
+        for (number, stage) in stages.iter().enumerate().skip(first) {
+            if searcher.is_finished() {
+                break;
+            }
+            if let Some(events) = &session.events {
+                events.emit(&Event::StageStarted {
+                    archive: zip,
+                    stage: number + 1,
+                    stages: stages.len(),
+                    source: &stage.source,
+                    keyspace: stage.generator.len(),
+                });
+            }
+            let offset = search::stage_offset(stages, number);
+            let start = if number == first { first_start } else { 0 };
+            if args.survivors_in.is_some() {
+                stats.push(searcher.confirm_stage(number, stage));
+                continue;
+            }
+            let stage_stats = searcher.run_stage(number, stage, start, |done| {
+                next_index = offset + done;
+                progress.set_coverage(match &stage.generator {
+                    Generator::Wordlist(words) => words.coverage(done),
+                    _ => None,
+                });
+                if let Some(checkpointer) = &mut checkpointer {
+                    checkpointer.record(next_index);
+                }
+                if (session.events.is_some() || service::is_supervised())
+                    && last_progress.0.elapsed() >= events::PROGRESS_INTERVAL
+                {
+                    let now = (Instant::now(), progress.tried());
+                    let rate =
+                        (now.1 - last_progress.1) as f64 / (now.0 - last_progress.0).as_secs_f64();
+                    let eta_seconds = progress.eta(rate).map(|(_, total)| total);
+                    if let Some(events) = &session.events {
+                        events.emit(&Event::Progress {
+                            archive: zip,
+                            stage: number + 1,
+                            tried: now.1,
+                            rate,
+                            eta_seconds,
+                            coverage: progress.coverage(),
+                        });
+                    }
+                    service::status(&format!(
+                        "{}: stage {}/{}, {} candidates tried, {rate:.0}/s{}{}",
+                        zip.display(),
+                        number + 1,
+                        stages.len(),
+                        now.1,
+                        progress
+                            .coverage()
+                            .map_or(String::new(), |coverage| format!(
+                                ", {:.1}% of the counts covered",
+                                coverage * 100.0
+                            )),
+                        eta_seconds.map_or(String::new(), |eta| format!(
+                            ", {} left",
+                            strength::describe(eta)
+                        ))
+                    ));
+                    last_progress = now;
+                }
+            })?;
+            stats.push(stage_stats);
+        }
+    }
+    if args.retry_skipped && !searcher.is_finished() {
+        // Every stage from the resume point ran to completion, so `stats` lines up with the tail.
+        let first = stages.len() - stats.len();
+        for (offset, stage_stats) in stats.iter_mut().enumerate() {
+            if !stage_stats.skipped.is_empty() {
+                searcher.retry_skipped(first + offset, &stages[first + offset], stage_stats);
+            }
+        }
+    }
+    if let Some(checkpointer) = &mut checkpointer {
+        checkpointer.finish(next_index);
+    }
+
+    let exhausted = searcher.is_exhausted();
+    let password = searcher.winner(stages);
+    let status = if password.is_some() {
+        Status::Found
+    } else if control::interrupted() {
+        Status::Interrupted
+    } else if exhausted {
+        Status::BudgetExhausted
     } else {
+        Status::NotFound
+    };
+    if let Some(writer) = status_writer {
+        writer.finish(status);
+    }
+    // Once settled, a session rerun should start over rather than resume past the password.
+    if let Some(plan) = &plan
+        && plan.session.is_some()
+        && dedup::is_conclusive(status)
+    {
+        let _ = fs::remove_file(&plan.path);
+    }
+    let strength = password
+        .as_deref()
+        .filter(|_| args.analyze_password)
+        .map(|password| {
+            let rank = searcher
+                .winner_position()
+                .filter(|&(number, _)| matches!(stages[number].generator, Generator::Wordlist(_)))
+                .map(|(_, index)| index + 1);
+            strength::analyze(password, rank)
+        });
+    let provenance = searcher.provenance(stages);
+    let mut resources = usage::measure(&searcher.worker_cpu(), started_at.elapsed());
+    resources.worker_time = progress.breakdown(governor.stalls());
+    drop(searcher);
+    if let (Some(log), Some(path)) = (survivor_log, &args.survivors_out) {
+        log.finish(path)?;
+    }
+    if let Some(events) = &session.events
+        && let Some(password) = &password
+    {
+        events.emit(&Event::CandidateFound {
+            archive: zip,
+            entry: entry_name,
+            password: shown(args, password),
+            provenance: provenance.as_ref(),
+        });
+    }
+    Ok(Search {
+        status,
+        timings,
+        provenance,
+        strength,
+        password,
+        target,
+        tried: stats.iter().map(|stage| stage.tried).sum(),
+        stages: stats,
+        elapsed: started_at.elapsed(),
+        resources,
+    })
+}
+
+/// Validates the whole plan and projects its duration from the throughput of made-up
+/// candidates, so not a single real one is tested.
+fn dry_run(args: &Args) -> Result<()> {
+    if batch::is_batch(args) {
+        batch::validate(args)?;
+    }
+    if args.threads == 0 {
+        bail!("--threads must be at least 1");
+    }
+    if args.chunk_size == 0 {
+        bail!("--chunk-size must be at least 1");
+    }
+    let stages = load_stages(args)?;
+    for entry in manifest::queue(args)? {
+        let zip = &entry.archive;
+        let entry_args = entry.apply(args);
+        let args = entry_args.as_ref().unwrap_or(args);
+        let entry_stages = entry.has_hints().then(|| load_stages(args)).transpose()?;
+        let stages = entry_stages.as_ref().unwrap_or(&stages);
+        let keyspace = stages.iter().fold(0u64, |total, stage| {
+            total.saturating_add(stage.generator.len())
+        });
+        let target = load_target(zip, args.target.as_deref(), &args.skip_entry, args.backend)?;
+        let entry = &target.entries[target.index];
+        let single = tune::probe_rate(&target.verifier);
+        let rate = single * args.threads as f64;
+        println!(
+            "{}: {} ({}, {}), about {rate:.0} candidates/s with {} thread{}",
+            zip.display(),
+            entry.name,
+            entry.compression,
+            entry.encryption,
+            args.threads,
+            if args.threads == 1 { "" } else { "s" }
+        );
+        println!(
+            "  Each candidate takes about {:.2?} of one thread: {}",
+            Duration::from_secs_f64(1.0 / single),
+            verify::cost_model(entry.encryption)
+        );
+        for (number, stage) in stages.iter().enumerate() {
+            let len = stage.generator.len();
+            println!(
+                "  {}. {}: {len} candidates, {}",
+                number + 1,
+                stage.source,
+                strength::describe(len as f64 / rate)
+            );
+        }
         println!(
-            "Password not found in the provided wordlist ({} candidates tried) after {:.2?}",
-            candidates.len(),
-            started_at.elapsed()
+            "  Total: {keyspace} candidates, {} at worst",
+            strength::describe(keyspace as f64 / rate)
         );
-        std::process::exit(1);
+        if let Some(limit) = args.time_limit
+            && keyspace as f64 / rate > limit.as_secs_f64()
+        {
+            println!(
+                "  The --time-limit of {limit:.0?} covers about {:.1}% of the plan",
+                100.0 * rate * limit.as_secs_f64() / keyspace as f64
+            );
+        }
+        if keyspace as f64 / rate >= HOPELESS && args.time_limit.is_none() && !args.force {
+            println!("  That is more than 10 years, so the attack only starts with --force");
+        }
     }
+    Ok(())
 }
 
-fn load_wordlist(path: &Path) -> Result<Vec<String>> {
-    let file =
-        File::open(path).with_context(|| format!("Failed to open wordlist: {}", path.display()))?;
-    let reader = BufReader::new(file);
+/// Projected durations past which an attack needs `--force`: ten years.
+const HOPELESS: f64 = 10.0 * 31_557_600.0;
 
-    let mut entries = Vec::new();
-    for line in reader.lines() {
-        let line = line?;
-        let trimmed = line.trim();
-        if !trimmed.is_empty() {
-            entries.push(trimmed.to_owned());
+/// Refuses an attack that would outlast any reasonable wait at this machine's rate on `entry`,
+/// naming what to change; `--force` or a `--time-limit` lets it run anyway.
+fn ensure_feasible(
+    args: &Args,
+    stages: &[Stage],
+    verifier: &Verifier,
+    entry: &archive::Entry,
+    next_index: u64,
+    threads: usize,
+) -> Result<()> {
+    let left = search::stage_offset(stages, stages.len()).saturating_sub(next_index);
+    // Even at one candidate a second this finishes in time, so skip measuring the rate.
+    if (left as f64) < HOPELESS {
+        return Ok(());
+    }
+    let rate = tune::probe_rate(verifier) * threads as f64;
+    let projected = left as f64 / rate;
+    if projected < HOPELESS {
+        return Ok(());
+    }
+    let costliest = stages
+        .iter()
+        .max_by_key(|stage| stage.generator.len())
+        .expect("a hopeless attack has stages");
+    let mut advice = vec![match costliest.generator {
+        Generator::BruteForce(_) => {
+            "shorten --length or narrow --charset, and pass --known-prefix, --known-suffix, or \
+             --known-contains for any part of the password you remember"
+        }
+        Generator::Affixed(_) => "attach fewer --affix-digits, --affix-years, or --affix-symbols",
+        Generator::Phrases(_) => "lower --phrase-count or use a shorter --phrase-words list",
+        Generator::Wordlist(_) | Generator::List(_) => {
+            "use a smaller, better-targeted wordlist, or --lines for the part worth trying"
         }
+    }];
+    if matches!(entry.encryption, Encryption::Aes { .. }) && args.backend == Backend::Cpu {
+        advice.push("use --backend simd, which derives AES keys eight at a time");
+    }
+    advice.push("let --auto plan an attack that fits a time budget");
+    advice.push("move the attack to a GPU cracker such as hashcat");
+    bail!(
+        "This attack is projected to take {} at about {rate:.0} candidates/s; its largest stage \
+         is {} with {} candidates. To make it feasible:\n  - {}\nPass --force to run it anyway, \
+         or --time-limit to bound it.",
+        strength::describe(projected),
+        costliest.source,
+        costliest.generator.len(),
+        advice.join("\n  - ")
+    )
+}
+
+/// Rebuilds the candidate at global `index` of the attack a checkpoint was written for and
+/// tests it again, for chasing suspected false negatives and generator bugs.
+fn replay(args: &Args, path: &Path, index: u64) -> Result<()> {
+    if batch::is_batch(args) {
+        bail!("--replay needs a single --zip");
+    }
+    let stages = load_stages(args)?;
+    let checkpoint = checkpoint::read(path, &checkpoint::stage_keys(&stages))?;
+    let Some((number, local)) = search::locate(&stages, index) else {
+        bail!(
+            "Candidate {index} is past the end of the attack, which has {} candidates",
+            search::stage_offset(&stages, stages.len())
+        );
+    };
+    let mut buf = SecretBuf::new();
+    let candidate = stages[number].generator.candidate(local, &mut buf);
+
+    let zip = &args.zip[0];
+    let target = load_target(zip, Some(&checkpoint.entry), &[], args.backend)?;
+    let passed = target.verifier.check_batch(&[candidate])[0];
+    let confirmed = passed && target.verifier.confirm(candidate)?;
+    let outcome = match (passed, confirmed) {
+        (false, _) => "rejected by the fast check",
+        (true, false) => "passed the fast check, rejected by full verification",
+        (true, true) => "this is the password",
+    };
+    let shown_candidate = if confirmed {
+        shown(args, candidate)
+    } else {
+        candidate
+    };
+    println!("Candidate {index}: {shown_candidate:?}");
+    println!("  From {}", search::provenance(&stages, number, local));
+    if index < checkpoint.next_index {
+        println!(
+            "  Tested before the checkpoint was written (it resumes at {})",
+            checkpoint.next_index
+        );
+    } else {
+        println!(
+            "  Not yet tested when the checkpoint was written (it resumes at {})",
+            checkpoint.next_index
+        );
     }
+    println!(
+        "  Against \"{}\" in {}: {outcome}",
+        checkpoint.entry,
+        zip.display()
+    );
+    Ok(())
+}
 
-    Ok(entries)
+/// A clipboard failure is only a warning: the password has already been printed.
+fn copy_password(password: &str, timeout: Option<Duration>) {
+    match clipboard::copy(password) {
+        Ok(helper) => println!("Copied password to clipboard ({helper})"),
+        Err(error) => {
+            eprintln!("Could not copy password to clipboard: {error:#}");
+            return;
+        }
+    }
+    if let Some(timeout) = timeout {
+        println!("Clearing clipboard in {timeout:.0?}");
+        match clipboard::clear_after(timeout) {
+            Ok(()) => println!("Clipboard cleared"),
+            Err(error) => eprintln!("Could not clear clipboard: {error:#}"),
+        }
+    }
 }
 
-fn password_matches(archive_bytes: &[u8], password: &str, target: Option<&str>) -> Result<bool> {
-    let cursor = std::io::Cursor::new(archive_bytes);
-    let mut archive = ZipArchive::new(cursor)?;
+/// Runs the optional `--preview`, `--sniff`, and `--extract` steps once the password is known.
+fn after_recovery(args: &Args, zip: &Path, target: &Target, password: &str) -> Result<()> {
+    if let Some(len) = args.preview {
+        preview::print_preview(
+            &target.entries,
+            target.index,
+            target.verifier.entry_archive(),
+            password,
+            len,
+        )?;
+    }
+    if args.sniff {
+        sniff::print_report(zip, &target.entries, password)?;
+    }
+    if let Some(dest) = &args.extract {
+        let selected;
+        let entries = if args.extract_entry.is_empty() {
+            &target.entries
+        } else {
+            selected = extract::select(&target.entries, &args.extract_entry)?;
+            &selected
+        };
+        extract::extract_all(
+            zip,
+            entries,
+            password,
+            dest,
+            ExtractOptions {
+                max_size: args.max_extract_size,
+                on_duplicate: args.on_duplicate,
+            },
+        )?;
+    }
+    Ok(())
+}
 
-    let target_index = match target {
-        Some(name) => {
-            let index = (0..archive.len()).find(|&i| match archive.by_index(i) {
-                Ok(file) => !file.is_dir() && file.name() == name,
-                Err(_) => false,
-            });
-            index.ok_or_else(|| anyhow!("Target file \"{name}\" not found in archive"))?
-        }
-        None => (0..archive.len())
-            .find(|&i| match archive.by_index(i) {
-                Ok(file) => !file.is_dir(),
-                Err(_) => false,
-            })
-            .ok_or_else(|| anyhow!("Archive contains no files to test"))?,
+/// Near misses of `--almost`, then wordlists in the order given, then their Unicode variants,
+/// then the same wordlists with affixes, then passphrases, then the brute-force stage if a
+/// charset was given.
+fn load_stages(args: &Args) -> Result<Vec<Stage>> {
+    let hints = Hints {
+        prefix: args.known_prefix.clone().unwrap_or_default(),
+        suffix: args.known_suffix.clone().unwrap_or_default(),
+        contains: args.known_contains.clone().unwrap_or_default(),
     };
+    let affixes = AffixSpec {
+        years: args.affix_years,
+        digits: args.affix_digits,
+        symbols: args.affix_symbols.clone().unwrap_or_default(),
+    };
+    let mut affixed = Vec::new();
+    let mut unicode_stages = Vec::new();
+    let mut stages = Vec::new();
+    let mut seen = Seen::new(args.dedupe);
+    let excluded = load_exclusions(args)?;
+    if let Some(path) = &args.survivors_in {
+        let (_, survivors) = survivors::load(path)?;
+        if survivors.is_empty() {
+            bail!("{} holds no survivors", path.display());
+        }
+        stages.push(Stage {
+            source: format!("survivors {}", path.display()),
+            generator: Generator::List(survivors),
+        });
+        return Ok(stages);
+    }
+    if let Some(password) = &args.password {
+        seen.insert(password);
+        let mut given = Candidates::default();
+        given.push(password);
+        stages.push(Stage {
+            source: "--password".to_owned(),
+            generator: Generator::List(given),
+        });
+    }
+    if let Some(almost) = &args.almost {
+        let mut variants = fuzzy::variants(almost, args.distance)?;
+        let mut source = format!(
+            "within {} edit{} of {almost:?}",
+            args.distance,
+            if args.distance == 1 { "" } else { "s" }
+        );
+        if !hints.is_empty() {
+            variants.retain(|candidate| hints.matches(candidate));
+            if variants.is_empty() {
+                bail!("No candidate near {almost:?} fits the known fragments ({hints})");
+            }
+            source = format!("{source} ({hints})");
+        }
+        let before = variants.len();
+        variants.retain(|candidate| !excluded.contains(candidate));
+        note_excluded(&mut source, before - variants.len());
+        let before = variants.len();
+        variants.retain(|candidate| seen.insert(candidate));
+        note_repeats(&mut source, before - variants.len());
+        stages.push(Stage {
+            source,
+            generator: Generator::List(variants),
+        });
+    }
+    if args.auto.is_some() {
+        let mut quick = auto::quick_list();
+        quick.retain(|candidate| {
+            hints.matches(candidate) && !excluded.contains(candidate) && seen.insert(candidate)
+        });
+        stages.push(Stage {
+            source: "common passwords".to_owned(),
+            generator: Generator::List(quick),
+        });
+    }
+    for path in &args.wordlist {
+        let wordlist = wordlist::load(&fetch::resolve_wordlist(path)?)?;
+        let stats = &wordlist.stats;
+        if args.verbose && wordlist.words.is_mapped() {
+            eprintln!(
+                "Indexed {} (memory-mapped, {}): {} lines, {} bytes in {:.2?}",
+                path.display(),
+                wordlist.format,
+                stats.lines,
+                stats.decoded_bytes,
+                stats.elapsed,
+            );
+        } else if args.verbose {
+            eprintln!(
+                "Loaded {} ({}, {}): {} lines, {} bytes decoded in {:.2?}{}; reader stalled {} \
+                 times ({:.2?}), splitter stalled {} times ({:.2?})",
+                path.display(),
+                wordlist.compression,
+                wordlist.format,
+                stats.lines,
+                stats.decoded_bytes,
+                stats.elapsed,
+                if wordlist.words.is_spilled() {
+                    ", spilled to disk past --memory-limit"
+                } else {
+                    ""
+                },
+                stats.producer_stalls,
+                stats.producer_stalled,
+                stats.consumer_stalls,
+                stats.consumer_stalled,
+            );
+        }
+        if wordlist.words.is_empty() {
+            bail!("Wordlist is empty: {}", path.display());
+        }
+        let mut words = wordlist.words;
+        shape_words(args, path, &mut words)?;
+        let mut listed = format!("wordlist {}", path.display());
+        if let Some(lines) = args.lines {
+            listed = format!("{listed} lines {lines}");
+        }
+        if args.counts {
+            listed = format!("{listed} by count");
+        }
+        let mut source = listed.clone();
+        // Fragments filter the plain words only, so the affixed stage needs its own index into
+        // the same loaded list.
+        let mut unfiltered = None;
+        if !affixes.is_empty() && !hints.is_empty() {
+            let mut words = words.clone();
+            let mut seen = Seen::new(args.dedupe);
+            if seen.is_enabled() {
+                words.retain(|candidate| seen.insert(candidate));
+            }
+            unfiltered = Some(Arc::new(words));
+        }
+        if args.unicode_variants {
+            let mut variants = unicode::variants(&words);
+            variants.retain(|candidate| hints.matches(candidate));
+            if args.verbose {
+                eprintln!(
+                    "{} Unicode variants of the non-ASCII candidates in {}",
+                    variants.len(),
+                    path.display()
+                );
+            }
+            if !variants.is_empty() {
+                unicode_stages.push((
+                    format!("Unicode variants of wordlist {}", path.display()),
+                    variants,
+                ));
+            }
+        }
+        if !hints.is_empty() {
+            let before = words.len();
+            words.retain(|candidate| hints.matches(candidate));
+            if words.is_empty() {
+                bail!(
+                    "No candidate in {} fits the known fragments ({hints})",
+                    path.display()
+                );
+            }
+            if args.verbose {
+                eprintln!(
+                    "Kept {} of {before} candidates from {} that fit the known fragments",
+                    words.len(),
+                    path.display()
+                );
+            }
+            source = format!("{source} ({hints})");
+        }
+        if excluded.is_enabled() {
+            let before = words.len();
+            words.retain(|candidate| !excluded.contains(candidate));
+            note_excluded(&mut source, before - words.len());
+        }
+        if seen.is_enabled() {
+            let before = words.len();
+            words.retain(|candidate| seen.insert(candidate));
+            note_repeats(&mut source, before - words.len());
+        }
+        let words = Arc::new(words);
+        affixed.push((listed, unfiltered.unwrap_or_else(|| Arc::clone(&words))));
+        stages.push(Stage {
+            source,
+            generator: Generator::Wordlist(words),
+        });
+    }
+    for (mut source, mut variants) in unicode_stages {
+        let before = variants.len();
+        variants.retain(|candidate| !excluded.contains(candidate));
+        note_excluded(&mut source, before - variants.len());
+        let before = variants.len();
+        variants.retain(|candidate| seen.insert(candidate));
+        note_repeats(&mut source, before - variants.len());
+        stages.push(Stage {
+            source,
+            generator: Generator::List(variants),
+        });
+    }
+    if !affixes.is_empty() {
+        if affixed.is_empty() {
+            bail!("--affix-years, --affix-digits, and --affix-symbols need a --wordlist");
+        }
+        for (listed, words) in affixed {
+            stages.push(Stage {
+                source: format!(
+                    "{listed} with {} {affixes}",
+                    match args.affix_position {
+                        AffixPosition::Suffix => "suffixes",
+                        AffixPosition::Prefix => "prefixes",
+                        AffixPosition::Both => "prefixes and suffixes",
+                    }
+                ),
+                generator: Generator::Affixed(Affixed::new(words, &affixes, args.affix_position)?),
+            });
+        }
+    }
+    if let Some(path) = &args.phrase_words {
+        let words = wordlist::load(&fetch::resolve_wordlist(path)?)?.words;
+        if words.is_empty() {
+            bail!("Wordlist is empty: {}", path.display());
+        }
+        let (min_words, max_words) = args.phrase_count;
+        let counts = if min_words == max_words {
+            min_words.to_string()
+        } else {
+            format!("{min_words}-{max_words}")
+        };
+        stages.push(Stage {
+            source: format!("passphrases of {counts} words from {}", path.display()),
+            generator: Generator::Phrases(Phrases::new(
+                words,
+                &args.separators,
+                min_words,
+                max_words,
+            )?),
+        });
+    }
+    if let Some(charset) = &args.charset {
+        let (min_len, max_len) = args.length;
+        let lengths = if min_len == max_len {
+            min_len.to_string()
+        } else {
+            format!("{min_len}-{max_len}")
+        };
+        let mut source = format!("brute force {} length {lengths}", charset.spec);
+        if !hints.is_empty() {
+            source = format!("{source} ({hints})");
+        }
+        let mut brute = BruteForce::new(charset.chars.clone(), min_len, max_len, hints.clone())?;
+        if !args.train.is_empty() {
+            let vault = args.store_key.as_ref().map(Vault::open).transpose()?;
+            let passwords = stats::recovered_passwords(&args.train, vault.as_ref())?;
+            // The count is part of the source, so a checkpoint taken before more passwords
+            // were recovered is not resumed in a different order.
+            source = format!(
+                "{source}, ordered by {} recovered password{}",
+                passwords.len(),
+                if passwords.len() == 1 { "" } else { "s" }
+            );
+            brute = brute.ordered(generate::trained_orders(
+                &charset.chars,
+                &passwords,
+                &hints,
+                max_len,
+            ));
+        }
+        stages.push(Stage {
+            source,
+            generator: Generator::BruteForce(brute),
+        });
+    }
+    if stages.is_empty() {
+        bail!("No candidate source given");
+    }
+    Ok(stages)
+}
+
+/// Narrows a freshly loaded wordlist to `--lines` and orders it by `--counts`.
+fn shape_words(args: &Args, path: &Path, words: &mut Words) -> Result<()> {
+    if let Some(lines) = args.lines {
+        words.keep_lines(lines);
+        if words.is_empty() {
+            bail!("No candidate in {} is on lines {lines}", path.display());
+        }
+    }
+    if args.counts {
+        words
+            .sort_by_counts()
+            .with_context(|| format!("Invalid counted wordlist: {}", path.display()))?;
+    }
+    Ok(())
+}
 
-    let mut file = match archive.by_index_decrypt(target_index, password.as_bytes()) {
-        Ok(file) => file,
-        Err(ZipError::InvalidPassword) => return Ok(false),
-        Err(error) => return Err(error.into()),
+/// The candidates of `--exclude-wordlist` and `--exclude-potfile`, matched exactly; empty and
+/// disabled when neither is given.
+fn load_exclusions(args: &Args) -> Result<Seen> {
+    if args.exclude_wordlist.is_empty() && args.exclude_potfile.is_empty() {
+        return Ok(Seen::new(Dedupe::Off));
+    }
+    let mut excluded = Seen::new(Dedupe::Exact);
+    for path in &args.exclude_wordlist {
+        let words = wordlist::load(&fetch::resolve_wordlist(path)?)?.words;
+        for index in 0..words.len() {
+            excluded.insert(words.get(index));
+        }
+        if args.verbose {
+            eprintln!(
+                "Excluding the {} candidates of {}",
+                words.len(),
+                path.display()
+            );
+        }
+    }
+    if !args.exclude_potfile.is_empty() {
+        let vault = args.store_key.as_ref().map(Vault::open).transpose()?;
+        for password in stats::recovered_passwords(&args.exclude_potfile, vault.as_ref())? {
+            excluded.insert(&password);
+        }
+    }
+    Ok(excluded)
+}
+
+/// Says in a stage's source how many of its candidates the exclusion lists skipped.
+fn note_excluded(source: &mut String, skipped: usize) {
+    if skipped > 0 {
+        *source = format!("{source} ({skipped} excluded)");
+    }
+}
+
+/// Says in a stage's source how many of its candidates `--dedupe` skipped.
+fn note_repeats(source: &mut String, skipped: usize) {
+    if skipped > 0 {
+        *source = format!(
+            "{source} ({skipped} repeat{} skipped)",
+            if skipped == 1 { "" } else { "s" }
+        );
+    }
+}
+
+/// The archive listing and the selected entry, isolated as a single-entry archive.
+struct Target {
+    entries: Vec<archive::Entry>,
+    index: usize,
+    verifier: Verifier,
+}
+
+fn load_target(
+    path: &Path,
+    target: Option<&str>,
+    skip: &[String],
+    backend: Backend,
+) -> Result<Target> {
+    let mut file = spool::open_archive(path)?;
+    let entries = match archive::read_central_directory(&mut file) {
+        Ok(directory) => directory.entries,
+        Err(error) => {
+            eprintln!(
+                "Central directory of {} is unreadable ({error:#}); scanning for local file headers",
+                path.display()
+            );
+            let recovery = archive::scan_local_headers(&mut file)?;
+            report_recovery(&recovery);
+            if recovery.entries.is_empty() {
+                bail!("No salvageable entries found in {}", path.display());
+            }
+            recovery.entries
+        }
     };
+    let index = archive::select_target(&entries, target, skip)?;
+    entries[index].ensure_verifiable()?;
+    let entry_archive = archive::extract_entry_archive(&mut file, &entries[index])?;
+    let verifier = Verifier::new(entry_archive, &entries[index], backend)?;
+    Ok(Target {
+        entries,
+        index,
+        verifier,
+    })
+}
 
-    let mut buffer = [0u8; 1];
-    match file.read(&mut buffer) {
-        Ok(_) => Ok(true),
-        Err(error) => Err(error.into()),
+fn report_recovery(recovery: &archive::Recovery) {
+    eprintln!("Recovered {} entries:", recovery.entries.len());
+    for entry in &recovery.entries {
+        let status = match entry.ensure_verifiable() {
+            Ok(()) => "usable".to_owned(),
+            Err(error) => format!("{error:#}"),
+        };
+        eprintln!(
+            "  {} ({}, {}): {status}",
+            entry.name, entry.compression, entry.encryption
+        );
+    }
+    for (offset, reason) in &recovery.damaged {
+        eprintln!("  damaged header at offset {offset}: {reason}");
     }
 }
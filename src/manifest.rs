@@ -0,0 +1,170 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+
+use crate::{Args, paths};
+
+/// One archive of a batch and whatever should differ from the command line for it.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Entry {
+    pub archive: PathBuf,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub known_prefix: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub known_suffix: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub known_contains: Option<String>,
+    /// Higher goes first; ties keep their order.
+    pub priority: i64,
+}
+
+impl Entry {
+    pub fn new(archive: &Path) -> Self {
+        Self {
+            archive: archive.to_path_buf(),
+            ..Self::default()
+        }
+    }
+
+    /// Whether the entry changes the hints, and so needs its own candidate stages.
+    pub fn has_hints(&self) -> bool {
+        self.known_prefix.is_some() || self.known_suffix.is_some() || self.known_contains.is_some()
+    }
+
+    /// Two entries attack the same way when everything but the archive and priority agrees.
+    pub fn same_plan(&self, other: &Entry) -> bool {
+        (
+            &self.target,
+            &self.known_prefix,
+            &self.known_suffix,
+            &self.known_contains,
+        ) == (
+            &other.target,
+            &other.known_prefix,
+            &other.known_suffix,
+            &other.known_contains,
+        )
+    }
+
+    /// The command line with this entry's overrides applied, or `None` if it has none.
+    pub fn apply(&self, args: &Args) -> Option<Args> {
+        if self.target.is_none() && !self.has_hints() {
+            return None;
+        }
+        let mut args = args.clone();
+        let replace = |value: &mut Option<String>, with: &Option<String>| {
+            if with.is_some() {
+                value.clone_from(with);
+            }
+        };
+        replace(&mut args.target, &self.target);
+        replace(&mut args.known_prefix, &self.known_prefix);
+        replace(&mut args.known_suffix, &self.known_suffix);
+        replace(&mut args.known_contains, &self.known_contains);
+        Some(args)
+    }
+}
+
+/// Every `--zip` archive and then every `--manifest` entry, highest priority first.
+pub fn queue(args: &Args) -> Result<Vec<Entry>> {
+    let mut queue: Vec<Entry> = args.zip.iter().map(|zip| Entry::new(zip)).collect();
+    if let Some(path) = &args.manifest {
+        queue.extend(load(path)?);
+    }
+    queue.sort_by_key(|entry| std::cmp::Reverse(entry.priority));
+    Ok(queue)
+}
+
+/// Reads a JSON array of entries, or CSV with a header row naming the same fields. Relative
+/// archive paths are taken relative to the manifest.
+pub fn load(path: &Path) -> Result<Vec<Entry>> {
+    let raw = fs::read_to_string(paths::extended(path))
+        .with_context(|| format!("Failed to read manifest: {}", path.display()))?;
+    let mut entries = if raw.trim_start().starts_with('[') {
+        serde_json::from_str(&raw)
+            .with_context(|| format!("Invalid manifest: {}", path.display()))?
+    } else {
+        parse_csv(&raw).with_context(|| format!("Invalid manifest: {}", path.display()))?
+    };
+    let dir = path.parent().unwrap_or(Path::new(""));
+    for entry in &mut entries {
+        if entry.archive.as_os_str().is_empty() {
+            bail!(
+                "Manifest {} has an entry without an archive",
+                path.display()
+            );
+        }
+        entry.archive = dir.join(&entry.archive);
+    }
+    Ok(entries)
+}
+
+fn parse_csv(raw: &str) -> Result<Vec<Entry>> {
+    let mut rows = raw
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty());
+    let Some((_, header)) = rows.next() else {
+        return Ok(Vec::new());
+    };
+    let columns = split_csv(header)?;
+    rows.map(|(number, line)| {
+        let cells = split_csv(line).with_context(|| format!("Line {}", number + 1))?;
+        if cells.len() > columns.len() {
+            bail!("Line {} has more cells than the header", number + 1);
+        }
+        let mut entry = Entry::default();
+        for (column, cell) in columns.iter().zip(cells) {
+            let value = Some(cell.clone()).filter(|cell| !cell.is_empty());
+            match column.trim() {
+                "archive" => entry.archive = PathBuf::from(cell),
+                "target" => entry.target = value,
+                "known_prefix" => entry.known_prefix = value,
+                "known_suffix" => entry.known_suffix = value,
+                "known_contains" => entry.known_contains = value,
+                "priority" => {
+                    entry.priority = match value {
+                        Some(value) => value.trim().parse().with_context(|| {
+                            format!("Line {}: invalid priority \"{value}\"", number + 1)
+                        })?,
+                        None => 0,
+                    }
+                }
+                other => bail!("Unknown manifest column \"{other}\""),
+            }
+        }
+        Ok(entry)
+    })
+    .collect()
+}
+
+/// Splits one line on commas, honouring double-quoted cells with `""` for a literal quote.
+fn split_csv(line: &str) -> Result<Vec<String>> {
+    let mut cells = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        let cell = cells.last_mut().expect("never empty");
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                cell.push('"');
+            }
+            '"' if quoted => quoted = false,
+            '"' if cell.is_empty() => quoted = true,
+            ',' if !quoted => cells.push(String::new()),
+            c => cell.push(c),
+        }
+    }
+    if quoted {
+        bail!("Unterminated quoted cell");
+    }
+    Ok(cells)
+}
@@ -0,0 +1,208 @@
+use std::{fs, thread};
+
+use anyhow::{Result, bail};
+
+use crate::verify::Verifier;
+
+/// How workers and their memory are laid out across NUMA nodes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Policy {
+    /// Spread workers over every node, each pinned to its node's CPUs and reading a copy of
+    /// the target state allocated there.
+    Auto,
+    /// Leave workers unpinned and interleave memory pages across the nodes.
+    Interleave,
+    /// Run every worker on one node and prefer its memory.
+    Node(usize),
+}
+
+pub fn parse_policy(value: &str) -> Result<Policy, String> {
+    match value {
+        "auto" => Ok(Policy::Auto),
+        "interleave" => Ok(Policy::Interleave),
+        _ => value
+            .strip_prefix("node:")
+            .and_then(|node| node.parse().ok())
+            .map(Policy::Node)
+            .ok_or_else(|| {
+                format!("invalid NUMA policy \"{value}\" (expected auto, interleave, or node:N)")
+            }),
+    }
+}
+
+/// A NUMA node and the CPUs it holds.
+struct Node {
+    id: usize,
+    cpus: Vec<usize>,
+}
+
+/// The machine's nodes. Where the topology is not exposed, as outside Linux, every CPU counts
+/// as node 0.
+fn topology() -> Vec<Node> {
+    let single = || {
+        vec![Node {
+            id: 0,
+            cpus: (0..num_cpus::get()).collect(),
+        }]
+    };
+    let Ok(dir) = fs::read_dir("/sys/devices/system/node") else {
+        return single();
+    };
+    let mut nodes: Vec<Node> = dir
+        .flatten()
+        .filter_map(|entry| {
+            let id = entry
+                .file_name()
+                .to_str()?
+                .strip_prefix("node")?
+                .parse()
+                .ok()?;
+            let cpulist = fs::read_to_string(entry.path().join("cpulist")).ok()?;
+            let cpus = parse_cpulist(cpulist.trim())?;
+            // Memory-only nodes have no CPUs to run workers on.
+            (!cpus.is_empty()).then_some(Node { id, cpus })
+        })
+        .collect();
+    if nodes.is_empty() {
+        return single();
+    }
+    nodes.sort_by_key(|node| node.id);
+    nodes
+}
+
+/// Parses the kernel's CPU list format, like `0-3,8-11`.
+fn parse_cpulist(list: &str) -> Option<Vec<usize>> {
+    let mut cpus = Vec::new();
+    for range in list.split(',').filter(|range| !range.is_empty()) {
+        let (first, last): (usize, usize) = match range.split_once('-') {
+            Some((first, last)) => (first.parse().ok()?, last.parse().ok()?),
+            None => {
+                let cpu = range.parse().ok()?;
+                (cpu, cpu)
+            }
+        };
+        cpus.extend(first..=last);
+    }
+    Some(cpus)
+}
+
+/// Applies the memory side of `policy` to this thread and every thread it starts afterwards,
+/// so it should run before wordlists and other candidate sources are loaded.
+pub fn apply_memory_policy(policy: Policy) -> Result<()> {
+    let nodes = topology();
+    let ids: Vec<usize> = match policy {
+        Policy::Auto => return Ok(()),
+        Policy::Interleave => nodes.iter().map(|node| node.id).collect(),
+        Policy::Node(id) => vec![find(&nodes, id)?.id],
+    };
+    // With one node there is nothing to prefer or interleave, and kernels built without NUMA
+    // support reject the call.
+    if nodes.len() < 2 {
+        return Ok(());
+    }
+    set_mempolicy(policy, &ids)
+}
+
+/// Where each worker of a search runs, and the per-node copies of the target state they read.
+pub struct Placement {
+    nodes: Vec<(Vec<usize>, Verifier)>,
+}
+
+impl Placement {
+    /// `None` when the policy does not pin workers, or there is only one node to pin them to.
+    pub fn new(policy: Policy, verifier: &Verifier) -> Result<Option<Self>> {
+        let nodes = topology();
+        let chosen: Vec<&Node> = match policy {
+            Policy::Interleave => return Ok(None),
+            Policy::Node(id) => vec![find(&nodes, id)?],
+            Policy::Auto => nodes.iter().collect(),
+        };
+        if nodes.len() < 2 {
+            return Ok(None);
+        }
+        // Each copy is made by a thread already pinned to the node, so first-touch allocation
+        // puts its pages in that node's memory.
+        let nodes = thread::scope(|scope| {
+            let copies: Vec<_> = chosen
+                .iter()
+                .map(|node| {
+                    scope.spawn(|| {
+                        pin(&node.cpus);
+                        (node.cpus.clone(), verifier.clone())
+                    })
+                })
+                .collect();
+            copies
+                .into_iter()
+                .map(|copy| copy.join().expect("copying the verifier does not panic"))
+                .collect()
+        });
+        Ok(Some(Self { nodes }))
+    }
+
+    /// Pins the calling worker to its node, round-robin, and returns that node's verifier.
+    pub fn enter(&self, worker: usize) -> &Verifier {
+        let (cpus, verifier) = &self.nodes[worker % self.nodes.len()];
+        pin(cpus);
+        verifier
+    }
+}
+
+fn find(nodes: &[Node], id: usize) -> Result<&Node> {
+    nodes.iter().find(|node| node.id == id).ok_or_else(|| {
+        let ids: Vec<String> = nodes.iter().map(|node| node.id.to_string()).collect();
+        anyhow::anyhow!("NUMA node {id} does not exist (nodes: {})", ids.join(", "))
+    })
+}
+
+/// Best effort: a CPU set the process may not use just leaves the thread where it was.
+#[cfg(target_os = "linux")]
+fn pin(cpus: &[usize]) {
+    // SAFETY: `set` is a plain bit mask owned by this frame, sized as the call expects.
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        for &cpu in cpus.iter().filter(|&&cpu| cpu < libc::CPU_SETSIZE as usize) {
+            libc::CPU_SET(cpu, &mut set);
+        }
+        libc::sched_setaffinity(0, size_of::<libc::cpu_set_t>(), &set);
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn pin(_cpus: &[usize]) {}
+
+#[cfg(target_os = "linux")]
+fn set_mempolicy(policy: Policy, ids: &[usize]) -> Result<()> {
+    const MPOL_PREFERRED: libc::c_long = 1;
+    const MPOL_INTERLEAVE: libc::c_long = 3;
+    let bits = libc::c_ulong::BITS as usize;
+    let mut mask = vec![0 as libc::c_ulong; ids.iter().max().map_or(1, |max| max / bits + 1)];
+    for &id in ids {
+        mask[id / bits] |= 1 << (id % bits);
+    }
+    let mode = match policy {
+        Policy::Interleave => MPOL_INTERLEAVE,
+        _ => MPOL_PREFERRED,
+    };
+    // SAFETY: the mask outlives the call, and the kernel reads at most `maxnode - 1` bits.
+    let result = unsafe {
+        libc::syscall(
+            libc::SYS_set_mempolicy,
+            mode,
+            mask.as_ptr(),
+            mask.len() * bits + 1,
+        )
+    };
+    if result != 0 {
+        bail!(
+            "Failed to set the NUMA memory policy: {}",
+            std::io::Error::last_os_error()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_mempolicy(_policy: Policy, _ids: &[usize]) -> Result<()> {
+    Ok(())
+}
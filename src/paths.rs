@@ -0,0 +1,35 @@
+use std::{borrow::Cow, path::Path};
+
+/// `path` in the form to hand to the OS. On Windows that is the extended-length form
+/// (`\\?\C:\...`, or `\\?\UNC\server\share\...` for shares), which lifts the 260-character
+/// limit that deeply nested evidence directories run into. Elsewhere, and for paths that
+/// cannot be resolved, it is `path` itself. Messages should keep showing the path as given.
+#[cfg(windows)]
+pub fn extended(path: &Path) -> Cow<'_, Path> {
+    use std::path::PathBuf;
+
+    let Some(absolute) = std::path::absolute(path)
+        .ok()
+        .and_then(|absolute| absolute.to_str().map(str::to_owned))
+    else {
+        return Cow::Borrowed(path);
+    };
+    let extended = if let Some(verbatim) = absolute.strip_prefix(r"\\?\") {
+        // Verbatim paths are not normalized, so separators that arrived by joining relative
+        // paths, like a manifest's entries, must be turned into backslashes here.
+        format!(r"\\?\{}", verbatim.replace('/', r"\"))
+    } else if absolute.starts_with(r"\\.\") {
+        // Devices such as `NUL` have no extended form.
+        absolute
+    } else if let Some(share) = absolute.strip_prefix(r"\\") {
+        format!(r"\\?\UNC\{share}")
+    } else {
+        format!(r"\\?\{absolute}")
+    };
+    Cow::Owned(PathBuf::from(extended))
+}
+
+#[cfg(not(windows))]
+pub fn extended(path: &Path) -> Cow<'_, Path> {
+    Cow::Borrowed(path)
+}
@@ -0,0 +1,113 @@
+use anyhow::{Result, bail};
+
+use crate::wordlist::Words;
+
+/// How the words of a phrase are capitalized, in the order they are tried.
+#[derive(Clone, Copy, Debug)]
+enum Case {
+    AsListed,
+    /// `Correct horse battery`
+    First,
+    /// `Correct Horse Battery`
+    Title,
+    Upper,
+}
+
+const CASES: [Case; 4] = [Case::AsListed, Case::First, Case::Title, Case::Upper];
+
+/// Every sequence of `min_words..=max_words` words from a list, joined by one of the separators
+/// (or run together) and capitalized in each of the common ways.
+pub struct Phrases {
+    words: Words,
+    /// The empty separator first, then the given ones.
+    separators: Vec<String>,
+    min_words: usize,
+    max_words: usize,
+    len: u64,
+}
+
+impl Phrases {
+    pub fn new(words: Words, separators: &str, min_words: usize, max_words: usize) -> Result<Self> {
+        if min_words == 0 || min_words > max_words {
+            bail!("Invalid phrase word count {min_words}-{max_words}");
+        }
+        let mut joins = vec![String::new()];
+        for separator in separators.chars() {
+            if !joins.contains(&separator.to_string()) {
+                joins.push(separator.to_string());
+            }
+        }
+        let mut phrases = Self {
+            words,
+            separators: joins,
+            min_words,
+            max_words,
+            len: 0,
+        };
+        for count in min_words..=max_words {
+            match phrases
+                .per_count(count)
+                .and_then(|per_count| phrases.len.checked_add(per_count))
+            {
+                Some(len) => phrases.len = len,
+                None => bail!("Passphrase keyspace is too large; narrow --phrase-count"),
+            }
+        }
+        Ok(phrases)
+    }
+
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// `count` words chosen with repetition, in every join and capitalization.
+    fn per_count(&self, count: usize) -> Option<u64> {
+        let joins = if count == 1 { 1 } else { self.separators.len() };
+        (self.words.len() as u64)
+            .checked_pow(u32::try_from(count).ok()?)?
+            .checked_mul((joins * CASES.len()) as u64)
+    }
+
+    /// Fewer words first; within one word count, every phrase in the likeliest capitalization
+    /// and join before any in the next, with the first word most significant.
+    pub fn write(&self, mut index: u64, buf: &mut String) {
+        let mut count = self.min_words;
+        while count < self.max_words {
+            let per_count = self.per_count(count).expect("checked in new");
+            if index < per_count {
+                break;
+            }
+            index -= per_count;
+            count += 1;
+        }
+        let tuples = (self.words.len() as u64).pow(count as u32);
+        let mut tuple = index % tuples;
+        let variant = index / tuples;
+        let joins = if count == 1 { 1 } else { self.separators.len() };
+        let separator = &self.separators[(variant % joins as u64) as usize];
+        let case = CASES[(variant / joins as u64) as usize];
+
+        buf.clear();
+        let mut place = tuples / self.words.len() as u64;
+        for position in 0..count {
+            if position > 0 {
+                buf.push_str(separator);
+            }
+            let word = self.words.get((tuple / place) as usize);
+            tuple %= place;
+            place = (place / self.words.len() as u64).max(1);
+            match case {
+                Case::AsListed => buf.push_str(word),
+                Case::First if position > 0 => buf.push_str(word),
+                Case::First | Case::Title => {
+                    let mut chars = word.chars();
+                    if let Some(first) = chars.next() {
+                        buf.extend(first.to_uppercase());
+                        buf.push_str(chars.as_str());
+                    }
+                }
+                Case::Upper => buf.push_str(&word.to_uppercase()),
+            }
+        }
+    }
+}
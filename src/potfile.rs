@@ -0,0 +1,55 @@
+use std::{
+    fs::OpenOptions,
+    io::Write,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use zeroize::Zeroizing;
+
+use crate::search::Provenance;
+use crate::vault;
+
+/// One line of the potfile: a recovered password and where it came from.
+#[derive(Debug, Serialize)]
+struct Record<'a> {
+    archive: String,
+    entry: &'a str,
+    password: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source: Option<&'a str>,
+    recovered_at: u64,
+}
+
+/// Appends a recovered password to the potfile as a single JSON line, so concurrent runs
+/// sharing one potfile never interleave partial records. With `--store-key` the line is sealed.
+pub fn append(
+    path: &Path,
+    archive: &Path,
+    entry: &str,
+    password: &str,
+    provenance: Option<&Provenance>,
+) -> Result<()> {
+    let record = Record {
+        archive: archive.display().to_string(),
+        entry,
+        password,
+        source: provenance.map(|provenance| provenance.source.as_str()),
+        recovered_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs()),
+    };
+    let mut line = Zeroizing::new(serde_json::to_vec(&record)?);
+    if let Some(vault) = vault::installed() {
+        line = Zeroizing::new(serde_json::to_vec(&vault.seal(&line)?)?);
+    }
+    line.push(b'\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(&line))
+        .with_context(|| format!("Failed to append to potfile: {}", path.display()))
+}
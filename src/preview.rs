@@ -0,0 +1,69 @@
+use std::io::{Cursor, Read};
+
+use anyhow::Result;
+use zip::ZipArchive;
+
+use crate::archive::Entry;
+
+/// Lists the archive and shows the first `len` bytes of the target entry, decrypted.
+pub fn print_preview(
+    entries: &[Entry],
+    target: usize,
+    entry_archive: &[u8],
+    password: &str,
+    len: usize,
+) -> Result<()> {
+    println!();
+    println!("Archive contents:");
+    for (index, entry) in entries.iter().enumerate() {
+        let marker = if index == target { '*' } else { ' ' };
+        println!(
+            "{marker} {:>12}  {:<10} {:<12} {}",
+            entry.uncompressed_size,
+            entry.compression.to_string(),
+            entry.encryption.to_string(),
+            entry.name
+        );
+    }
+
+    let mut archive = ZipArchive::new(Cursor::new(entry_archive))?;
+    let file = archive.by_index_decrypt(0, password.as_bytes())?;
+    let mut head = Vec::with_capacity(len);
+    file.take(len as u64).read_to_end(&mut head)?;
+
+    println!();
+    println!("First {} bytes of {}:", head.len(), entries[target].name);
+    // A multi-byte character cut off by the length limit shouldn't turn text into a hexdump.
+    let text_len = match std::str::from_utf8(&head) {
+        Ok(_) => head.len(),
+        Err(error) if error.error_len().is_none() => error.valid_up_to(),
+        Err(_) => 0,
+    };
+    match std::str::from_utf8(&head[..text_len]) {
+        Ok(text) if text_len > 0 && looks_like_text(text) => println!("{text}"),
+        _ => print_hexdump(&head),
+    }
+    Ok(())
+}
+
+pub fn looks_like_text(text: &str) -> bool {
+    text.chars()
+        .all(|c| !c.is_control() || matches!(c, '\n' | '\r' | '\t'))
+}
+
+fn print_hexdump(bytes: &[u8]) {
+    for (row, chunk) in bytes.chunks(16).enumerate() {
+        let hex: Vec<String> = chunk.iter().map(|byte| format!("{byte:02x}")).collect();
+        let ascii: String = chunk
+            .iter()
+            .map(|&byte| {
+                if byte.is_ascii_graphic() || byte == b' ' {
+                    char::from(byte)
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+        println!("{:08x}  {:<47}  |{ascii}|", row * 16, hex.join(" "));
+    }
+}
@@ -0,0 +1,179 @@
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::Path,
+    process::ExitCode,
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::search::{Provenance, StageStats};
+use crate::strength::Strength;
+use crate::usage::Resources;
+use crate::vault;
+
+/// How a run ended; each status maps to its own exit code.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    Found,
+    NotFound,
+    Error,
+    Interrupted,
+    BudgetExhausted,
+}
+
+impl Status {
+    pub fn exit_code(self) -> ExitCode {
+        ExitCode::from(match self {
+            Status::Found => 0,
+            Status::NotFound => 1,
+            Status::Error => 2,
+            Status::Interrupted => 3,
+            Status::BudgetExhausted => 4,
+        })
+    }
+
+    /// The name the result file and `--events` use.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Status::Found => "found",
+            Status::NotFound => "not_found",
+            Status::Error => "error",
+            Status::Interrupted => "interrupted",
+            Status::BudgetExhausted => "budget_exhausted",
+        }
+    }
+}
+
+/// The machine-readable record written by `--result-file`.
+#[derive(Debug, Serialize)]
+pub struct RunResult<'a> {
+    pub status: Status,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password: Option<&'a str>,
+    /// ZipCrypto keys, as printed after the password.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub internal_keys: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    pub archive: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entry: Option<&'a str>,
+    pub candidates_tried: u64,
+    pub elapsed_seconds: f64,
+    pub stages: &'a [StageStats],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<&'a Provenance>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub strength: Option<&'a Strength>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resources: Option<&'a Resources>,
+    /// The archive with the same contents whose outcome this one shares instead of being attacked.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duplicate_of: Option<String>,
+}
+
+impl<'a> RunResult<'a> {
+    pub fn new(status: Status, archive: &Path, candidates_tried: u64, elapsed: Duration) -> Self {
+        Self {
+            status,
+            password: None,
+            internal_keys: None,
+            reason: None,
+            archive: archive.display().to_string(),
+            entry: None,
+            candidates_tried,
+            elapsed_seconds: elapsed.as_secs_f64(),
+            stages: &[],
+            provenance: None,
+            strength: None,
+            resources: None,
+            duplicate_of: None,
+        }
+    }
+}
+
+/// How many skipped indices the summary lists per stage; the result file has all of them.
+const SKIPPED_SHOWN: usize = 10;
+
+/// Breaks the run down by stage so unproductive sources stand out.
+pub fn print_stage_summary(stages: &[StageStats]) {
+    println!("Stages:");
+    for (number, stage) in stages.iter().enumerate() {
+        println!(
+            "  {}. {}: {} tried, {} rejected, {} errors in {:.2?}{}",
+            number + 1,
+            stage.source,
+            stage.tried,
+            stage.rejected,
+            stage.errors,
+            stage.elapsed,
+            if stage.hit { " (hit)" } else { "" }
+        );
+        if !stage.skipped.is_empty() {
+            let shown: Vec<String> = stage
+                .skipped
+                .iter()
+                .take(SKIPPED_SHOWN)
+                .map(u64::to_string)
+                .collect();
+            let more = stage.skipped.len().saturating_sub(SKIPPED_SHOWN);
+            println!(
+                "     skipped indices: {}{}",
+                shown.join(", "),
+                if more > 0 {
+                    format!(" and {more} more")
+                } else {
+                    String::new()
+                }
+            );
+        }
+    }
+    let skipped: usize = stages.iter().map(|stage| stage.skipped.len()).sum();
+    if skipped > 0 {
+        println!(
+            "{skipped} candidates were skipped after errors and never ruled out; \
+             see --retry-skipped"
+        );
+    }
+}
+
+/// Writes one result, or with several archives an array of them, sealed with `--store-key`.
+pub fn write_result_file(path: &Path, result: &impl Serialize) -> Result<()> {
+    match vault::installed() {
+        Some(vault) => vault
+            .seal(&serde_json::to_vec(result)?)
+            .and_then(|sealed| write_json_atomically(path, &sealed)),
+        None => write_json_atomically(path, result),
+    }
+    .with_context(|| format!("Failed to write result file: {}", path.display()))
+}
+
+/// Writes `value` next to `path` first and renames it into place, so a watcher never
+/// observes a half-written file.
+pub fn write_json_atomically(path: &Path, value: &impl Serialize) -> Result<()> {
+    let file_name = path
+        .file_name()
+        .with_context(|| format!("{} has no file name", path.display()))?;
+    let temp = path.with_file_name(format!(
+        ".{}.{}.tmp",
+        file_name.to_string_lossy(),
+        std::process::id()
+    ));
+
+    let written = (|| -> Result<()> {
+        let mut file = File::create(&temp)?;
+        serde_json::to_writer_pretty(&mut file, value)?;
+        writeln!(file)?;
+        file.sync_all()?;
+        fs::rename(&temp, path)?;
+        Ok(())
+    })();
+    if written.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    written
+}
@@ -0,0 +1,205 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufReader, BufWriter, Cursor, Read},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, bail};
+use zip::{
+    AesMode, CompressionMethod, ZipArchive, ZipWriter, result::ZipError, write::FullFileOptions,
+};
+
+use crate::archive;
+use crate::paths;
+use crate::zipcrypto::{self, Keys};
+
+/// How the input's entries are decrypted.
+#[derive(Clone, Copy)]
+enum Key<'a> {
+    Password(&'a str),
+    /// ZipCrypto internal keys, which stand in for a password that was never found.
+    Internal(Keys),
+}
+
+#[derive(clap::Args, Debug)]
+pub struct RekeyArgs {
+    /// The encrypted archive.
+    input: PathBuf,
+
+    /// Where to write the re-encrypted copy.
+    output: PathBuf,
+
+    /// The archive's current password, e.g. the one a crack recovered.
+    #[arg(long, value_name = "PASSWORD")]
+    old_password: String,
+
+    /// The password to encrypt every entry with, using AES-256.
+    #[arg(long, value_name = "PASSWORD")]
+    new_password: String,
+
+    /// Replace OUTPUT if it already exists.
+    #[arg(long)]
+    force: bool,
+}
+
+pub fn rekey(args: &RekeyArgs) -> Result<()> {
+    if args.new_password.is_empty() {
+        bail!("--new-password must not be empty");
+    }
+    let copied = rewrite(
+        &args.input,
+        &args.output,
+        Key::Password(&args.old_password),
+        Some(&args.new_password),
+        args.force,
+    )?;
+    println!(
+        "Wrote {}: {copied} entries encrypted with AES-256",
+        args.output.display()
+    );
+    Ok(())
+}
+
+#[derive(clap::Args, Debug)]
+pub struct StripArgs {
+    /// The encrypted archive.
+    input: PathBuf,
+
+    /// Where to write the unencrypted copy.
+    output: PathBuf,
+
+    /// The archive's password, e.g. the one a crack recovered.
+    #[arg(long, required_unless_present = "keys")]
+    password: Option<String>,
+
+    /// ZipCrypto internal keys to decrypt with instead of a password, as three hexadecimal
+    /// words like "c4490e28 b414a23d 91404b31".
+    #[arg(long, value_name = "KEYS", conflicts_with = "password")]
+    keys: Option<Keys>,
+
+    /// Replace OUTPUT if it already exists.
+    #[arg(long)]
+    force: bool,
+}
+
+pub fn strip(args: &StripArgs) -> Result<()> {
+    let key = match (&args.password, args.keys) {
+        (_, Some(keys)) => Key::Internal(keys),
+        (Some(password), None) => Key::Password(password),
+        (None, None) => unreachable!("clap requires --password or --keys"),
+    };
+    let copied = rewrite(&args.input, &args.output, key, None, args.force)?;
+    println!(
+        "Wrote {}: {copied} entries, unencrypted",
+        args.output.display()
+    );
+    Ok(())
+}
+
+/// Copies every entry of `input` to `output`, decrypting with `key` and encrypting with
+/// `encrypt_with` (or not at all). Names, timestamps, attributes, comments, and extra fields
+/// carry over; entries are recompressed, stored ones stored and the rest deflated.
+fn rewrite(
+    input: &Path,
+    output: &Path,
+    key: Key,
+    encrypt_with: Option<&str>,
+    force: bool,
+) -> Result<usize> {
+    let file = File::open(paths::extended(input))
+        .with_context(|| format!("Failed to open archive: {}", input.display()))?;
+    let mut archive = ZipArchive::new(BufReader::new(file))
+        .with_context(|| format!("Failed to read archive: {}", input.display()))?;
+    // The zip crate drops extra fields it parses, like extended timestamps, so they are read
+    // from the local headers directly.
+    let mut raw = BufReader::new(File::open(paths::extended(input))?);
+    let entries = archive::read_central_directory(&mut raw)?.entries;
+    if paths::extended(output).exists() {
+        if !force {
+            bail!(
+                "{} already exists; pass --force to replace it",
+                output.display()
+            );
+        }
+        if fs::canonicalize(paths::extended(output))? == fs::canonicalize(paths::extended(input))? {
+            bail!("The output must not be the input archive");
+        }
+    }
+    let created = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(paths::extended(output))
+        .with_context(|| format!("Failed to create {}", output.display()))?;
+
+    let result = (|| -> Result<usize> {
+        let mut writer = ZipWriter::new(BufWriter::new(created));
+        for index in 0..archive.len() {
+            let (name, encrypted, is_dir, mut options) = {
+                let raw = archive.by_index_raw(index)?;
+                let mut options = FullFileOptions::default()
+                    .compression_method(match raw.compression() {
+                        CompressionMethod::Stored => CompressionMethod::Stored,
+                        _ => CompressionMethod::Deflated,
+                    })
+                    .large_file(raw.size() >= u64::from(u32::MAX))
+                    .external_attributes(raw.external_attributes())
+                    .with_file_comment(raw.comment());
+                if let Some(modified) = raw.last_modified() {
+                    options = options.last_modified_time(modified);
+                }
+                (
+                    raw.name()?.into_owned(),
+                    raw.encrypted(),
+                    raw.is_dir(),
+                    options,
+                )
+            };
+            if let Some(entry) = entries.get(index).filter(|entry| entry.name == name) {
+                for (id, data) in archive::local_extra_fields(&mut raw, entry)? {
+                    options.add_extra_field(id, data, false)?;
+                }
+            }
+            if let Some(password) = encrypt_with {
+                options = options.with_aes_encryption(AesMode::Aes256, password);
+            }
+            if is_dir {
+                writer.add_directory(name, options)?;
+                continue;
+            }
+            let mut decrypted;
+            let mut contents: Box<dyn Read + '_> = match key {
+                _ if !encrypted => Box::new(archive.by_index(index)?),
+                Key::Password(password) => {
+                    match archive.by_index_decrypt(index, password.as_bytes()) {
+                        Err(ZipError::InvalidPassword) => {
+                            bail!("\"{name}\" is not encrypted with the given password")
+                        }
+                        opened => Box::new(opened?),
+                    }
+                }
+                Key::Internal(keys) => {
+                    let Some(entry) = entries.get(index).filter(|entry| entry.name == name) else {
+                        bail!("Could not locate the local header of \"{name}\"");
+                    };
+                    let entry_archive = archive::extract_entry_archive(&mut raw, entry)?;
+                    let plain = zipcrypto::decrypt_entry_archive(&entry_archive, entry, keys)?;
+                    decrypted = ZipArchive::new(Cursor::new(plain))?;
+                    Box::new(decrypted.by_index(0)?)
+                }
+            };
+            writer.start_file(&name, options)?;
+            // A wrong password that slips past the ZipCrypto check byte, or wrong internal keys,
+            // fail here, on the CRC.
+            io::copy(&mut contents, &mut writer)
+                .with_context(|| format!("Failed to decrypt \"{name}\""))?;
+        }
+        writer.set_raw_comment(archive.comment().into())?;
+        writer.finish()?;
+        Ok(archive.len())
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(paths::extended(output));
+    }
+    result
+}
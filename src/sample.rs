@@ -0,0 +1,119 @@
+use std::{collections::HashSet, time::Instant};
+
+use anyhow::{Result, bail};
+
+use crate::search;
+use crate::secret::SecretBuf;
+use crate::{Args, batch, load_stages, load_target, shown, strength};
+
+/// Tests `fraction` of every stage, picked at random, and extrapolates the rate, the chance
+/// of a hit, and how many candidates repeat, before committing to the whole plan.
+pub fn run(args: &Args, fraction: f64) -> Result<()> {
+    if batch::is_batch(args) {
+        bail!("--sample needs a single --zip");
+    }
+    let stages = load_stages(args)?;
+    let zip = &args.zip[0];
+    let target = load_target(zip, args.target.as_deref(), &args.skip_entry, args.backend)?;
+    let entry = &target.entries[target.index];
+    println!(
+        "Sampling {:.2}% of each stage against {} ({}, {}):",
+        fraction * 100.0,
+        entry.name,
+        entry.compression,
+        entry.encryption
+    );
+
+    let mut rng = Rng::new();
+    let mut buf = SecretBuf::new();
+    let (mut total_time, mut hit) = (0.0, None);
+    for (number, stage) in stages.iter().enumerate() {
+        let len = stage.generator.len();
+        if len == 0 {
+            println!("  {}. {}: empty", number + 1, stage.source);
+            continue;
+        }
+        let indices = rng.stratified(len, fraction);
+        let mut seen = HashSet::new();
+        let started_at = Instant::now();
+        for &index in &indices {
+            let candidate = stage.generator.candidate(index, &mut buf);
+            seen.insert(candidate.to_owned());
+            if target.verifier.check_batch(&[candidate])[0]
+                && target.verifier.confirm(candidate)?
+                && hit.is_none()
+            {
+                hit = Some((number, index, candidate.to_owned()));
+            }
+        }
+        let elapsed = started_at.elapsed();
+        // Sampling runs on one thread; a real run splits the work across all of them.
+        let rate = indices.len() as f64 / elapsed.as_secs_f64().max(1e-9) * args.threads as f64;
+        let duplicates = indices.len() - seen.len();
+        total_time += len as f64 / rate;
+        println!(
+            "  {}. {}: {} of {len} tested in {elapsed:.2?}, about {rate:.0} candidates/s with {} \
+             thread{}, {:.1}% repeats in the sample; whole stage {}",
+            number + 1,
+            stage.source,
+            indices.len(),
+            args.threads,
+            if args.threads == 1 { "" } else { "s" },
+            100.0 * duplicates as f64 / indices.len() as f64,
+            strength::describe(len as f64 / rate)
+        );
+    }
+    match hit {
+        Some((number, index, password)) => println!(
+            "  The sample found the password {:?}: {}",
+            shown(args, &password),
+            search::provenance(&stages, number, index)
+        ),
+        None => println!(
+            "  No hit in the sample. A sample this size finds a password that is in the plan \
+             {:.2}% of the time; the whole plan takes {} at worst",
+            fraction * 100.0,
+            strength::describe(total_time)
+        ),
+    }
+    println!(
+        "  Repeats are counted within the sample only, so they understate how often candidates \
+         repeat across a whole stage"
+    );
+    Ok(())
+}
+
+/// Splitmix64, seeded from the OS; sampling needs speed and spread, not secrecy.
+struct Rng(u64);
+
+impl Rng {
+    fn new() -> Self {
+        let mut seed = [0u8; 8];
+        let _ = getrandom::fill(&mut seed);
+        Self(u64::from_le_bytes(seed))
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// One random index from each of `fraction * len` equal slices of `0..len`, so the sample
+    /// covers the whole stage without repeating an index.
+    fn stratified(&mut self, len: u64, fraction: f64) -> Vec<u64> {
+        if len == 0 {
+            return Vec::new();
+        }
+        let count = ((len as f64 * fraction).ceil() as u64).clamp(1, len);
+        (0..count)
+            .map(|slice| {
+                let start = (slice as u128 * len as u128 / count as u128) as u64;
+                let end = ((slice + 1) as u128 * len as u128 / count as u128) as u64;
+                start + self.next() % (end - start)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,546 @@
+use std::{
+    ops::Range,
+    sync::{
+        Mutex, OnceLock,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use zeroize::Zeroizing;
+
+use crate::control::{self, Governor};
+use crate::generate::Generator;
+use crate::numa::Placement;
+use crate::secret::{self, SecretBuf};
+use crate::status::Progress;
+use crate::survivors::SurvivorLog;
+use crate::timing::Timings;
+use crate::usage;
+use crate::verify::{self, Verifier};
+
+/// How often the dispatching thread reports progress while workers run.
+const MONITOR_INTERVAL: Duration = Duration::from_millis(20);
+const NO_HIT: u64 = u64::MAX;
+/// One batch in this many is timed step by step; timing every one would slow fast checks.
+const STEP_SAMPLE: u64 = 64;
+/// Wait before the first retry of a failed test; it doubles with every further attempt.
+const RETRY_BACKOFF: Duration = Duration::from_millis(50);
+
+/// One step of an attack: a candidate source tried in full before the next one starts.
+pub struct Stage {
+    pub source: String,
+    pub generator: Generator,
+}
+
+/// The global index of a stage's first candidate: the keyspaces of every earlier stage laid end
+/// to end. Within a stage, the index is the generator's own (wordlist line, brute-force rank).
+pub fn stage_offset(stages: &[Stage], number: usize) -> u64 {
+    stages[..number].iter().fold(0u64, |total, stage| {
+        total.saturating_add(stage.generator.len())
+    })
+}
+
+/// Maps a global index back to the stage and the stage-local index, or `None` past the end.
+pub fn locate(stages: &[Stage], mut index: u64) -> Option<(usize, u64)> {
+    for (number, stage) in stages.iter().enumerate() {
+        let len = stage.generator.len();
+        if index < len {
+            return Some((number, index));
+        }
+        index -= len;
+    }
+    None
+}
+
+/// Where candidate `index` of stage `number` came from.
+pub fn provenance(stages: &[Stage], number: usize, index: u64) -> Provenance {
+    let (line, position) = match &stages[number].generator {
+        Generator::Wordlist(words) => (Some(words.line_number(index as usize)), None),
+        Generator::Affixed(affixed) => (
+            Some(affixed.words().line_number(affixed.word_index(index))),
+            Some(index),
+        ),
+        Generator::BruteForce(_) | Generator::List(_) | Generator::Phrases(_) => {
+            (None, Some(index))
+        }
+    };
+    Provenance {
+        stage: number + 1,
+        source: stages[number].source.clone(),
+        index: stage_offset(stages, number) + index,
+        line,
+        position,
+    }
+}
+
+/// Where the winning candidate came from, so attack plans can be tuned to what works.
+#[derive(Debug, Serialize)]
+pub struct Provenance {
+    /// 1-based, in the order the stages ran.
+    pub stage: usize,
+    pub source: String,
+    /// Global keyspace index, as recorded by checkpoints.
+    pub index: u64,
+    /// Line of the wordlist file, for wordlist stages and the word an affix was attached to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<u64>,
+    /// Rank within the stage, for generated stages.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<u64>,
+}
+
+impl std::fmt::Display for Provenance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "stage {} ({})", self.stage, self.source)?;
+        if let Some(line) = self.line {
+            write!(f, ", line {line}")?;
+        }
+        if let Some(position) = self.position {
+            write!(f, ", candidate {position}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct StageStats {
+    pub source: String,
+    pub tried: u64,
+    /// Candidates that were cleanly ruled out as the wrong password.
+    pub rejected: u64,
+    /// Candidates whose test failed for a reason other than a wrong password.
+    pub errors: u64,
+    /// Stage-local indices of those candidates, which were never ruled out.
+    pub skipped: Vec<u64>,
+    #[serde(rename = "elapsed_seconds", serialize_with = "seconds")]
+    pub elapsed: Duration,
+    pub hit: bool,
+}
+
+fn seconds<S: serde::Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64())
+}
+
+/// Run-wide state shared by every stage: stop conditions and the winning password.
+pub struct Searcher<'a> {
+    verifier: &'a Verifier,
+    governor: &'a Governor,
+    progress: &'a Progress,
+    threads: usize,
+    chunk_size: usize,
+    deadline: Option<Instant>,
+    retries: u32,
+    timings: Option<&'a Timings>,
+    /// Whether candidates are left out of error messages, as recovered passwords are.
+    redact: bool,
+    placement: Option<&'a Placement>,
+    survivors: Option<&'a SurvivorLog>,
+    /// CPU time of each worker in nanoseconds, summed over the stages.
+    worker_cpu: Vec<AtomicU64>,
+    found: AtomicBool,
+    exhausted: AtomicBool,
+    /// Stage number and index of the password, materialized only once the run is over.
+    winner: OnceLock<(usize, u64)>,
+}
+
+impl<'a> Searcher<'a> {
+    pub fn new(
+        verifier: &'a Verifier,
+        governor: &'a Governor,
+        progress: &'a Progress,
+        threads: usize,
+        chunk_size: usize,
+        deadline: Option<Instant>,
+        retries: u32,
+    ) -> Self {
+        Self {
+            verifier,
+            governor,
+            progress,
+            threads,
+            // Every chunk should fill whole batches.
+            chunk_size: chunk_size.next_multiple_of(verifier.batch_size()),
+            deadline,
+            retries,
+            timings: None,
+            redact: false,
+            placement: None,
+            survivors: None,
+            worker_cpu: (0..threads).map(|_| AtomicU64::new(0)).collect(),
+            found: AtomicBool::new(false),
+            exhausted: AtomicBool::new(false),
+            winner: OnceLock::new(),
+        }
+    }
+
+    /// CPU time each worker has used so far.
+    pub fn worker_cpu(&self) -> Vec<Duration> {
+        self.worker_cpu
+            .iter()
+            .map(|nanos| Duration::from_nanos(nanos.load(Ordering::Relaxed)))
+            .collect()
+    }
+
+    /// Records fast-check and verification latencies into `timings`.
+    pub fn with_timings(mut self, timings: Option<&'a Timings>) -> Self {
+        self.timings = timings;
+        self
+    }
+
+    /// Prints candidates in error messages as `[redacted]`.
+    pub fn with_redaction(mut self, redact: bool) -> Self {
+        self.redact = redact;
+        self
+    }
+
+    /// Pins workers to NUMA nodes and gives them their node's copy of the verifier.
+    pub fn with_placement(mut self, placement: Option<&'a Placement>) -> Self {
+        self.placement = placement;
+        self
+    }
+
+    /// Writes every candidate that passes the fast check to `survivors`.
+    pub fn with_survivors(mut self, survivors: Option<&'a SurvivorLog>) -> Self {
+        self.survivors = survivors;
+        self
+    }
+
+    /// Whether the run should stop before starting another stage.
+    pub fn is_finished(&self) -> bool {
+        self.found.load(Ordering::Relaxed)
+            || self.exhausted.load(Ordering::Relaxed)
+            || control::interrupted()
+    }
+
+    pub fn is_exhausted(&self) -> bool {
+        self.exhausted.load(Ordering::Relaxed)
+    }
+
+    /// Stage number and stage-local index of the password, once found.
+    pub fn winner_position(&self) -> Option<(usize, u64)> {
+        self.winner.get().copied()
+    }
+
+    pub fn provenance(&self, stages: &[Stage]) -> Option<Provenance> {
+        let (number, index) = self.winner_position()?;
+        Some(provenance(stages, number, index))
+    }
+
+    pub fn winner(&self, stages: &[Stage]) -> Option<Zeroizing<String>> {
+        let &(number, index) = self.winner.get()?;
+        let mut buf = SecretBuf::new();
+        Some(Zeroizing::new(
+            stages[number]
+                .generator
+                .candidate(index, &mut buf)
+                .to_owned(),
+        ))
+    }
+
+    /// Tests `stage` from local index `start`, periodically reporting to `on_progress` an index
+    /// below which every candidate has been tested.
+    pub fn run_stage(
+        &self,
+        number: usize,
+        stage: &Stage,
+        start: u64,
+        mut on_progress: impl FnMut(u64),
+    ) -> Result<StageStats> {
+        self.progress.start_stage(number);
+        let tried_before = self.progress.tried();
+        let skipped = Mutex::new(Vec::new());
+        let hit = AtomicU64::new(NO_HIT);
+        let started_at = Instant::now();
+
+        let keyspace = stage.generator.len();
+        usize::try_from(keyspace).context("Keyspace is too large for this platform")?;
+        let cursor = AtomicU64::new(start);
+        // Each worker's slot holds a lower bound on the chunk it is testing, so the minimum over
+        // the cursor and all slots is a safe resume point even mid-chunk.
+        let slots: Vec<AtomicU64> = (0..self.threads).map(|_| AtomicU64::new(start)).collect();
+        let low_watermark = || {
+            let mut low = cursor.load(Ordering::SeqCst).min(keyspace);
+            for slot in &slots {
+                low = low.min(slot.load(Ordering::SeqCst));
+            }
+            low
+        };
+
+        thread::scope(|scope| {
+            let workers: Vec<_> = slots
+                .iter()
+                .enumerate()
+                .map(|(worker, slot)| {
+                    let (cursor, skipped, hit) = (&cursor, &skipped, &hit);
+                    scope.spawn(move || {
+                        let cpu_before = usage::thread_cpu_time();
+                        self.work(worker, stage, cursor, slot, skipped, hit);
+                        let cpu = usage::thread_cpu_time().saturating_sub(cpu_before);
+                        self.worker_cpu[worker].fetch_add(cpu.as_nanos() as u64, Ordering::Relaxed);
+                    })
+                })
+                .collect();
+            while workers.iter().any(|worker| !worker.is_finished()) {
+                thread::sleep(MONITOR_INTERVAL);
+                on_progress(low_watermark());
+            }
+        });
+        on_progress(low_watermark());
+
+        let tried = self.progress.tried() - tried_before;
+        let mut skipped = skipped.into_inner().expect("worker panicked");
+        skipped.sort_unstable();
+        let errors = skipped.len() as u64;
+        let hit = hit.into_inner();
+        if hit != NO_HIT {
+            let _ = self.winner.set((number, hit));
+        }
+        let hit = hit != NO_HIT;
+        Ok(StageStats {
+            source: stage.source.clone(),
+            tried,
+            rejected: tried - errors - u64::from(hit),
+            errors,
+            skipped,
+            elapsed: started_at.elapsed(),
+            hit,
+        })
+    }
+
+    /// Claims chunks off the shared cursor until the stage is exhausted or the run stops.
+    fn work(
+        &self,
+        worker: usize,
+        stage: &Stage,
+        cursor: &AtomicU64,
+        slot: &AtomicU64,
+        skipped: &Mutex<Vec<u64>>,
+        hit: &AtomicU64,
+    ) {
+        // Placed before the buffers are allocated, so they land in the worker's node too.
+        let verifier = self
+            .placement
+            .map_or(self.verifier, |placement| placement.enter(worker));
+        let keyspace = stage.generator.len();
+        let chunk_size = self.chunk_size as u64;
+        let batch_size = verifier.batch_size() as u64;
+        let mut bufs: Vec<SecretBuf> = (0..verifier.batch_size())
+            .map(|_| SecretBuf::new())
+            .collect();
+        loop {
+            slot.store(
+                cursor.load(Ordering::SeqCst).min(keyspace),
+                Ordering::SeqCst,
+            );
+            let claim = cursor.fetch_add(chunk_size, Ordering::SeqCst);
+            if claim >= keyspace {
+                slot.store(u64::MAX, Ordering::SeqCst);
+                return;
+            }
+            slot.store(claim, Ordering::SeqCst);
+            let end = keyspace.min(claim.saturating_add(chunk_size));
+            let mut first = claim;
+            while first < end {
+                let last = end.min(first + batch_size);
+                if !self.test_batch(verifier, stage, first..last, &mut bufs, skipped, hit) {
+                    return;
+                }
+                first = last;
+            }
+        }
+    }
+
+    /// Tests one batch of candidates, returning `false` once the run should stop. One batch in
+    /// [`STEP_SAMPLE`] also times each step for [`Progress::breakdown`].
+    fn test_batch(
+        &self,
+        verifier: &Verifier,
+        stage: &Stage,
+        indices: Range<u64>,
+        bufs: &mut [SecretBuf],
+        skipped: &Mutex<Vec<u64>>,
+        hit: &AtomicU64,
+    ) -> bool {
+        let batch = indices.start / verifier.batch_size() as u64;
+        let sampled_at = batch.is_multiple_of(STEP_SAMPLE).then(Instant::now);
+        let Some(_permit) = self.governor.acquire() else {
+            return false;
+        };
+        if self.is_finished() {
+            return false;
+        }
+        if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            self.exhausted.store(true, Ordering::Relaxed);
+            return false;
+        }
+        self.progress.record_tried(indices.end - indices.start);
+
+        let acquired_at = sampled_at.map(|_| Instant::now());
+        let candidates: Vec<&str> = bufs
+            .iter_mut()
+            .zip(indices.clone())
+            .map(|(buf, index)| stage.generator.candidate(index, buf))
+            .collect();
+        let started_at = Instant::now();
+        let passed = verifier.check_batch(&candidates);
+        let checked_at = sampled_at.map(|_| Instant::now());
+        if let Some(timings) = self.timings {
+            timings.record_fast_check(started_at.elapsed(), candidates.len());
+        }
+        for ((index, candidate), passed) in indices.zip(candidates).zip(passed) {
+            if !passed {
+                continue;
+            }
+            if let Some(survivors) = self.survivors {
+                survivors.record(candidate);
+            }
+            let started_at = Instant::now();
+            let confirmed = self.confirm(verifier, candidate);
+            if let Some(timings) = self.timings {
+                timings.record_verify(started_at.elapsed(), candidate);
+            }
+            match confirmed {
+                Ok(true) => {
+                    hit.store(index, Ordering::Relaxed);
+                    self.found.store(true, Ordering::Relaxed);
+                }
+                Ok(false) => {}
+                Err(error) => {
+                    skipped.lock().expect("worker panicked").push(index);
+                    eprintln!(
+                        "Error while trying \"{}\": {error:#}",
+                        secret::shown(self.redact, candidate)
+                    );
+                }
+            }
+        }
+        if let (Some(sampled_at), Some(acquired_at), Some(checked_at)) =
+            (sampled_at, acquired_at, checked_at)
+        {
+            self.progress.record_steps(
+                started_at - acquired_at,
+                checked_at - started_at,
+                checked_at.elapsed(),
+                acquired_at - sampled_at,
+            );
+        }
+        true
+    }
+
+    /// Puts every candidate of `stage` through full verification only, one at a time, for the
+    /// survivors of an earlier run's fast check.
+    pub fn confirm_stage(&self, number: usize, stage: &Stage) -> StageStats {
+        self.progress.start_stage(number);
+        let started_at = Instant::now();
+        let mut buf = SecretBuf::new();
+        let (mut tried, mut skipped, mut hit) = (0, Vec::new(), false);
+        for index in 0..stage.generator.len() {
+            let Some(_permit) = self.governor.acquire() else {
+                break;
+            };
+            if self.is_finished() {
+                break;
+            }
+            if self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+            {
+                self.exhausted.store(true, Ordering::Relaxed);
+                break;
+            }
+            tried += 1;
+            self.progress.record_tried(1);
+            let candidate = stage.generator.candidate(index, &mut buf);
+            match self.confirm(self.verifier, candidate) {
+                Ok(true) => {
+                    let _ = self.winner.set((number, index));
+                    self.found.store(true, Ordering::Relaxed);
+                    hit = true;
+                }
+                Ok(false) => {}
+                Err(error) => {
+                    eprintln!(
+                        "Error while trying \"{}\": {error:#}",
+                        secret::shown(self.redact, candidate)
+                    );
+                    skipped.push(index);
+                }
+            }
+        }
+        let errors = skipped.len() as u64;
+        StageStats {
+            source: stage.source.clone(),
+            tried,
+            rejected: tried - errors - u64::from(hit),
+            errors,
+            skipped,
+            elapsed: started_at.elapsed(),
+            hit,
+        }
+    }
+
+    /// Tests the candidates a finished stage skipped after errors once more, one at a time.
+    pub fn retry_skipped(&self, number: usize, stage: &Stage, stats: &mut StageStats) {
+        let started_at = Instant::now();
+        let mut buf = SecretBuf::new();
+        let mut still_skipped = Vec::new();
+        for &index in &stats.skipped {
+            if self.is_finished() {
+                still_skipped.push(index);
+                continue;
+            }
+            let candidate = stage.generator.candidate(index, &mut buf);
+            match self.confirm(self.verifier, candidate) {
+                Ok(true) => {
+                    let _ = self.winner.set((number, index));
+                    self.found.store(true, Ordering::Relaxed);
+                    stats.hit = true;
+                }
+                Ok(false) => stats.rejected += 1,
+                Err(error) => {
+                    eprintln!(
+                        "Error while retrying \"{}\": {error:#}",
+                        secret::shown(self.redact, candidate)
+                    );
+                    still_skipped.push(index);
+                }
+            }
+        }
+        stats.errors = still_skipped.len() as u64;
+        stats.skipped = still_skipped;
+        stats.elapsed += started_at.elapsed();
+    }
+
+    /// Confirms `candidate`, retrying transient failures with exponential backoff so they
+    /// don't leave the candidate untested. Other errors would only fail again. An error that
+    /// outlasted retries says how many there were.
+    fn confirm(&self, verifier: &Verifier, candidate: &str) -> Result<bool> {
+        let mut delay = RETRY_BACKOFF;
+        let mut retried = 0;
+        loop {
+            match verifier.confirm(candidate) {
+                Err(error)
+                    if retried < self.retries
+                        && verify::is_transient(&error)
+                        && !self.is_finished() =>
+                {
+                    thread::sleep(delay);
+                    delay *= 2;
+                    retried += 1;
+                }
+                Err(error) if retried > 0 => {
+                    let plural = if retried == 1 { "retry" } else { "retries" };
+                    return Err(error.context(format!("after {retried} {plural}")));
+                }
+                outcome => return outcome,
+            }
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use std::ops::{Deref, DerefMut};
+
+use zeroize::Zeroize;
+
+/// Candidates longer than this make the buffer reallocate, leaving the old allocation
+/// unwiped and unlocked, so it is sized well past any realistic password.
+const CAPACITY: usize = 256;
+
+/// A reusable candidate buffer, kept out of swap where the OS allows it and wiped on drop.
+pub struct SecretBuf {
+    text: String,
+    locked: bool,
+}
+
+impl SecretBuf {
+    pub fn new() -> Self {
+        let text = String::with_capacity(CAPACITY);
+        let locked = lock(text.as_ptr(), text.capacity());
+        Self { text, locked }
+    }
+}
+
+impl Default for SecretBuf {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deref for SecretBuf {
+    type Target = String;
+
+    fn deref(&self) -> &String {
+        &self.text
+    }
+}
+
+impl DerefMut for SecretBuf {
+    fn deref_mut(&mut self) -> &mut String {
+        &mut self.text
+    }
+}
+
+impl Drop for SecretBuf {
+    fn drop(&mut self) {
+        self.text.zeroize();
+        if self.locked {
+            unlock(self.text.as_ptr(), self.text.capacity());
+        }
+    }
+}
+
+/// `password` as it may be printed: `[redacted]` when redaction is on.
+pub fn shown(redact: bool, password: &str) -> &str {
+    if redact { "[redacted]" } else { password }
+}
+
+/// Best effort: an `RLIMIT_MEMLOCK` too small for the buffer just leaves it swappable.
+#[cfg(unix)]
+fn lock(ptr: *const u8, len: usize) -> bool {
+    // SAFETY: the range is one live allocation owned by the caller.
+    len > 0 && unsafe { libc::mlock(ptr.cast(), len) } == 0
+}
+
+#[cfg(unix)]
+fn unlock(ptr: *const u8, len: usize) {
+    // SAFETY: as for `lock`; the allocation is still live while its owner is dropped.
+    unsafe {
+        libc::munlock(ptr.cast(), len);
+    }
+}
+
+#[cfg(not(unix))]
+fn lock(_ptr: *const u8, _len: usize) -> bool {
+    false
+}
+
+#[cfg(not(unix))]
+fn unlock(_ptr: *const u8, _len: usize) {}
@@ -0,0 +1,158 @@
+use std::{
+    io::{Cursor, Read},
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result, bail, ensure};
+use zip::ZipArchive;
+
+use crate::archive::{self, Encryption};
+use crate::control::Governor;
+use crate::cpus;
+use crate::fixture::{self, Method, Scheme};
+use crate::generate::{BruteForce, Candidates, Generator, Hints};
+use crate::search::{Searcher, Stage};
+use crate::status::Progress;
+use crate::verify::{Backend, Verifier};
+
+/// Found by the brute-force stage after a few hundred candidates.
+const PASSWORD: &str = "self-407";
+const DECOYS: usize = 200;
+
+#[derive(clap::Args, Debug)]
+pub struct SelftestArgs {
+    /// Number of worker threads each check uses.
+    #[arg(long, default_value_t = cpus::available())]
+    threads: usize,
+}
+
+/// Cracks a freshly built archive for every scheme, compression method, and backend, and
+/// decrypts the entry with the recovered password.
+pub fn run(args: &SelftestArgs) -> Result<()> {
+    if args.threads == 0 {
+        bail!("--threads must be at least 1");
+    }
+    let mut checks = 0;
+    let mut failures = 0;
+    for scheme in Scheme::ALL {
+        for method in Method::ALL {
+            for backend in backends(scheme) {
+                checks += 1;
+                let label = format!(
+                    "{}, {}, {} backend",
+                    scheme.encryption(),
+                    match method {
+                        Method::Stored => "stored",
+                        Method::Deflate => "deflate",
+                    },
+                    match backend {
+                        Backend::Simd => "simd",
+                        _ => "cpu",
+                    }
+                );
+                match check(scheme, method, backend, args.threads) {
+                    Ok((tried, elapsed)) => {
+                        println!("ok    {label}: {tried} candidates in {elapsed:.2?}");
+                    }
+                    Err(error) => {
+                        failures += 1;
+                        println!("FAIL  {label}: {error:#}");
+                    }
+                }
+            }
+        }
+    }
+    if failures > 0 {
+        bail!("{failures} of {checks} self-test checks failed");
+    }
+    println!("All {checks} checks passed");
+    Ok(())
+}
+
+/// The multi-buffer backend only differs from the scalar one for AES.
+fn backends(scheme: Scheme) -> Vec<Backend> {
+    match scheme {
+        Scheme::Zipcrypto => vec![Backend::Cpu],
+        _ => vec![Backend::Cpu, Backend::Simd],
+    }
+}
+
+fn check(
+    scheme: Scheme,
+    method: Method,
+    backend: Backend,
+    threads: usize,
+) -> Result<(u64, Duration)> {
+    let bytes = fixture::build(scheme, method, PASSWORD, 2)?;
+    let mut reader = Cursor::new(bytes);
+    let entries = archive::read_central_directory(&mut reader)?.entries;
+    let index = archive::select_target(&entries, None, &[])?;
+    let entry = &entries[index];
+    ensure!(
+        entry.encryption == scheme.encryption(),
+        "Central directory reports {}, not {}",
+        entry.encryption,
+        scheme.encryption()
+    );
+    entry.ensure_verifiable()?;
+    let entry_archive = archive::extract_entry_archive(&mut reader, entry)?;
+    let verifier = Verifier::new(entry_archive, entry, backend)?;
+
+    // Decoys that share the password's prefix, then a brute force over its digits.
+    let mut decoys = Candidates::default();
+    for number in 0..DECOYS {
+        decoys.push(&format!("self-{number:03}x"));
+    }
+    let hints = Hints {
+        prefix: "self-".to_owned(),
+        ..Hints::default()
+    };
+    let digits: Vec<char> = ('0'..='9').collect();
+    let stages = [
+        Stage {
+            source: "decoys".to_owned(),
+            generator: Generator::List(decoys),
+        },
+        Stage {
+            source: "brute force".to_owned(),
+            generator: Generator::BruteForce(BruteForce::new(digits, 8, 8, hints)?),
+        },
+    ];
+
+    let started_at = Instant::now();
+    let governor = Governor::new(threads);
+    let progress = Progress::new(&stages, 0);
+    let searcher = Searcher::new(&verifier, &governor, &progress, threads, 64, None, 0);
+    for (number, stage) in stages.iter().enumerate() {
+        if searcher.is_finished() {
+            break;
+        }
+        searcher.run_stage(number, stage, 0, |_| {})?;
+    }
+    let found = searcher.winner(&stages);
+    ensure!(
+        found.as_deref().map(String::as_str) == Some(PASSWORD),
+        "Expected to recover {PASSWORD:?}, got {:?}",
+        found.as_deref()
+    );
+    ensure!(
+        searcher.winner_position() == Some((1, 407)),
+        "Recovered the password at the wrong position {:?}",
+        searcher.winner_position()
+    );
+
+    let mut zip = ZipArchive::new(Cursor::new(verifier.entry_archive()))?;
+    let mut file = if entry.encryption == Encryption::None {
+        zip.by_index(0)?
+    } else {
+        zip.by_index_decrypt(0, PASSWORD.as_bytes())?
+    };
+    let mut content = Vec::new();
+    file.read_to_end(&mut content)
+        .context("Failed to decrypt the entry with the recovered password")?;
+    ensure!(
+        content == fixture::entry_content(1),
+        "Decrypted entry does not match what was archived"
+    );
+    Ok((progress.tried(), started_at.elapsed()))
+}
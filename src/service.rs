@@ -0,0 +1,132 @@
+use std::{io, thread, time::Duration};
+
+use anyhow::Result;
+
+/// Whether a service manager asked to be told about the run, through `NOTIFY_SOCKET`.
+pub fn is_supervised() -> bool {
+    std::env::var_os("NOTIFY_SOCKET").is_some_and(|socket| !socket.is_empty())
+}
+
+/// Tells the service manager the run is up, and keeps its watchdog fed from then on when the
+/// unit sets `WatchdogSec=`.
+pub fn ready() {
+    if !is_supervised() {
+        return;
+    }
+    notify("READY=1");
+    let ours =
+        std::env::var("WATCHDOG_PID").map_or(true, |pid| pid.parse() == Ok(std::process::id()));
+    if let Some(interval) = std::env::var("WATCHDOG_USEC")
+        .ok()
+        .and_then(|usec| usec.parse().ok())
+        .filter(|&usec: &u64| usec > 0 && ours)
+    {
+        thread::spawn(move || {
+            loop {
+                // Half the timeout, as sd_notify(3) recommends.
+                thread::sleep(Duration::from_micros(interval / 2));
+                notify("WATCHDOG=1");
+            }
+        });
+    }
+}
+
+/// Sets the one-line status `systemctl status` shows.
+pub fn status(text: &str) {
+    if is_supervised() {
+        notify(&format!("STATUS={}", text.replace('\n', " ")));
+    }
+}
+
+/// Sends one `sd_notify` state; a manager that stopped listening is not the run's problem.
+#[cfg(unix)]
+fn notify(state: &str) {
+    let Some(socket) = std::env::var_os("NOTIFY_SOCKET") else {
+        return;
+    };
+    let _ = unix::send(std::path::Path::new(&socket), state.as_bytes());
+}
+
+#[cfg(not(unix))]
+fn notify(_state: &str) {}
+
+/// `--events journal`: each event becomes one journal entry with its fields alongside, so
+/// `journalctl -o json` or a log shipper can filter on them.
+pub struct Journal {
+    #[cfg(unix)]
+    socket: std::os::unix::net::UnixDatagram,
+}
+
+impl Journal {
+    #[cfg(unix)]
+    pub fn open() -> Result<Self> {
+        use anyhow::Context;
+
+        let socket = std::os::unix::net::UnixDatagram::unbound()?;
+        socket
+            .connect(JOURNAL_SOCKET)
+            .with_context(|| format!("Failed to connect to the journal at {JOURNAL_SOCKET}"))?;
+        Ok(Self { socket })
+    }
+
+    #[cfg(not(unix))]
+    pub fn open() -> Result<Self> {
+        anyhow::bail!("--events journal needs systemd-journald")
+    }
+
+    /// Sends `MESSAGE`, `PRIORITY` (syslog levels, 3 for errors through 6 for information),
+    /// and `fields`, whose names must already be valid journal field names.
+    pub fn send(&self, message: &str, priority: u8, fields: &[(String, String)]) -> io::Result<()> {
+        let mut entry = Vec::new();
+        let mut field = |name: &str, value: &str| {
+            // Values are sent in the binary form when they span lines.
+            if value.contains('\n') {
+                entry.extend_from_slice(name.as_bytes());
+                entry.push(b'\n');
+                entry.extend_from_slice(&(value.len() as u64).to_le_bytes());
+                entry.extend_from_slice(value.as_bytes());
+            } else {
+                entry.extend_from_slice(format!("{name}={value}").as_bytes());
+            }
+            entry.push(b'\n');
+        };
+        field("MESSAGE", message);
+        field("PRIORITY", &priority.to_string());
+        field("SYSLOG_IDENTIFIER", "unzippoo");
+        for (name, value) in fields {
+            field(name, value);
+        }
+        self.write(&entry)
+    }
+
+    #[cfg(unix)]
+    fn write(&self, entry: &[u8]) -> io::Result<()> {
+        self.socket.send(entry).map(drop)
+    }
+
+    #[cfg(not(unix))]
+    fn write(&self, _entry: &[u8]) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+const JOURNAL_SOCKET: &str = "/run/systemd/journal/socket";
+
+#[cfg(unix)]
+mod unix {
+    use std::{io, os::unix::net::UnixDatagram, path::Path};
+
+    /// Sends a datagram to `path`, or on Linux to the abstract socket `@name`.
+    pub fn send(path: &Path, payload: &[u8]) -> io::Result<()> {
+        let socket = UnixDatagram::unbound()?;
+        #[cfg(target_os = "linux")]
+        if let Some(name) = path.to_str().and_then(|path| path.strip_prefix('@')) {
+            use std::os::linux::net::SocketAddrExt;
+
+            let address = std::os::unix::net::SocketAddr::from_abstract_name(name)?;
+            return socket.send_to_addr(payload, &address).map(drop);
+        }
+        socket.send_to(payload, path).map(drop)
+    }
+}
@@ -0,0 +1,124 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use anyhow::{Context, Result, bail};
+use sha2::{Digest, Sha256};
+
+use crate::archive::Entry;
+use crate::search::Stage;
+use crate::{Args, dedup, spool};
+
+/// The checkpoint a run keeps and whether it resumes from it.
+pub struct Plan {
+    pub path: PathBuf,
+    pub resume: bool,
+    /// The session the checkpoint belongs to, unless it is a `--checkpoint` given by hand.
+    pub session: Option<String>,
+}
+
+/// `--checkpoint` as given, then a `--session` by name, then an automatic session named after
+/// the archive and the attack, so rerunning the same command picks up where it stopped.
+/// Automatic sessions are skipped where there is nowhere to keep them.
+pub fn checkpoint(
+    args: &Args,
+    zip: &Path,
+    entries: &[Entry],
+    entry: &str,
+    stages: &[Stage],
+) -> Result<Option<Plan>> {
+    if let Some(path) = &args.checkpoint {
+        return Ok(Some(Plan {
+            path: path.clone(),
+            resume: args.resume,
+            session: None,
+        }));
+    }
+    if args.survivors_in.is_some() {
+        return Ok(None);
+    }
+    let (name, dir) = match &args.session {
+        Some(name) => (name.clone(), dir()?),
+        None => match dir() {
+            Ok(dir) => (automatic_name(zip, entries, entry, stages)?, dir),
+            Err(_) => return Ok(None),
+        },
+    };
+    if let Err(error) = fs::create_dir_all(&dir) {
+        if args.session.is_none() {
+            return Ok(None);
+        }
+        return Err(error)
+            .with_context(|| format!("Failed to create session directory: {}", dir.display()));
+    }
+    let path = dir.join(format!("{name}.json"));
+    Ok(Some(Plan {
+        resume: !args.no_auto_resume && path.exists(),
+        path,
+        session: Some(name),
+    }))
+}
+
+/// Session names become file names, so path separators and the like are refused.
+pub fn parse_name(value: &str) -> Result<String, String> {
+    let valid = |c: char| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.');
+    if value.is_empty() || value.starts_with('.') || !value.chars().all(valid) {
+        return Err(format!(
+            "invalid session name \"{value}\" (use letters, digits, '-', '_', and '.')"
+        ));
+    }
+    Ok(value.to_owned())
+}
+
+/// The archive's path, size, modification time, and central directory together with the entry
+/// and every stage as it is numbered. Metadata stands in for the contents so that naming a
+/// session never reads a large archive through; an archive touched since starts over.
+fn automatic_name(zip: &Path, entries: &[Entry], entry: &str, stages: &[Stage]) -> Result<String> {
+    let resolved = spool::resolve(zip);
+    let meta = fs::metadata(&resolved)
+        .with_context(|| format!("Failed to read archive metadata: {}", zip.display()))?;
+    let mut digest = Sha256::new();
+    // Stdin is spooled to a new file every run, so only its size and directory identify it.
+    if zip != Path::new("-") {
+        let path = fs::canonicalize(&resolved).unwrap_or_else(|_| resolved.to_path_buf());
+        let modified = meta
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |since| since.as_nanos());
+        digest.update(path.as_os_str().as_encoded_bytes());
+        digest.update(modified.to_le_bytes());
+    }
+    digest.update(meta.len().to_le_bytes());
+    for entry in entries {
+        digest.update(entry.record());
+    }
+    digest.update(dedup::plan(Some(entry), stages));
+    Ok(digest.finalize()[..8]
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect())
+}
+
+/// `$UNZIPPOO_SESSION_DIR`, or `unzippoo/sessions` under the user's state directory.
+fn dir() -> Result<PathBuf> {
+    let env = |name| std::env::var_os(name).filter(|value| !value.is_empty());
+    if let Some(dir) = env("UNZIPPOO_SESSION_DIR") {
+        return Ok(PathBuf::from(dir));
+    }
+    let base = if cfg!(windows) {
+        env("LOCALAPPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        env("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"))
+    } else {
+        env("XDG_STATE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env("HOME").map(|home| PathBuf::from(home).join(".local/state")))
+    };
+    match base {
+        Some(base) => Ok(base.join("unzippoo").join("sessions")),
+        None => bail!("Cannot locate a state directory for sessions; set UNZIPPOO_SESSION_DIR"),
+    }
+}
@@ -0,0 +1,173 @@
+use std::path::Path;
+
+use anyhow::Result;
+use memchr::memmem;
+
+use crate::archive::Entry;
+use crate::extract;
+use crate::preview::looks_like_text;
+use crate::spool;
+
+/// How much of each entry is decrypted and examined.
+const SAMPLE: u64 = 1 << 20;
+/// Above this many bits per byte, content is compressed, encrypted, or random. Compressed
+/// formats are recognized by their magic bytes, so anything else is probably encrypted.
+const HIGH_ENTROPY: f64 = 7.5;
+/// Shorter samples cannot reach a meaningful entropy.
+const MIN_ENTROPY_SAMPLE: usize = 256;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Plain,
+    /// High entropy is expected.
+    Compressed,
+    /// Another layer of encryption.
+    Encrypted,
+}
+
+/// Magic bytes, their offset, the format name, and what the format implies.
+const SIGNATURES: &[(&[u8], usize, &str, Kind)] = &[
+    (b"PK\x03\x04", 0, "ZIP", Kind::Compressed),
+    (b"7z\xbc\xaf\x27\x1c", 0, "7z", Kind::Compressed),
+    (b"Rar!\x1a\x07", 0, "RAR", Kind::Compressed),
+    (b"\x1f\x8b", 0, "gzip", Kind::Compressed),
+    (b"BZh", 0, "bzip2", Kind::Compressed),
+    (b"\xfd7zXZ\x00", 0, "xz", Kind::Compressed),
+    (b"\x28\xb5\x2f\xfd", 0, "zstd", Kind::Compressed),
+    (b"\x89PNG\r\n\x1a\n", 0, "PNG", Kind::Compressed),
+    (b"\xff\xd8\xff", 0, "JPEG", Kind::Compressed),
+    (b"GIF8", 0, "GIF", Kind::Compressed),
+    (b"ftyp", 4, "MP4/QuickTime", Kind::Compressed),
+    (b"%PDF-", 0, "PDF", Kind::Compressed),
+    (
+        b"\xd0\xcf\x11\xe0\xa1\xb1\x1a\xe1",
+        0,
+        "OLE compound file",
+        Kind::Plain,
+    ),
+    (b"SQLite format 3\x00", 0, "SQLite", Kind::Plain),
+    (b"\x7fELF", 0, "ELF executable", Kind::Plain),
+    (b"MZ", 0, "Windows executable", Kind::Plain),
+    (b"Salted__", 0, "OpenSSL enc", Kind::Encrypted),
+    (b"age-encryption.org/v1", 0, "age", Kind::Encrypted),
+    (
+        b"-----BEGIN PGP MESSAGE",
+        0,
+        "OpenPGP (armored)",
+        Kind::Encrypted,
+    ),
+    (b"LUKS\xba\xbe", 0, "LUKS volume", Kind::Encrypted),
+    (b"-FVE-FS-", 3, "BitLocker volume", Kind::Encrypted),
+];
+
+/// What the magic bytes and entropy of one decrypted entry suggest.
+struct Verdict {
+    format: String,
+    entropy: f64,
+    warning: Option<String>,
+}
+
+/// Decrypts the start of every file entry and reports its type and entropy, flagging
+/// anything that looks like it holds another layer of encryption.
+pub fn print_report(zip: &Path, entries: &[Entry], password: &str) -> Result<()> {
+    let mut input = spool::open_archive(zip)?;
+    println!();
+    println!("Decrypted content:");
+    let mut flagged = 0;
+    for entry in entries.iter().filter(|entry| !entry.is_dir()) {
+        let mut sample = Vec::new();
+        if let Err(error) = extract::read_entry(&mut input, entry, password, &mut sample, SAMPLE) {
+            println!("  {}: unreadable ({error:#})", entry.name);
+            continue;
+        }
+        let verdict = sniff(&sample);
+        print!(
+            "  {:<40} {:<22} {:.2} bits/byte",
+            entry.name, verdict.format, verdict.entropy
+        );
+        match &verdict.warning {
+            Some(warning) => {
+                flagged += 1;
+                println!("  (!) {warning}");
+            }
+            None => println!(),
+        }
+    }
+    if flagged > 0 {
+        println!("{flagged} entries may be encrypted again inside");
+    }
+    Ok(())
+}
+
+fn sniff(sample: &[u8]) -> Verdict {
+    let entropy = entropy(sample);
+    let signature = SIGNATURES.iter().find(|(magic, offset, _, _)| {
+        sample
+            .get(*offset..)
+            .is_some_and(|rest| rest.starts_with(magic))
+    });
+    let (format, kind) = match signature {
+        Some((_, _, name, kind)) => ((*name).to_owned(), *kind),
+        None if sample.is_empty() => ("empty".to_owned(), Kind::Plain),
+        None if is_text(sample) => ("text".to_owned(), Kind::Plain),
+        None => ("unknown".to_owned(), Kind::Plain),
+    };
+    let warning = match (format.as_str(), kind) {
+        (_, Kind::Encrypted) => Some(format!("{format} encrypted data")),
+        // General purpose flag bit 0 of the first local header.
+        ("ZIP", _) if sample.get(6).is_some_and(|flags| flags & 1 != 0) => {
+            Some("encrypted ZIP archive".to_owned())
+        }
+        ("PDF", _) if memmem::find(sample, b"/Encrypt").is_some() => {
+            Some("encrypted PDF".to_owned())
+        }
+        ("OLE compound file", _) if memmem::find(sample, &utf16("EncryptedPackage")).is_some() => {
+            Some("password-protected Office document".to_owned())
+        }
+        (_, Kind::Plain) if sample.len() >= MIN_ENTROPY_SAMPLE && entropy > HIGH_ENTROPY => {
+            Some("high entropy for an uncompressed format; possibly encrypted".to_owned())
+        }
+        _ => None,
+    };
+    Verdict {
+        format,
+        entropy,
+        warning,
+    }
+}
+
+/// Shannon entropy in bits per byte: 8 for uniformly random data.
+fn entropy(bytes: &[u8]) -> f64 {
+    if bytes.is_empty() {
+        return 0.0;
+    }
+    let mut counts = [0u64; 256];
+    for &byte in bytes {
+        counts[usize::from(byte)] += 1;
+    }
+    let total = bytes.len() as f64;
+    counts
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f64 / total;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// Valid UTF-8 without control characters, allowing a character cut off by the sample end.
+fn is_text(sample: &[u8]) -> bool {
+    let valid = match std::str::from_utf8(sample) {
+        Ok(text) => text,
+        Err(error) if error.error_len().is_none() => {
+            std::str::from_utf8(&sample[..error.valid_up_to()]).expect("valid prefix")
+        }
+        Err(_) => return false,
+    };
+    looks_like_text(valid)
+}
+
+fn utf16(text: &str) -> Vec<u8> {
+    text.encode_utf16().flat_map(u16::to_le_bytes).collect()
+}
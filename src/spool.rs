@@ -0,0 +1,203 @@
+use std::{
+    borrow::Cow,
+    env,
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    sync::{
+        OnceLock,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use anyhow::{Context, Result, bail};
+
+use crate::paths;
+
+/// The default `--memory-limit`, 256M.
+const DEFAULT_MEMORY_LIMIT: u64 = 256 << 20;
+
+static MEMORY_LIMIT: AtomicU64 = AtomicU64::new(DEFAULT_MEMORY_LIMIT);
+
+/// Where the archive read from stdin can be reopened, once [`stdin_archive`] has run.
+static STDIN: OnceLock<PathBuf> = OnceLock::new();
+
+/// Sets the largest piped archive or decoded wordlist kept in memory; larger ones spill to
+/// disk.
+pub fn set_memory_limit(bytes: u64) {
+    MEMORY_LIMIT.store(bytes, Ordering::Relaxed);
+}
+
+pub fn memory_limit() -> u64 {
+    MEMORY_LIMIT.load(Ordering::Relaxed)
+}
+
+/// The archive read from `--zip -`. Archives are reopened by path wherever they are read, so
+/// it is kept somewhere with one; a named temp file is removed when this is dropped.
+pub struct Spool {
+    temp: Option<PathBuf>,
+    /// Keeps an anonymous copy alive for as long as its path is in use.
+    _file: File,
+}
+
+impl Drop for Spool {
+    fn drop(&mut self) {
+        if let Some(temp) = &self.temp {
+            let _ = fs::remove_file(temp);
+        }
+    }
+}
+
+/// Reads stdin if one of `zips` is `-`. Stdin runs out after one archive, so only one may be.
+pub fn stdin_archive(zips: &[PathBuf]) -> Result<Option<Spool>> {
+    match zips.iter().filter(|zip| *zip == Path::new("-")).count() {
+        0 => Ok(None),
+        1 => stdin().map(Some),
+        _ => bail!("--zip - can only be given once"),
+    }
+}
+
+fn stdin() -> Result<Spool> {
+    if io::IsTerminal::is_terminal(&io::stdin()) {
+        bail!("--zip - reads the archive from stdin, but stdin is a terminal");
+    }
+    let limit = memory_limit();
+    let mut input = io::stdin().lock();
+    let mut head = Vec::new();
+    input
+        .by_ref()
+        .take(limit.saturating_add(1))
+        .read_to_end(&mut head)
+        .context("Failed to read the archive from stdin")?;
+    let (mut file, spool) = if head.len() as u64 <= limit {
+        in_memory()?
+    } else {
+        on_disk()?
+    };
+    file.write_all(&head)
+        .and_then(|()| io::copy(&mut input, &mut file).map(drop))
+        .context("Failed to buffer the archive from stdin")?;
+    Ok(spool)
+}
+
+/// An anonymous in-memory file, reopened through `/proc`.
+#[cfg(target_os = "linux")]
+fn in_memory() -> Result<(File, Spool)> {
+    use std::os::fd::FromRawFd;
+
+    // SAFETY: the name is NUL-terminated, and a valid descriptor is owned by the `File`.
+    let file = unsafe {
+        let fd = libc::memfd_create(c"unzippoo-stdin".as_ptr(), 0);
+        if fd < 0 {
+            return Err(io::Error::last_os_error()).context("Failed to buffer the archive");
+        }
+        File::from_raw_fd(fd)
+    };
+    Ok((file.try_clone()?, reopen_through_proc(file)))
+}
+
+/// Without anonymous in-memory files, small archives go to disk too.
+#[cfg(not(target_os = "linux"))]
+fn in_memory() -> Result<(File, Spool)> {
+    on_disk()
+}
+
+/// A spilled archive: on Linux an unnamed temp file reopened through `/proc`, elsewhere a
+/// named one removed with the [`Spool`].
+#[cfg(target_os = "linux")]
+fn on_disk() -> Result<(File, Spool)> {
+    let file = temp_file()?;
+    Ok((file.try_clone()?, reopen_through_proc(file)))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn on_disk() -> Result<(File, Spool)> {
+    let (file, path) = named_temp_file(|_| {})?;
+    let _ = STDIN.set(path.clone());
+    let spool = Spool {
+        temp: Some(path),
+        _file: file.try_clone()?,
+    };
+    Ok((file, spool))
+}
+
+#[cfg(target_os = "linux")]
+fn reopen_through_proc(file: File) -> Spool {
+    use std::os::fd::AsRawFd;
+
+    let _ = STDIN.set(PathBuf::from(format!("/proc/self/fd/{}", file.as_raw_fd())));
+    Spool {
+        temp: None,
+        _file: file,
+    }
+}
+
+/// A read-write temp file that is gone once closed, even if the process is killed: created with
+/// `O_TMPFILE` where the file system supports it, marked delete-on-close on Windows, and
+/// otherwise unlinked straight after it is created.
+pub fn temp_file() -> Result<File> {
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+
+        if let Ok(file) = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags(libc::O_TMPFILE)
+            .mode(0o600)
+            .open(env::temp_dir())
+        {
+            return Ok(file);
+        }
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::OpenOptionsExt;
+
+        const FILE_FLAG_DELETE_ON_CLOSE: u32 = 0x0400_0000;
+        named_temp_file(|options| {
+            options.custom_flags(FILE_FLAG_DELETE_ON_CLOSE);
+        })
+        .map(|(file, _)| file)
+    }
+    #[cfg(not(windows))]
+    {
+        let (file, path) = named_temp_file(|_| {})?;
+        fs::remove_file(&path).with_context(|| format!("Failed to unlink {}", path.display()))?;
+        Ok(file)
+    }
+}
+
+/// Creates a fresh file in the temp directory, readable only by us on Unix.
+fn named_temp_file(configure: impl Fn(&mut OpenOptions)) -> Result<(File, PathBuf)> {
+    for attempt in 0u32.. {
+        let path = env::temp_dir().join(format!("unzippoo-{}-{attempt}.tmp", std::process::id()));
+        let mut options = OpenOptions::new();
+        options.read(true).write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        configure(&mut options);
+        match options.open(&path) {
+            Ok(file) => return Ok((file, path)),
+            Err(error) if error.kind() == io::ErrorKind::AlreadyExists => {}
+            Err(error) => {
+                return Err(error).with_context(|| format!("Failed to create {}", path.display()));
+            }
+        }
+    }
+    unreachable!("some attempt finds a free name")
+}
+
+/// The path to read an archive from: the spooled copy for `-`, otherwise `path` in the form
+/// [`paths::extended`] gives.
+pub fn resolve(path: &Path) -> Cow<'_, Path> {
+    match STDIN.get() {
+        Some(spooled) if path == Path::new("-") => Cow::Borrowed(spooled),
+        _ => paths::extended(path),
+    }
+}
+
+/// Opens the archive at `path`, or the one read from stdin for `-`.
+pub fn open_archive(path: &Path) -> Result<File> {
+    File::open(resolve(path)).with_context(|| format!("Failed to open archive: {}", path.display()))
+}
@@ -0,0 +1,237 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::PathBuf,
+};
+
+use anyhow::{Context, Result, bail};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::vault::{self, KeySource, Sealed, Vault};
+
+#[derive(clap::Args, Debug)]
+pub struct StatsArgs {
+    /// Potfiles and `--result-file` reports to aggregate.
+    #[arg(required = true, value_name = "FILE")]
+    files: Vec<PathBuf>,
+
+    /// How many base words and sources to list.
+    #[arg(long, value_name = "N", default_value_t = 10)]
+    top: usize,
+
+    /// Print the aggregate as JSON instead of a table.
+    #[arg(long)]
+    json: bool,
+
+    /// Decrypt records sealed with this key source (`keyring` or `env:NAME`).
+    #[arg(long, value_name = "SOURCE", value_parser = vault::parse_key_source)]
+    store_key: Option<KeySource>,
+}
+
+/// A recovered password and the stage that found it, from either kind of file.
+struct Hit {
+    password: String,
+    source: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct Summary {
+    passwords: usize,
+    lengths: BTreeMap<usize, usize>,
+    charsets: BTreeMap<String, usize>,
+    base_words: Vec<Count>,
+    sources: Vec<Count>,
+}
+
+#[derive(Debug, Serialize)]
+struct Count {
+    value: String,
+    count: usize,
+}
+
+pub fn run(args: &StatsArgs) -> Result<()> {
+    let vault = args.store_key.as_ref().map(Vault::open).transpose()?;
+    let hits = read_hits(&args.files, vault.as_ref())?;
+
+    let summary = summarize(&hits, args.top);
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&summary)?);
+    } else {
+        print(&summary);
+    }
+    Ok(())
+}
+
+/// The passwords recovered in potfiles and result files, each recovery once.
+pub fn recovered_passwords(files: &[PathBuf], vault: Option<&Vault>) -> Result<Vec<String>> {
+    let hits = read_hits(files, vault)?;
+    Ok(hits.into_iter().map(|hit| hit.password).collect())
+}
+
+fn read_hits(files: &[PathBuf], vault: Option<&Vault>) -> Result<Vec<Hit>> {
+    let mut hits = Vec::new();
+    let mut seen = BTreeSet::new();
+    for path in files {
+        let bytes = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        read_values(&bytes, vault, &mut |value| {
+            collect(value, &mut seen, &mut hits)
+        })
+        .with_context(|| format!("Unreadable record in {}", path.display()))?;
+    }
+    if hits.is_empty() {
+        bail!("No recovered passwords in the given files");
+    }
+    Ok(hits)
+}
+
+/// Potfiles are JSON lines and result files pretty-printed values, so reads a stream; sealed
+/// records are opened and read the same way.
+fn read_values(bytes: &[u8], vault: Option<&Vault>, each: &mut dyn FnMut(&Value)) -> Result<()> {
+    for value in serde_json::Deserializer::from_slice(bytes).into_iter::<Value>() {
+        let value = value?;
+        if value.get("encrypted").is_some() {
+            let Some(vault) = vault else {
+                bail!("Records are encrypted; pass --store-key");
+            };
+            let sealed: Sealed = serde_json::from_value(value)?;
+            read_values(&vault.unseal(&sealed)?, None, each)?;
+        } else {
+            each(&value);
+        }
+    }
+    Ok(())
+}
+
+/// Takes potfile records and found results; batch result files are arrays of results. A
+/// recovery that is in both a potfile and a result file is counted once.
+fn collect(value: &Value, seen: &mut BTreeSet<String>, hits: &mut Vec<Hit>) {
+    match value {
+        Value::Array(values) => values.iter().for_each(|value| collect(value, seen, hits)),
+        Value::Object(record) => {
+            let found = record
+                .get("status")
+                .is_none_or(|status| status.as_str() == Some("found"));
+            let Some(password) = record
+                .get("password")
+                .and_then(Value::as_str)
+                .filter(|_| found)
+            else {
+                return;
+            };
+            if let Some(archive) = record.get("archive").and_then(Value::as_str) {
+                let entry = record.get("entry").and_then(Value::as_str).unwrap_or("");
+                if !seen.insert(format!("{archive}\0{entry}\0{password}")) {
+                    return;
+                }
+            }
+            let source = record
+                .get("source")
+                .or_else(|| record.get("provenance").and_then(|p| p.get("source")))
+                .and_then(Value::as_str);
+            hits.push(Hit {
+                password: password.to_owned(),
+                source: source.map(str::to_owned),
+            });
+        }
+        _ => {}
+    }
+}
+
+fn summarize(hits: &[Hit], top: usize) -> Summary {
+    let mut summary = Summary {
+        passwords: hits.len(),
+        ..Summary::default()
+    };
+    let mut base_words = BTreeMap::new();
+    let mut sources = BTreeMap::new();
+    for hit in hits {
+        *summary
+            .lengths
+            .entry(hit.password.chars().count())
+            .or_default() += 1;
+        *summary.charsets.entry(charset(&hit.password)).or_default() += 1;
+        if let Some(word) = base_word(&hit.password) {
+            *base_words.entry(word).or_default() += 1;
+        }
+        let source = hit.source.as_deref().unwrap_or("unknown");
+        *sources.entry(source.to_owned()).or_default() += 1;
+    }
+    summary.base_words = most_common(base_words, top);
+    summary.sources = most_common(sources, top);
+    summary
+}
+
+/// The character classes a password uses, e.g. `lower+digit`.
+fn charset(password: &str) -> String {
+    let classes = [
+        ("lower", password.chars().any(|c| c.is_ascii_lowercase())),
+        ("upper", password.chars().any(|c| c.is_ascii_uppercase())),
+        ("digit", password.chars().any(|c| c.is_ascii_digit())),
+        (
+            "symbol",
+            password
+                .chars()
+                .any(|c| c.is_ascii_punctuation() || c == ' '),
+        ),
+        ("other", !password.is_ascii()),
+    ];
+    let used: Vec<&str> = classes
+        .iter()
+        .filter(|(_, used)| *used)
+        .map(|(name, _)| *name)
+        .collect();
+    if used.is_empty() {
+        "empty".to_owned()
+    } else {
+        used.join("+")
+    }
+}
+
+/// The password with leading and trailing digits and symbols removed, lowercased, if at least
+/// three letters remain: `Summer2024!` is `summer`.
+fn base_word(password: &str) -> Option<String> {
+    let word = password.trim_matches(|c: char| !c.is_alphabetic());
+    (word.chars().count() >= 3).then(|| word.to_lowercase())
+}
+
+fn most_common(counts: BTreeMap<String, usize>, top: usize) -> Vec<Count> {
+    let mut counts: Vec<Count> = counts
+        .into_iter()
+        .map(|(value, count)| Count { value, count })
+        .collect();
+    // Stable, so ties stay alphabetical.
+    counts.sort_by_key(|count| std::cmp::Reverse(count.count));
+    counts.truncate(top);
+    counts
+}
+
+fn print(summary: &Summary) {
+    let percent = |count: usize| 100.0 * count as f64 / summary.passwords as f64;
+    println!("{} recovered passwords", summary.passwords);
+    println!("Lengths:");
+    for (length, count) in &summary.lengths {
+        println!("  {length:>3}: {count:>6} ({:.1}%)", percent(*count));
+    }
+    println!("Character classes:");
+    let mut charsets: Vec<(&String, &usize)> = summary.charsets.iter().collect();
+    charsets.sort_by_key(|&(_, count)| std::cmp::Reverse(*count));
+    for (charset, count) in charsets {
+        println!("  {charset}: {count} ({:.1}%)", percent(*count));
+    }
+    if !summary.base_words.is_empty() {
+        println!("Top base words:");
+        for word in &summary.base_words {
+            println!("  {}: {}", word.value, word.count);
+        }
+    }
+    println!("Found by:");
+    for source in &summary.sources {
+        println!(
+            "  {}: {} ({:.1}%)",
+            source.value,
+            source.count,
+            percent(source.count)
+        );
+    }
+}
@@ -0,0 +1,339 @@
+use std::{
+    path::PathBuf,
+    sync::{
+        Arc, OnceLock,
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+
+use crate::control::Governor;
+use crate::report::{self, Status};
+use crate::search::Stage;
+use crate::vault;
+
+const STATUS_INTERVAL: Duration = Duration::from_secs(2);
+const STOP_POLL: Duration = Duration::from_millis(100);
+
+/// How sampled worker time splits between building candidates, the fast check, full
+/// verification, and waiting for a pause or throttle to lift, as fractions of the whole.
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct Breakdown {
+    pub generate: f64,
+    pub fast_check: f64,
+    pub verify: f64,
+    pub stalled: f64,
+    /// Times a worker had to wait for a permit.
+    pub stalls: u64,
+}
+
+impl Breakdown {
+    /// Which step holds the run back, in the terms `--verbose` prints.
+    pub fn bottleneck(&self, major_page_faults: u64) -> &'static str {
+        let crypto = self.fast_check + self.verify;
+        if self.stalled > self.generate.max(crypto) {
+            "held back by pausing or throttling"
+        } else if self.generate > crypto && major_page_faults > 0 {
+            "I/O-bound: building candidates waits on wordlist pages read from storage"
+        } else if self.generate > crypto {
+            "generator-bound"
+        } else {
+            "crypto-bound"
+        }
+    }
+}
+
+/// Worker time in each [`Breakdown`] step, in nanoseconds.
+#[derive(Default)]
+struct StepTimes {
+    generate: AtomicU64,
+    fast_check: AtomicU64,
+    verify: AtomicU64,
+    stalled: AtomicU64,
+}
+
+/// Live counters the search updates and monitors read.
+pub struct Progress {
+    started_at: Instant,
+    /// Candidates a resumed run skips because an earlier run already tested them.
+    resumed_at: u64,
+    sources: Vec<String>,
+    lens: Vec<u64>,
+    keyspace: u64,
+    /// Relative per-candidate cost of each stage, from `tune::stage_costs`.
+    costs: OnceLock<Vec<f64>>,
+    stage: AtomicUsize,
+    tried: AtomicU64,
+    steps: StepTimes,
+    /// Bits of the `--counts` coverage of the current stage, or `u64::MAX` for none.
+    coverage: AtomicU64,
+}
+
+impl Progress {
+    pub fn new(stages: &[Stage], resumed_at: u64) -> Self {
+        Self {
+            started_at: Instant::now(),
+            resumed_at,
+            sources: stages.iter().map(|stage| stage.source.clone()).collect(),
+            lens: stages.iter().map(|stage| stage.generator.len()).collect(),
+            costs: OnceLock::new(),
+            keyspace: stages.iter().fold(0u64, |total, stage| {
+                total.saturating_add(stage.generator.len())
+            }),
+            stage: AtomicUsize::new(0),
+            tried: AtomicU64::new(0),
+            steps: StepTimes::default(),
+            coverage: AtomicU64::new(u64::MAX),
+        }
+    }
+
+    pub fn start_stage(&self, stage: usize) {
+        self.stage.store(stage, Ordering::Relaxed);
+    }
+
+    pub fn record_tried(&self, count: u64) {
+        self.tried.fetch_add(count, Ordering::Relaxed);
+    }
+
+    pub fn tried(&self) -> u64 {
+        self.tried.load(Ordering::Relaxed)
+    }
+
+    /// The share of a counted wordlist's total count the current stage has tried.
+    pub fn set_coverage(&self, coverage: Option<f64>) {
+        let bits = coverage.map_or(u64::MAX, f64::to_bits);
+        self.coverage.store(bits, Ordering::Relaxed);
+    }
+
+    pub fn coverage(&self) -> Option<f64> {
+        let bits = self.coverage.load(Ordering::Relaxed);
+        (bits != u64::MAX).then(|| f64::from_bits(bits))
+    }
+
+    /// Adds one sampled batch's time in each step.
+    pub fn record_steps(
+        &self,
+        generate: Duration,
+        fast_check: Duration,
+        verify: Duration,
+        stalled: Duration,
+    ) {
+        let add = |total: &AtomicU64, time: Duration| {
+            total.fetch_add(time.as_nanos() as u64, Ordering::Relaxed);
+        };
+        add(&self.steps.generate, generate);
+        add(&self.steps.fast_check, fast_check);
+        add(&self.steps.verify, verify);
+        add(&self.steps.stalled, stalled);
+    }
+
+    /// The split of the sampled time so far, once there is any, with `stalls` from the governor.
+    pub fn breakdown(&self, stalls: u64) -> Option<Breakdown> {
+        let steps = [
+            &self.steps.generate,
+            &self.steps.fast_check,
+            &self.steps.verify,
+            &self.steps.stalled,
+        ]
+        .map(|step| step.load(Ordering::Relaxed) as f64);
+        let total: f64 = steps.iter().sum();
+        if total == 0.0 {
+            return None;
+        }
+        let [generate, fast_check, verify, stalled] = steps.map(|step| step / total);
+        Some(Breakdown {
+            generate,
+            fast_check,
+            verify,
+            stalled,
+            stalls,
+        })
+    }
+
+    pub fn set_costs(&self, costs: Vec<f64>) {
+        let _ = self.costs.set(costs);
+    }
+
+    /// Seconds left in the current stage and in the whole run at `rate`, the current stage's
+    /// throughput. Later stages are scaled by their cost relative to the current one, when known.
+    pub fn eta(&self, rate: f64) -> Option<(f64, f64)> {
+        if rate <= 0.0 {
+            return None;
+        }
+        let stage = self.stage.load(Ordering::Relaxed);
+        let done = self.resumed_at.saturating_add(self.tried());
+        let stage_end = self.lens[..=stage.min(self.lens.len() - 1)]
+            .iter()
+            .fold(0u64, |total, &len| total.saturating_add(len));
+        let stage_eta = stage_end.saturating_sub(done) as f64 / rate;
+        let scale = |later: usize| match self.costs.get() {
+            Some(costs) if costs[stage] > 0.0 => costs[later] / costs[stage],
+            _ => 1.0,
+        };
+        let later: f64 = (stage + 1..self.lens.len())
+            .map(|later| self.lens[later] as f64 / rate * scale(later))
+            .sum();
+        Some((stage_eta, stage_eta + later))
+    }
+}
+
+#[derive(Serialize)]
+struct Snapshot<'a> {
+    phase: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    status: Option<Status>,
+    archive: &'a str,
+    entry: &'a str,
+    stage: usize,
+    stages: usize,
+    source: &'a str,
+    tried: u64,
+    keyspace: u64,
+    progress: f64,
+    rate: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stage_eta_seconds: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    eta_seconds: Option<f64>,
+    elapsed_seconds: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    coverage: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    worker_time: Option<Breakdown>,
+    updated_at: u64,
+}
+
+/// Rewrites the `--status-file` document in the background until finished.
+pub struct StatusWriter {
+    shared: Arc<Shared>,
+    handle: Option<JoinHandle<()>>,
+}
+
+struct Shared {
+    path: PathBuf,
+    archive: String,
+    entry: String,
+    progress: Arc<Progress>,
+    governor: Arc<Governor>,
+    stop: AtomicBool,
+}
+
+pub fn spawn_status_writer(
+    path: PathBuf,
+    archive: String,
+    entry: String,
+    progress: Arc<Progress>,
+    governor: Arc<Governor>,
+) -> StatusWriter {
+    let shared = Arc::new(Shared {
+        path,
+        archive,
+        entry,
+        progress,
+        governor,
+        stop: AtomicBool::new(false),
+    });
+
+    let handle = {
+        let shared = Arc::clone(&shared);
+        thread::spawn(move || {
+            let mut last = (Instant::now(), shared.progress.tried());
+            let mut warned = false;
+            shared.write(None, 0.0, &mut warned);
+            while !shared.stop.load(Ordering::Relaxed) {
+                let next = last.0 + STATUS_INTERVAL;
+                while Instant::now() < next && !shared.stop.load(Ordering::Relaxed) {
+                    thread::sleep(STOP_POLL);
+                }
+                let now = (Instant::now(), shared.progress.tried());
+                let rate = (now.1 - last.1) as f64 / (now.0 - last.0).as_secs_f64();
+                shared.write(None, rate, &mut warned);
+                last = now;
+            }
+        })
+    };
+
+    StatusWriter {
+        shared,
+        handle: Some(handle),
+    }
+}
+
+impl StatusWriter {
+    /// Stops the background updates and records how the run ended.
+    pub fn finish(mut self, status: Status) {
+        self.stop();
+        let elapsed = self.shared.progress.started_at.elapsed().as_secs_f64();
+        let rate = self.shared.progress.tried() as f64 / elapsed.max(f64::EPSILON);
+        self.shared.write(Some(status), rate, &mut false);
+    }
+
+    fn stop(&mut self) {
+        self.shared.stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for StatusWriter {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+impl Shared {
+    fn write(&self, status: Option<Status>, rate: f64, warned: &mut bool) {
+        let progress = &self.progress;
+        let tried = progress.tried();
+        let stage = progress.stage.load(Ordering::Relaxed);
+        let done = progress.resumed_at.saturating_add(tried);
+        let eta = progress.eta(rate).filter(|_| status.is_none());
+        let phase = match status {
+            Some(_) => "finished",
+            None if self.governor.is_paused() => "paused",
+            None => "searching",
+        };
+        let snapshot = Snapshot {
+            phase,
+            status,
+            archive: &self.archive,
+            entry: &self.entry,
+            stage: stage + 1,
+            stages: progress.sources.len(),
+            source: progress.sources.get(stage).map_or("", String::as_str),
+            tried,
+            keyspace: progress.keyspace,
+            progress: done as f64 / progress.keyspace.max(1) as f64,
+            rate,
+            stage_eta_seconds: eta.map(|(stage, _)| stage),
+            eta_seconds: eta.map(|(_, total)| total),
+            elapsed_seconds: progress.started_at.elapsed().as_secs_f64(),
+            coverage: progress.coverage(),
+            worker_time: progress.breakdown(self.governor.stalls()),
+            updated_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |since| since.as_secs()),
+        };
+        // The status names the archive and its progress, so it is sealed like checkpoints.
+        let written = match vault::installed() {
+            Some(vault) => serde_json::to_vec(&snapshot)
+                .map_err(anyhow::Error::from)
+                .and_then(|plaintext| vault.seal(&plaintext))
+                .and_then(|sealed| report::write_json_atomically(&self.path, &sealed)),
+            None => report::write_json_atomically(&self.path, &snapshot),
+        };
+        if let Err(error) = written
+            && !*warned
+        {
+            eprintln!(
+                "Failed to write status file {}: {error:#}",
+                self.path.display()
+            );
+            *warned = true;
+        }
+    }
+}
@@ -0,0 +1,218 @@
+use serde::Serialize;
+
+/// Keyboard rows a walk can follow, in both directions.
+const KEYBOARD_ROWS: [&str; 4] = ["1234567890", "qwertyuiop", "asdfghjkl", "zxcvbnm"];
+/// Shortest run that counts as a repeat, sequence, or keyboard walk.
+const MIN_PATTERN_LEN: usize = 3;
+
+/// Guessing rates for the usual attack scenarios, in guesses per second.
+const SCENARIOS: [(&str, f64); 4] = [
+    ("online_throttled", 100.0 / 3600.0),
+    ("online_unthrottled", 10.0),
+    ("offline_slow_hash", 1e4),
+    ("offline_fast_hash", 1e10),
+];
+
+/// A zxcvbn-style estimate of how many guesses the password takes.
+#[derive(Debug, Serialize)]
+pub struct Strength {
+    /// 0 (trivial) to 4 (strong), on zxcvbn's guess thresholds.
+    pub score: u8,
+    pub guesses_log2: f64,
+    /// The cheapest way to cover the password with patterns and brute force.
+    pub patterns: Vec<Pattern>,
+    pub crack_times_seconds: Vec<CrackTime>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Pattern {
+    pub kind: &'static str,
+    pub token: String,
+    pub guesses_log2: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CrackTime {
+    pub scenario: &'static str,
+    pub seconds: f64,
+}
+
+/// Estimates `password`'s strength; `wordlist_rank` is its 1-based position in the wordlist
+/// it was found in, if any, which bounds the guesses from above.
+pub fn analyze(password: &str, wordlist_rank: Option<u64>) -> Strength {
+    let chars: Vec<char> = password.chars().collect();
+    let mut candidates = find_patterns(&chars);
+    if let Some(rank) = wordlist_rank {
+        candidates.push((0, chars.len(), "wordlist", (rank as f64).log2()));
+    }
+
+    // Cheapest cover of chars[..end], in log2 guesses, with the match that ends it.
+    let mut best: Vec<(f64, Option<usize>)> = vec![(f64::INFINITY, None); chars.len() + 1];
+    best[0] = (0.0, None);
+    for end in 1..=chars.len() {
+        let brute = best[end - 1].0 + cardinality(chars[end - 1]).log2();
+        best[end] = (brute, None);
+        for (index, &(start, stop, _, guesses)) in candidates.iter().enumerate() {
+            if stop == end && best[start].0 + guesses < best[end].0 {
+                best[end] = (best[start].0 + guesses, Some(index));
+            }
+        }
+    }
+
+    let mut patterns = Vec::new();
+    let mut end = chars.len();
+    let mut brute_end = end;
+    while end > 0 {
+        match best[end].1 {
+            Some(index) => {
+                let (start, stop, kind, guesses) = candidates[index];
+                push_brute_force(&mut patterns, &chars[stop..brute_end]);
+                patterns.push(Pattern {
+                    kind,
+                    token: chars[start..stop].iter().collect(),
+                    guesses_log2: guesses,
+                });
+                end = start;
+                brute_end = start;
+            }
+            None => end -= 1,
+        }
+    }
+    push_brute_force(&mut patterns, &chars[..brute_end]);
+    patterns.reverse();
+
+    let guesses_log2 = best[chars.len()].0.max(0.0);
+    let guesses = guesses_log2.exp2();
+    let score = [1e3, 1e6, 1e8, 1e10]
+        .iter()
+        .take_while(|&&threshold| guesses >= threshold)
+        .count() as u8;
+    Strength {
+        score,
+        guesses_log2,
+        patterns,
+        crack_times_seconds: SCENARIOS
+            .iter()
+            .map(|&(scenario, rate)| CrackTime {
+                scenario,
+                seconds: guesses / rate,
+            })
+            .collect(),
+    }
+}
+
+/// `tokens` is false while passwords are redacted, since the patterns quote pieces of it.
+pub fn print(strength: &Strength, tokens: bool) {
+    println!(
+        "Password strength: {}/4 (about 2^{:.1} guesses)",
+        strength.score, strength.guesses_log2
+    );
+    for pattern in strength.patterns.iter().filter(|_| tokens) {
+        println!(
+            "  {} \"{}\": 2^{:.1} guesses",
+            pattern.kind, pattern.token, pattern.guesses_log2
+        );
+    }
+    for time in &strength.crack_times_seconds {
+        println!(
+            "  {}: {}",
+            time.scenario.replace('_', " "),
+            describe(time.seconds)
+        );
+    }
+}
+
+fn push_brute_force(patterns: &mut Vec<Pattern>, chars: &[char]) {
+    if !chars.is_empty() {
+        patterns.push(Pattern {
+            kind: "brute force",
+            token: chars.iter().collect(),
+            guesses_log2: chars.iter().map(|&c| cardinality(c).log2()).sum(),
+        });
+    }
+}
+
+/// Every repeat, sequence, keyboard walk, and year in the password, as
+/// `(start, end, kind, log2 guesses)`.
+fn find_patterns(chars: &[char]) -> Vec<(usize, usize, &'static str, f64)> {
+    let mut found = Vec::new();
+    for start in 0..chars.len() {
+        for end in start + MIN_PATTERN_LEN..=chars.len() {
+            let token = &chars[start..end];
+            let len = token.len() as f64;
+            if token.iter().all(|&c| c == token[0]) {
+                found.push((start, end, "repeat", (cardinality(token[0]) * len).log2()));
+            }
+            let step = token[1] as i64 - token[0] as i64;
+            if step.abs() == 1
+                && token
+                    .windows(2)
+                    .all(|pair| pair[1] as i64 - pair[0] as i64 == step)
+            {
+                let obvious = matches!(token[0], 'a' | 'A' | 'z' | 'Z' | '0' | '1' | '9');
+                let first = if obvious { 4.0 } else { 26.0 };
+                found.push((start, end, "sequence", (first * len * 2.0).log2()));
+            }
+            if is_keyboard_walk(token) {
+                found.push((start, end, "keyboard walk", (40.0 * len).log2()));
+            }
+        }
+        if let Some(year) = chars.get(start..start + 4)
+            && matches!(year, ['1', '9', ..] | ['2', '0', ..])
+            && year.iter().all(char::is_ascii_digit)
+        {
+            found.push((start, start + 4, "year", 120f64.log2()));
+        }
+    }
+    found
+}
+
+fn is_keyboard_walk(token: &[char]) -> bool {
+    let lower: String = token.iter().map(char::to_ascii_lowercase).collect();
+    let reversed: String = lower.chars().rev().collect();
+    KEYBOARD_ROWS
+        .iter()
+        .any(|row| row.contains(&lower) || row.contains(&reversed))
+}
+
+/// Size of the character class a brute force would have to cover for `c`.
+fn cardinality(c: char) -> f64 {
+    if c.is_ascii_lowercase() || c.is_ascii_uppercase() {
+        26.0
+    } else if c.is_ascii_digit() {
+        10.0
+    } else if c.is_ascii() {
+        33.0
+    } else {
+        100.0
+    }
+}
+
+pub fn describe(seconds: f64) -> String {
+    const UNITS: [(&str, f64); 6] = [
+        ("minute", 60.0),
+        ("hour", 3600.0),
+        ("day", 86400.0),
+        ("month", 2_629_800.0),
+        ("year", 31_557_600.0),
+        ("century", 3_155_760_000.0),
+    ];
+    if seconds < 1.0 {
+        return "less than a second".to_owned();
+    }
+    if seconds >= 100.0 * UNITS[5].1 {
+        return "centuries".to_owned();
+    }
+    let (unit, size) = UNITS
+        .iter()
+        .rev()
+        .find(|&&(_, size)| seconds >= size)
+        .copied()
+        .unwrap_or(("second", 1.0));
+    let count = (seconds / size).round() as u64;
+    match (unit, count) {
+        (unit, 1) => format!("1 {unit}"),
+        ("century", count) => format!("{count} centuries"),
+        (unit, count) => format!("{count} {unit}s"),
+    }
+}
@@ -0,0 +1,92 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::report::{self, Status};
+
+/// One archive's row of `--summary`, the same in the JSON and the CSV file.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Row {
+    pub archive: String,
+    pub entry: Option<String>,
+    /// The target entry's encryption, unless the archive could not be read.
+    pub scheme: Option<String>,
+    pub status: Status,
+    pub password: Option<String>,
+    /// The stage that found the password.
+    pub stage: Option<String>,
+    pub elapsed_seconds: f64,
+    pub finished_at: u64,
+}
+
+const COLUMNS: &str = "archive,entry,scheme,status,password,stage,elapsed_seconds,finished_at";
+
+/// The time rows are stamped with, so the runs appended to one summary can be told apart.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs())
+}
+
+/// Appends `rows` to `path` with a `.json` and a `.csv` extension, creating either file
+/// as needed. The JSON file stays one array, rewritten atomically; the CSV file is appended to
+/// and gets its header when it is new.
+pub fn append(path: &Path, rows: Vec<Row>) -> Result<()> {
+    let json = path.with_extension("json");
+    let mut all: Vec<Row> = match fs::read(&json) {
+        Ok(raw) => serde_json::from_slice(&raw)
+            .with_context(|| format!("Invalid summary: {}", json.display()))?,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(error) => {
+            return Err(error)
+                .with_context(|| format!("Failed to read summary: {}", json.display()));
+        }
+    };
+    let appended = all.len();
+    all.extend(rows);
+    report::write_json_atomically(&json, &all)
+        .with_context(|| format!("Failed to write summary: {}", json.display()))?;
+
+    let csv = path.with_extension("csv");
+    let mut text = String::new();
+    if fs::metadata(&csv).map_or(true, |meta| meta.len() == 0) {
+        text.push_str(COLUMNS);
+        text.push('\n');
+    }
+    for row in &all[appended..] {
+        let cells = [
+            row.archive.clone(),
+            row.entry.clone().unwrap_or_default(),
+            row.scheme.clone().unwrap_or_default(),
+            row.status.as_str().to_owned(),
+            row.password.clone().unwrap_or_default(),
+            row.stage.clone().unwrap_or_default(),
+            format!("{:.3}", row.elapsed_seconds),
+            row.finished_at.to_string(),
+        ];
+        let cells: Vec<String> = cells.iter().map(|cell| quote(cell)).collect();
+        text.push_str(&cells.join(","));
+        text.push('\n');
+    }
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&csv)
+        .and_then(|mut file| file.write_all(text.as_bytes()))
+        .with_context(|| format!("Failed to append to summary: {}", csv.display()))
+}
+
+/// Quotes a cell that holds a comma, a quote, or a line break, doubling its quotes.
+fn quote(cell: &str) -> String {
+    if cell.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_owned()
+    }
+}
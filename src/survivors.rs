@@ -0,0 +1,111 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::Path,
+    sync::Mutex,
+};
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::generate::Candidates;
+use crate::vault::{self, Sealed};
+
+/// One candidate that passed the fast check.
+#[derive(Serialize, Deserialize)]
+struct Record {
+    entry: String,
+    candidate: String,
+}
+
+/// Candidates that pass the fast check, written as JSON lines as the run finds them so
+/// `--survivors-in` can later put just these through full verification again. The password
+/// itself is one of them, so with `--store-key` every line is sealed.
+pub struct SurvivorLog {
+    entry: String,
+    /// The first write error is kept for [`SurvivorLog::finish`]; the run goes on regardless.
+    writer: Mutex<(BufWriter<File>, io::Result<()>)>,
+}
+
+impl SurvivorLog {
+    pub fn create(path: &Path, entry: &str) -> Result<Self> {
+        let file = File::create(path)
+            .with_context(|| format!("Failed to create survivors file: {}", path.display()))?;
+        Ok(Self {
+            entry: entry.to_owned(),
+            writer: Mutex::new((BufWriter::new(file), Ok(()))),
+        })
+    }
+
+    pub fn record(&self, candidate: &str) {
+        let line = (|| -> Result<Vec<u8>> {
+            let record = Record {
+                entry: self.entry.clone(),
+                candidate: candidate.to_owned(),
+            };
+            let mut line = serde_json::to_vec(&record)?;
+            if let Some(vault) = vault::installed() {
+                line = serde_json::to_vec(&vault.seal(&line)?)?;
+            }
+            line.push(b'\n');
+            Ok(line)
+        })();
+        let mut writer = self
+            .writer
+            .lock()
+            .expect("no worker panics holding the lock");
+        let (file, result) = &mut *writer;
+        if result.is_ok() {
+            *result = line
+                .map_err(io::Error::other)
+                .and_then(|line| file.write_all(&line));
+        }
+    }
+
+    pub fn finish(self, path: &Path) -> Result<()> {
+        let (mut file, result) = self
+            .writer
+            .into_inner()
+            .expect("no worker panics holding the lock");
+        result
+            .and_then(|()| file.flush())
+            .with_context(|| format!("Failed to write survivors file: {}", path.display()))
+    }
+}
+
+/// Reads a `--survivors-out` file: the entry its candidates were recorded for, and the
+/// candidates in the order they were found.
+pub fn load(path: &Path) -> Result<(Option<String>, Candidates)> {
+    let bytes = fs::read(path)
+        .with_context(|| format!("Failed to read survivors file: {}", path.display()))?;
+    let mut entry = None;
+    let mut candidates = Candidates::default();
+    for value in serde_json::Deserializer::from_slice(&bytes).into_iter::<Value>() {
+        let value = value.with_context(|| format!("Invalid survivors file: {}", path.display()))?;
+        let record: Record = if value.get("encrypted").is_some() {
+            let Some(vault) = vault::installed() else {
+                bail!(
+                    "Survivors in {} are encrypted; pass --store-key",
+                    path.display()
+                );
+            };
+            let sealed: Sealed = serde_json::from_value(value)?;
+            serde_json::from_slice(&vault.unseal(&sealed)?)?
+        } else {
+            serde_json::from_value(value)
+                .with_context(|| format!("Invalid survivors file: {}", path.display()))?
+        };
+        match &entry {
+            None => entry = Some(record.entry),
+            Some(first) if *first != record.entry => bail!(
+                "{} mixes survivors of \"{first}\" and \"{}\"",
+                path.display(),
+                record.entry
+            ),
+            Some(_) => {}
+        }
+        candidates.push(&record.candidate);
+    }
+    Ok((entry, candidates))
+}
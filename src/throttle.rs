@@ -0,0 +1,139 @@
+use std::{sync::Arc, thread, time::Duration};
+
+use crate::control::Governor;
+
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(2);
+const THERMAL_HYSTERESIS: f64 = 5.0;
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ThrottleConfig {
+    /// Highest CPU temperature, in degrees Celsius, before workers are parked.
+    pub thermal_limit: Option<f64>,
+    /// Highest 1-minute load average per logical CPU the run may push the system to.
+    pub load_ceiling: Option<f64>,
+}
+
+impl ThrottleConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.thermal_limit.is_some() || self.load_ceiling.is_some()
+    }
+}
+
+/// Parses temperatures such as `85C`, `85`, or `185F` into degrees Celsius.
+pub fn parse_temperature(value: &str) -> Result<f64, String> {
+    let value = value.trim();
+    let (number, fahrenheit) = if let Some(number) = value.strip_suffix(['F', 'f']) {
+        (number, true)
+    } else {
+        (value.strip_suffix(['C', 'c']).unwrap_or(value), false)
+    };
+    let degrees: f64 = number
+        .trim_end_matches('°')
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid temperature \"{value}\" (expected e.g. 85C)"))?;
+    Ok(if fahrenheit {
+        (degrees - 32.0) * 5.0 / 9.0
+    } else {
+        degrees
+    })
+}
+
+pub fn parse_load_ceiling(value: &str) -> Result<f64, String> {
+    match value.trim().parse::<f64>() {
+        Ok(ceiling) if ceiling > 0.0 => Ok(ceiling),
+        _ => Err(format!(
+            "invalid load ceiling \"{value}\" (expected a positive fraction such as 0.8)"
+        )),
+    }
+}
+
+/// Samples temperature and load in the background and adjusts how many workers may run.
+pub fn spawn_throttle(config: ThrottleConfig, governor: Arc<Governor>, workers: usize) {
+    let mut config = config;
+    if config.thermal_limit.is_some() && read_cpu_temperature().is_none() {
+        eprintln!("No CPU temperature sensor found; ignoring --thermal-limit");
+        config.thermal_limit = None;
+    }
+    if config.load_ceiling.is_some() && read_load_average().is_none() {
+        eprintln!("System load average is unavailable; ignoring --load-ceiling");
+        config.load_ceiling = None;
+    }
+    if !config.is_enabled() {
+        return;
+    }
+
+    thread::spawn(move || {
+        let cpus = num_cpus::get() as f64;
+        let mut thermal_cap = workers;
+
+        loop {
+            thread::sleep(SAMPLE_INTERVAL);
+            let current = governor.active_limit();
+            let mut target = workers;
+            let temperature = read_cpu_temperature();
+            let load = read_load_average();
+
+            if let (Some(ceiling), Some(load)) = (config.load_ceiling, load) {
+                // Our own workers contribute to the load average, so only budget against the rest.
+                let others = (load - current as f64).max(0.0);
+                let budget = (ceiling * cpus - others).floor().max(1.0) as usize;
+                target = target.min(budget);
+            }
+
+            if let (Some(limit), Some(temperature)) = (config.thermal_limit, temperature) {
+                if temperature >= limit {
+                    thermal_cap = current.saturating_sub((current / 4).max(1)).max(1);
+                } else if temperature <= limit - THERMAL_HYSTERESIS {
+                    thermal_cap = (thermal_cap + 1).min(workers);
+                }
+                target = target.min(thermal_cap);
+            }
+
+            // Back off immediately but ramp up one worker at a time to avoid oscillating.
+            let next = if target > current {
+                current + 1
+            } else {
+                target
+            };
+            if next != current {
+                governor.set_active_limit(next);
+                eprintln!(
+                    "Throttle: {next} of {workers} workers active{}{}",
+                    load.map(|load| format!(", load {load:.2}"))
+                        .unwrap_or_default(),
+                    temperature
+                        .map(|temperature| format!(", {temperature:.1}°C"))
+                        .unwrap_or_default(),
+                );
+            }
+        }
+    });
+}
+
+fn read_cpu_temperature() -> Option<f64> {
+    let zones = std::fs::read_dir("/sys/class/thermal").ok()?;
+    zones
+        .flatten()
+        .filter(|zone| {
+            zone.file_name()
+                .to_string_lossy()
+                .starts_with("thermal_zone")
+        })
+        .filter_map(|zone| std::fs::read_to_string(zone.path().join("temp")).ok())
+        .filter_map(|raw| raw.trim().parse::<f64>().ok())
+        .map(|millidegrees| millidegrees / 1000.0)
+        .reduce(f64::max)
+}
+
+#[cfg(unix)]
+fn read_load_average() -> Option<f64> {
+    let mut samples = [0f64; 1];
+    let read = unsafe { libc::getloadavg(samples.as_mut_ptr(), 1) };
+    (read == 1).then_some(samples[0])
+}
+
+#[cfg(not(unix))]
+fn read_load_average() -> Option<f64> {
+    None
+}
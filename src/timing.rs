@@ -0,0 +1,150 @@
+use std::{
+    sync::{
+        Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use crate::secret;
+
+/// How many of the slowest verifications are kept for the report.
+const SLOWEST_KEPT: usize = 5;
+/// A verification this many times slower than the median, and slower than `OUTLIER_FLOOR`, is
+/// flagged; the floor keeps scheduler hiccups on fast ZipCrypto checks from counting.
+const OUTLIER_FACTOR: u32 = 100;
+const OUTLIER_FLOOR: Duration = Duration::from_millis(10);
+
+/// Latencies in power-of-two nanosecond buckets, cheap enough to update from every worker.
+struct Histogram {
+    buckets: [AtomicU64; 64],
+    max_nanos: AtomicU64,
+    total_nanos: AtomicU64,
+}
+
+impl Histogram {
+    fn new() -> Self {
+        Self {
+            buckets: std::array::from_fn(|_| AtomicU64::new(0)),
+            max_nanos: AtomicU64::new(0),
+            total_nanos: AtomicU64::new(0),
+        }
+    }
+
+    fn record(&self, latency: Duration, count: u64) {
+        let nanos = u64::try_from(latency.as_nanos()).unwrap_or(u64::MAX).max(1);
+        self.buckets[nanos.ilog2() as usize].fetch_add(count, Ordering::Relaxed);
+        self.max_nanos.fetch_max(nanos, Ordering::Relaxed);
+        self.total_nanos
+            .fetch_add(nanos.saturating_mul(count), Ordering::Relaxed);
+    }
+
+    fn count(&self) -> u64 {
+        self.buckets
+            .iter()
+            .map(|bucket| bucket.load(Ordering::Relaxed))
+            .sum()
+    }
+
+    /// The upper edge of the bucket holding the `fraction` quantile, capped at the maximum.
+    fn quantile(&self, fraction: f64) -> Duration {
+        let rank = (self.count() as f64 * fraction).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (bucket, count) in self.buckets.iter().enumerate() {
+            seen += count.load(Ordering::Relaxed);
+            if seen >= rank {
+                return Duration::from_nanos(
+                    1u64.checked_shl(bucket as u32 + 1).unwrap_or(u64::MAX),
+                );
+            }
+        }
+        Duration::ZERO
+    }
+
+    fn total(&self) -> Duration {
+        Duration::from_nanos(self.total_nanos.load(Ordering::Relaxed))
+    }
+
+    fn max(&self) -> Duration {
+        Duration::from_nanos(self.max_nanos.load(Ordering::Relaxed))
+    }
+
+    fn print(&self, label: &str) {
+        println!(
+            "  {label}: {} candidates, p50 <{:.2?}, p99 <{:.2?}, max {:.2?}",
+            self.count(),
+            self.quantile(0.5),
+            self.quantile(0.99),
+            self.max()
+        );
+    }
+}
+
+/// `--timings` instrumentation: latency of the fast check and of full verification.
+pub struct Timings {
+    fast_check: Histogram,
+    verify: Histogram,
+    slowest: Mutex<Vec<(Duration, String)>>,
+}
+
+impl Timings {
+    pub fn new() -> Self {
+        Self {
+            fast_check: Histogram::new(),
+            verify: Histogram::new(),
+            slowest: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Records a fast check over `count` candidates, attributing an equal share to each.
+    pub fn record_fast_check(&self, elapsed: Duration, count: usize) {
+        if count > 0 {
+            self.fast_check.record(elapsed / count as u32, count as u64);
+        }
+    }
+
+    pub fn record_verify(&self, elapsed: Duration, candidate: &str) {
+        self.verify.record(elapsed, 1);
+        let mut slowest = self.slowest.lock().expect("worker panicked");
+        if slowest.len() < SLOWEST_KEPT || slowest.last().is_some_and(|&(kept, _)| elapsed > kept) {
+            slowest.push((elapsed, candidate.to_owned()));
+            slowest.sort_by_key(|&(elapsed, _)| std::cmp::Reverse(elapsed));
+            slowest.truncate(SLOWEST_KEPT);
+        }
+    }
+
+    /// With `redact`, the slowest candidates are listed as `[redacted]`, since the password
+    /// may be among them.
+    pub fn print(&self, redact: bool) {
+        println!("Timings per candidate:");
+        self.fast_check.print("fast check");
+        self.verify.print("verify");
+        let (checked, passed) = (self.fast_check.count(), self.verify.count());
+        let (fast, verify) = (self.fast_check.total(), self.verify.total());
+        if checked > 0 {
+            println!(
+                "  {passed} of {checked} candidates ({:.2}%) passed the fast check; verifying \
+                 them took {:.0}% of the measured time ({fast:.2?} fast check, {verify:.2?} \
+                 verify)",
+                100.0 * passed as f64 / checked as f64,
+                100.0 * verify.as_secs_f64() / (fast + verify).as_secs_f64().max(1e-9)
+            );
+        }
+        let slowest = self.slowest.lock().expect("worker panicked");
+        if slowest.is_empty() {
+            return;
+        }
+        println!("  slowest verifications:");
+        for (elapsed, candidate) in slowest.iter() {
+            println!("    {elapsed:.2?} \"{}\"", secret::shown(redact, candidate));
+        }
+        let max = self.verify.max();
+        if max > OUTLIER_FLOOR && max > self.verify.quantile(0.5) * OUTLIER_FACTOR {
+            println!(
+                "  Some verifications took over {OUTLIER_FACTOR}x the median, usually because a \
+                 wrong key decompressed a large part of the entry; a smaller --target entry \
+                 verifies faster."
+            );
+        }
+    }
+}
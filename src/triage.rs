@@ -0,0 +1,513 @@
+use std::{
+    collections::BTreeSet,
+    fs::{self, File},
+    io::{BufReader, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, bail};
+use serde::Serialize;
+
+use crate::archive::{self, Encryption};
+use crate::manifest;
+use crate::paths;
+
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+const EMPTY_ZIP_MAGIC: &[u8] = b"PK\x05\x06";
+const SEVEN_ZIP_MAGIC: &[u8] = b"7z\xbc\xaf\x27\x1c";
+const RAR4_MAGIC: &[u8] = b"Rar!\x1a\x07\x00";
+const RAR5_MAGIC: &[u8] = b"Rar!\x1a\x07\x01\x00";
+const CFB_MAGIC: &[u8] = b"\xd0\xcf\x11\xe0\xa1\xb1\x1a\xe1";
+/// The 7-Zip AES-256 + SHA-256 coder.
+const SEVEN_ZIP_AES: &[u8] = b"\x06\xf1\x07\x01";
+/// Formats that are ZIP archives under another extension.
+const ZIP_BASED: &[&str] = &[
+    "apk", "docx", "epub", "jar", "odp", "ods", "odt", "pptx", "xlsx",
+];
+const OFFICE: &[&str] = &["docx", "pptx", "xlsx"];
+/// Headers larger than this are assumed to be corrupt rather than read into memory.
+const MAX_HEADER: u64 = 4 << 20;
+/// RAR headers walked before giving up on finding an encrypted file.
+const MAX_RAR_HEADERS: usize = 10_000;
+
+#[derive(clap::Args, Debug)]
+pub struct TriageArgs {
+    /// Directory to scan; every file below it is examined.
+    dir: PathBuf,
+
+    /// Print the report as JSON instead of a table.
+    #[arg(long)]
+    json: bool,
+
+    /// Also write the archives unzippoo can attack as a `--manifest` file, in queue order.
+    #[arg(long, value_name = "FILE")]
+    write_manifest: Option<PathBuf>,
+}
+
+/// What the scan learned about one archive-like file.
+#[derive(Debug, Serialize)]
+struct Finding {
+    path: PathBuf,
+    format: String,
+    size: u64,
+    /// Encryption schemes seen, most common first; empty when nothing is encrypted.
+    encryption: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    entries: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    encrypted_entries: Option<usize>,
+    /// Whether unzippoo itself can attack it.
+    crackable: bool,
+    /// Queue order: higher goes first. Unencrypted files are not queued.
+    priority: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    note: Option<String>,
+}
+
+impl Finding {
+    fn new(path: &Path, format: &str, size: u64) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            format: format.to_owned(),
+            size,
+            encryption: Vec::new(),
+            entries: None,
+            encrypted_entries: None,
+            crackable: false,
+            priority: 0,
+            note: None,
+        }
+    }
+
+    fn is_queued(&self) -> bool {
+        !self.encryption.is_empty()
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Report {
+    dir: PathBuf,
+    files: usize,
+    unreadable: usize,
+    /// Queued archives first, in queue order, then the unencrypted ones.
+    archives: Vec<Finding>,
+}
+
+pub fn run(args: &TriageArgs) -> Result<()> {
+    if !paths::extended(&args.dir).is_dir() {
+        bail!("{} is not a directory", args.dir.display());
+    }
+    let mut files = Vec::new();
+    let mut unreadable = 0;
+    walk(&args.dir, &mut files, &mut unreadable);
+
+    let mut archives = Vec::new();
+    for path in &files {
+        match examine(path) {
+            Ok(Some(finding)) => archives.push(finding),
+            Ok(None) => {}
+            Err(_) => unreadable += 1,
+        }
+    }
+    // Stable, so ties stay in path order.
+    archives.sort_by_key(|finding| (!finding.is_queued(), std::cmp::Reverse(finding.priority)));
+    let report = Report {
+        dir: args.dir.clone(),
+        files: files.len(),
+        unreadable,
+        archives,
+    };
+
+    if let Some(path) = &args.write_manifest {
+        write_manifest(path, &report.archives)?;
+    }
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print(&report, args.write_manifest.as_deref());
+    }
+    Ok(())
+}
+
+/// Every regular file below `dir`, sorted by path. Symlinks are not followed, so a link
+/// cycle cannot send the scan around forever.
+fn walk(dir: &Path, files: &mut Vec<PathBuf>, unreadable: &mut usize) {
+    let Ok(entries) = fs::read_dir(paths::extended(dir)) else {
+        *unreadable += 1;
+        return;
+    };
+    // Joined to `dir` rather than taken from the entries, so paths show as they were given.
+    let mut children: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| dir.join(entry.file_name())))
+        .collect();
+    children.sort();
+    for path in children {
+        match fs::symlink_metadata(paths::extended(&path)) {
+            Ok(metadata) if metadata.is_dir() => walk(&path, files, unreadable),
+            Ok(metadata) if metadata.is_file() => files.push(path),
+            Ok(_) => {}
+            Err(_) => *unreadable += 1,
+        }
+    }
+}
+
+/// Identifies the file by its magic bytes (and extension, for formats that share them), or
+/// returns `None` if it is not archive-like.
+fn examine(path: &Path) -> Result<Option<Finding>> {
+    let mut reader = BufReader::new(File::open(paths::extended(path))?);
+    let size = reader.seek(SeekFrom::End(0))?;
+    reader.seek(SeekFrom::Start(0))?;
+    let mut magic = Vec::with_capacity(8);
+    reader.by_ref().take(8).read_to_end(&mut magic)?;
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_default();
+
+    let finding = if magic.starts_with(ZIP_MAGIC) || magic.starts_with(EMPTY_ZIP_MAGIC) {
+        let format = if ZIP_BASED.contains(&extension.as_str()) {
+            extension.as_str()
+        } else {
+            "zip"
+        };
+        zip(&mut reader, path, format, size)
+    } else if magic.starts_with(b"MZ") || magic.starts_with(b"\x7fELF") || extension == "sfx" {
+        // A self-extractor is an executable with the archive appended.
+        if archive::read_central_directory(&mut reader).is_err() {
+            return Ok(None);
+        }
+        zip(&mut reader, path, "zip (self-extracting)", size)
+    } else if magic.starts_with(SEVEN_ZIP_MAGIC) {
+        seven_zip(&mut reader, path, size)?
+    } else if magic.starts_with(RAR5_MAGIC) {
+        rar5(&mut reader, path, size)?
+    } else if magic.starts_with(RAR4_MAGIC) {
+        rar4(&mut reader, path, size)?
+    } else if magic.starts_with(CFB_MAGIC) && OFFICE.contains(&extension.as_str()) {
+        // Password-protected Office documents are not ZIPs at all, but an EncryptedPackage
+        // stream inside an OLE compound file.
+        let mut finding = Finding::new(path, &extension, size);
+        finding.encryption.push("Office".to_owned());
+        finding.note = Some("Office document encryption is not supported".to_owned());
+        finding
+    } else {
+        return Ok(None);
+    };
+    Ok(Some(finding))
+}
+
+/// ZipCrypto entries are orders of magnitude cheaper to attack than AES ones, so they go
+/// first; archives unzippoo cannot attack go last.
+fn zip<R: Read + Seek>(reader: &mut R, path: &Path, format: &str, size: u64) -> Finding {
+    let mut finding = Finding::new(path, format, size);
+    let entries = match archive::read_central_directory(reader) {
+        Ok(directory) => directory.entries,
+        Err(error) => {
+            finding.note = Some(format!("{error:#}"));
+            return finding;
+        }
+    };
+    let files: Vec<_> = entries.iter().filter(|entry| !entry.is_dir()).collect();
+    let encrypted: Vec<_> = files
+        .iter()
+        .filter(|entry| entry.encryption != Encryption::None)
+        .collect();
+    finding.entries = Some(files.len());
+    finding.encrypted_entries = Some(encrypted.len());
+    if encrypted.is_empty() {
+        return finding;
+    }
+
+    let mut schemes: Vec<(usize, String)> = encrypted
+        .iter()
+        .map(|entry| entry.encryption.to_string())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .map(|scheme| {
+            let count = encrypted
+                .iter()
+                .filter(|entry| entry.encryption.to_string() == scheme)
+                .count();
+            (count, scheme)
+        })
+        .collect();
+    schemes.sort_by_key(|(count, _)| std::cmp::Reverse(*count));
+    finding.encryption = schemes.into_iter().map(|(_, scheme)| scheme).collect();
+
+    let target = archive::select_target(&entries, None, &[]).map(|index| &entries[index]);
+    match target.and_then(|entry| entry.ensure_verifiable().map(|()| entry)) {
+        Ok(entry) => {
+            finding.crackable = true;
+            finding.priority = match entry.encryption {
+                Encryption::ZipCrypto => 2,
+                _ => 1,
+            };
+        }
+        Err(error) => finding.note = Some(format!("{error:#}")),
+    }
+    finding
+}
+
+/// Looks for the AES coder in the header the start header points at. A header that is
+/// itself compressed hides which coders the files use, short of decompressing it.
+fn seven_zip<R: Read + Seek>(reader: &mut R, path: &Path, size: u64) -> Result<Finding> {
+    let mut finding = Finding::new(path, "7z", size);
+    finding.note = Some("7-Zip archives are not supported".to_owned());
+    let start = read_at(reader, 0, 32)?;
+    if start.len() < 32 {
+        finding.encryption.push("unknown".to_owned());
+        finding.note = Some("Truncated 7-Zip start header".to_owned());
+        return Ok(finding);
+    }
+    let offset = u64::from_le_bytes(start[12..20].try_into().expect("8 bytes"));
+    let length = u64::from_le_bytes(start[20..28].try_into().expect("8 bytes"));
+    if length == 0 {
+        return Ok(finding);
+    }
+    let header = read_at(
+        reader,
+        32u64.saturating_add(offset),
+        length.min(MAX_HEADER) as usize,
+    )?;
+    let has_aes = header
+        .windows(SEVEN_ZIP_AES.len())
+        .any(|window| window == SEVEN_ZIP_AES);
+    match (header.first(), has_aes) {
+        (Some(0x17), true) => {
+            finding.encryption.push("AES-256".to_owned());
+            finding.note = Some("7-Zip archive with encrypted headers; not supported".to_owned());
+        }
+        (_, true) => finding.encryption.push("AES-256".to_owned()),
+        (Some(0x17), false) => {
+            finding.encryption.push("unknown".to_owned());
+            finding.note =
+                Some("7-Zip header is compressed, so its encryption is unknown".to_owned());
+        }
+        _ => finding.note = None,
+    }
+    Ok(finding)
+}
+
+/// Walks RAR 5 block headers until one shows encryption or the archive ends.
+fn rar5<R: Read + Seek>(reader: &mut R, path: &Path, size: u64) -> Result<Finding> {
+    let mut finding = Finding::new(path, "rar", size);
+    let mut position = RAR5_MAGIC.len() as u64;
+    let mut files = 0;
+    let mut encrypted = 0;
+    for _ in 0..MAX_RAR_HEADERS {
+        let prefix = read_at(reader, position, 4 + 10)?;
+        let Some((header_len, size_len)) = prefix.get(4..).and_then(vint) else {
+            break;
+        };
+        if header_len == 0 || header_len > MAX_HEADER {
+            break;
+        }
+        let body_start = position + 4 + size_len as u64;
+        let header = read_at(reader, body_start, header_len as usize)?;
+        let mut at = 0;
+        let mut field = || {
+            let (value, len) = vint(header.get(at..)?)?;
+            at += len;
+            Some(value)
+        };
+        let (Some(kind), Some(flags)) = (field(), field()) else {
+            break;
+        };
+        let extra_len = if flags & 0x01 != 0 { field() } else { Some(0) };
+        let data_len = if flags & 0x02 != 0 { field() } else { Some(0) };
+        let (Some(extra_len), Some(data_len)) = (extra_len, data_len) else {
+            break;
+        };
+        match kind {
+            // Archive encryption header: every later header is encrypted too.
+            4 => {
+                finding.encryption.push("AES-256".to_owned());
+                finding.note = Some("RAR archive with encrypted headers; not supported".to_owned());
+                return Ok(finding);
+            }
+            2 => {
+                files += 1;
+                let extra_start = header.len().saturating_sub(extra_len as usize);
+                if rar5_extra_has_encryption(&header[extra_start..]) {
+                    encrypted += 1;
+                }
+            }
+            5 => break,
+            _ => {}
+        }
+        position = body_start
+            .saturating_add(header_len)
+            .saturating_add(data_len);
+    }
+    finish_rar(&mut finding, files, encrypted, "AES-256");
+    Ok(finding)
+}
+
+/// The file encryption record is type 1 of the extra area.
+fn rar5_extra_has_encryption(mut extra: &[u8]) -> bool {
+    while let Some((len, size_len)) = vint(extra) {
+        let record = &extra[size_len..];
+        if vint(record).is_some_and(|(kind, _)| kind == 1) {
+            return true;
+        }
+        let Some(rest) = record.get(len as usize..) else {
+            return false;
+        };
+        extra = rest;
+    }
+    false
+}
+
+/// Walks RAR 1.5–4 blocks: the main header flags encrypted headers, file headers encrypted
+/// data.
+fn rar4<R: Read + Seek>(reader: &mut R, path: &Path, size: u64) -> Result<Finding> {
+    const MAIN: u8 = 0x73;
+    const FILE: u8 = 0x74;
+    const END: u8 = 0x7b;
+    let mut finding = Finding::new(path, "rar", size);
+    let mut position = RAR4_MAGIC.len() as u64;
+    let mut files = 0;
+    let mut encrypted = 0;
+    for _ in 0..MAX_RAR_HEADERS {
+        let header = read_at(reader, position, 11)?;
+        if header.len() < 7 {
+            break;
+        }
+        let kind = header[2];
+        let flags = u16::from_le_bytes([header[3], header[4]]);
+        let header_len = u64::from(u16::from_le_bytes([header[5], header[6]]));
+        if header_len < 7 {
+            break;
+        }
+        let data_len = if flags & 0x8000 != 0 && header.len() >= 11 {
+            u64::from(u32::from_le_bytes(
+                header[7..11].try_into().expect("4 bytes"),
+            ))
+        } else {
+            0
+        };
+        match kind {
+            MAIN if flags & 0x0080 != 0 => {
+                finding.encryption.push("AES-128".to_owned());
+                finding.note = Some("RAR archive with encrypted headers; not supported".to_owned());
+                return Ok(finding);
+            }
+            FILE => {
+                files += 1;
+                if flags & 0x0004 != 0 {
+                    encrypted += 1;
+                }
+            }
+            END => break,
+            _ => {}
+        }
+        position = position.saturating_add(header_len).saturating_add(data_len);
+    }
+    finish_rar(&mut finding, files, encrypted, "AES-128");
+    Ok(finding)
+}
+
+fn finish_rar(finding: &mut Finding, files: usize, encrypted: usize, scheme: &str) {
+    finding.entries = Some(files);
+    finding.encrypted_entries = Some(encrypted);
+    if encrypted > 0 {
+        finding.encryption.push(scheme.to_owned());
+        finding.note = Some("RAR archives are not supported".to_owned());
+    }
+}
+
+/// A RAR 5 variable-length integer and how many bytes it took.
+fn vint(bytes: &[u8]) -> Option<(u64, usize)> {
+    let mut value = 0u64;
+    for (index, byte) in bytes.iter().take(10).enumerate() {
+        value |= u64::from(byte & 0x7f) << (7 * index);
+        if byte & 0x80 == 0 {
+            return Some((value, index + 1));
+        }
+    }
+    None
+}
+
+/// Up to `len` bytes at `offset`; fewer at the end of the file.
+fn read_at<R: Read + Seek>(reader: &mut R, offset: u64, len: usize) -> Result<Vec<u8>> {
+    reader.seek(SeekFrom::Start(offset))?;
+    let mut bytes = Vec::with_capacity(len);
+    reader.take(len as u64).read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// The queue as manifest entries with absolute paths, so it can be used from anywhere.
+fn write_manifest(path: &Path, archives: &[Finding]) -> Result<()> {
+    let entries = archives
+        .iter()
+        .filter(|finding| finding.crackable)
+        .map(|finding| {
+            let archive = fs::canonicalize(paths::extended(&finding.path))
+                .with_context(|| format!("Failed to resolve {}", finding.path.display()))?;
+            Ok(manifest::Entry {
+                priority: finding.priority,
+                ..manifest::Entry::new(&archive)
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let json = serde_json::to_string_pretty(&entries)?;
+    fs::write(path, json + "\n")
+        .with_context(|| format!("Failed to write manifest: {}", path.display()))
+}
+
+fn print(report: &Report, manifest: Option<&Path>) {
+    let (queued, plain): (Vec<&Finding>, Vec<&Finding>) = report
+        .archives
+        .iter()
+        .partition(|finding| finding.is_queued());
+    println!(
+        "Scanned {} files under {}: {} archives, {} encrypted",
+        report.files,
+        report.dir.display(),
+        report.archives.len(),
+        queued.len()
+    );
+    if report.unreadable > 0 {
+        println!(
+            "  {} files or directories could not be read",
+            report.unreadable
+        );
+    }
+    if !queued.is_empty() {
+        println!("\nQueue:");
+        for (number, finding) in queued.iter().enumerate() {
+            println!("  {:>3}. {}", number + 1, describe(finding));
+        }
+    }
+    if !plain.is_empty() {
+        println!("\nNot encrypted:");
+        for finding in plain {
+            println!("       {}", describe(finding));
+        }
+    }
+    if let Some(path) = manifest {
+        let crackable = queued.iter().filter(|finding| finding.crackable).count();
+        println!(
+            "\nWrote {crackable} archives to {}; attack them with --manifest",
+            path.display()
+        );
+    }
+}
+
+fn describe(finding: &Finding) -> String {
+    let mut text = format!("{} ({}", finding.path.display(), finding.format);
+    if !finding.encryption.is_empty() {
+        text.push_str(&format!(", {}", finding.encryption.join(" and ")));
+    }
+    if let (Some(entries), Some(encrypted)) = (finding.entries, finding.encrypted_entries)
+        && encrypted > 0
+    {
+        text.push_str(&format!(", {encrypted} of {entries} entries encrypted"));
+    }
+    text.push(')');
+    if let Some(note) = &finding.note {
+        text.push_str(&format!(": {note}"));
+    }
+    text
+}
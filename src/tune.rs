@@ -0,0 +1,183 @@
+use std::{
+    fmt::Write,
+    ops::Range,
+    sync::atomic::{AtomicU64, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+
+use crate::generate::Generator;
+use crate::search::Stage;
+use crate::secret::SecretBuf;
+use crate::verify::Verifier;
+
+const CHUNK_SIZES: [usize; 4] = [1, 16, 256, 4096];
+const PROBE_TIME: Duration = Duration::from_millis(250);
+const MIN_SAMPLE: u64 = 64;
+const STAGE_PROBE_TIME: Duration = Duration::from_millis(50);
+
+#[derive(Clone, Copy, Debug)]
+pub struct Tuning {
+    pub threads: usize,
+    pub chunk_size: usize,
+    pub rate: f64,
+}
+
+/// Sweeps thread counts up to `max_threads` and a handful of chunk sizes over the first
+/// candidates, returning the combination with the best measured throughput.
+pub fn auto_tune(verifier: &Verifier, generator: &Generator, max_threads: usize) -> Result<Tuning> {
+    // Size the sample from a single-threaded probe so ZipCrypto and AES targets both get
+    // enough work per configuration without the sweep dragging on for AES.
+    let probe_started = Instant::now();
+    let mut probed = 0;
+    let mut bufs: Vec<SecretBuf> = (0..verifier.batch_size())
+        .map(|_| SecretBuf::new())
+        .collect();
+    while probed < generator.len() && probe_started.elapsed() < PROBE_TIME {
+        let end = generator.len().min(probed + verifier.batch_size() as u64);
+        test_range(verifier, generator, probed..end, &mut bufs);
+        probed = end;
+    }
+    let single_rate = probed as f64 / probe_started.elapsed().as_secs_f64();
+    let sample_len = ((single_rate * PROBE_TIME.as_secs_f64()) as u64 * max_threads as u64)
+        .max(MIN_SAMPLE)
+        .min(generator.len());
+
+    let mut best: Option<Tuning> = None;
+    for threads in thread_counts(max_threads) {
+        for chunk_size in CHUNK_SIZES {
+            if chunk_size as u64 > sample_len {
+                continue;
+            }
+            let started = Instant::now();
+            measure(verifier, generator, sample_len, threads, chunk_size);
+            let rate = sample_len as f64 / started.elapsed().as_secs_f64();
+            if best.is_none_or(|best| rate > best.rate) {
+                best = Some(Tuning {
+                    threads,
+                    chunk_size,
+                    rate,
+                });
+            }
+        }
+    }
+
+    Ok(best.unwrap_or(Tuning {
+        threads: max_threads,
+        chunk_size: 1,
+        rate: single_rate,
+    }))
+}
+
+/// Single-threaded candidates per second on made-up candidates, for projections that must not
+/// test the real ones.
+pub fn probe_rate(verifier: &Verifier) -> f64 {
+    let started = Instant::now();
+    let mut tested = 0u64;
+    let mut bufs: Vec<SecretBuf> = (0..verifier.batch_size())
+        .map(|_| SecretBuf::new())
+        .collect();
+    while started.elapsed() < PROBE_TIME {
+        for (lane, buf) in bufs.iter_mut().enumerate() {
+            buf.clear();
+            let _ = write!(buf, "unzippoo-probe-{tested}-{lane}");
+        }
+        let candidates: Vec<&str> = bufs.iter().map(|buf| buf.as_str()).collect();
+        for (candidate, passed) in candidates.iter().zip(verifier.check_batch(&candidates)) {
+            if passed {
+                let _ = verifier.confirm(candidate);
+            }
+        }
+        tested += candidates.len() as u64;
+    }
+    tested as f64 / started.elapsed().as_secs_f64()
+}
+
+/// Single-threaded seconds per candidate for each stage, from candidates spread evenly across
+/// it, so stages whose candidates pass the fast check more often (and need the slower full
+/// verification) show up as costlier.
+pub fn stage_costs(verifier: &Verifier, stages: &[Stage]) -> Vec<f64> {
+    let mut bufs: Vec<SecretBuf> = (0..verifier.batch_size())
+        .map(|_| SecretBuf::new())
+        .collect();
+    stages
+        .iter()
+        .map(|stage| {
+            let generator = &stage.generator;
+            let batch = verifier.batch_size() as u64;
+            // Far more batches than fit in the probe time, so the sample covers the whole stage.
+            let stride = (generator.len() / 4096).max(batch);
+            let started = Instant::now();
+            let mut tested = 0;
+            let mut first = 0;
+            while first < generator.len() && started.elapsed() < STAGE_PROBE_TIME {
+                let last = generator.len().min(first + batch);
+                test_range(verifier, generator, first..last, &mut bufs);
+                tested += last - first;
+                first += stride;
+            }
+            started.elapsed().as_secs_f64() / tested.max(1) as f64
+        })
+        .collect()
+}
+
+/// Tests the first `sample_len` candidates with the same chunk-claiming dispatch as a real run.
+fn measure(
+    verifier: &Verifier,
+    generator: &Generator,
+    sample_len: u64,
+    threads: usize,
+    chunk_size: usize,
+) {
+    let chunk_size = chunk_size.next_multiple_of(verifier.batch_size());
+    let cursor = AtomicU64::new(0);
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| {
+                let mut bufs: Vec<SecretBuf> = (0..verifier.batch_size())
+                    .map(|_| SecretBuf::new())
+                    .collect();
+                loop {
+                    let claim = cursor.fetch_add(chunk_size as u64, Ordering::Relaxed);
+                    if claim >= sample_len {
+                        return;
+                    }
+                    let end = sample_len.min(claim + chunk_size as u64);
+                    for first in (claim..end).step_by(verifier.batch_size()) {
+                        let last = end.min(first + verifier.batch_size() as u64);
+                        test_range(verifier, generator, first..last, &mut bufs);
+                    }
+                }
+            });
+        }
+    });
+}
+
+/// Tests one batch the way a worker does, fast check first and then confirmation.
+fn test_range(
+    verifier: &Verifier,
+    generator: &Generator,
+    indices: Range<u64>,
+    bufs: &mut [SecretBuf],
+) {
+    let candidates: Vec<&str> = bufs
+        .iter_mut()
+        .zip(indices)
+        .map(|(buf, index)| generator.candidate(index, buf))
+        .collect();
+    for (candidate, passed) in candidates.iter().zip(verifier.check_batch(&candidates)) {
+        if passed {
+            let _ = verifier.confirm(candidate);
+        }
+    }
+}
+
+fn thread_counts(max_threads: usize) -> Vec<usize> {
+    let mut counts: Vec<usize> = std::iter::successors(Some(1usize), |&n| n.checked_mul(2))
+        .take_while(|&n| n < max_threads)
+        .collect();
+    counts.push(max_threads);
+    counts
+}
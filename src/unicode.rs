@@ -0,0 +1,338 @@
+use std::collections::HashSet;
+
+use crate::generate::Candidates;
+use crate::wordlist::Words;
+
+const HANGUL_BASE: u32 = 0xac00;
+const HANGUL_COUNT: u32 = 11172;
+const LEADING_BASE: u32 = 0x1100;
+const VOWEL_BASE: u32 = 0x1161;
+const TRAILING_BASE: u32 = 0x11a7;
+const VOWELS: u32 = 21;
+const TRAILINGS: u32 = 28;
+
+/// Every NFC and NFD form of the wordlist's non-ASCII candidates in each case, leaving out the
+/// candidates themselves.
+pub fn variants(words: &Words) -> Candidates {
+    let listed: HashSet<&str> = (0..words.len())
+        .map(|index| words.get(index))
+        .filter(|word| !word.is_ascii())
+        .collect();
+    let mut seen = HashSet::new();
+    let mut variants = Candidates::default();
+    for index in 0..words.len() {
+        let word = words.get(index);
+        if word.is_ascii() {
+            continue;
+        }
+        for form in [nfc(word), nfd(word)] {
+            for variant in cases(&form) {
+                if !listed.contains(variant.as_str()) && seen.insert(variant.clone()) {
+                    variants.push(&variant);
+                }
+            }
+        }
+    }
+    variants
+}
+
+/// As typed, lower, upper, and capitalized, then the same with the Turkish dotted and dotless
+/// i, where `I` lowers to `ı` and `i` uppers to `İ`.
+fn cases(word: &str) -> [String; 7] {
+    let capitalize = |lower: fn(&str) -> String, upper: fn(&str) -> String| {
+        let split = word.chars().next().map_or(0, char::len_utf8);
+        upper(&word[..split]) + &lower(&word[split..])
+    };
+    [
+        word.to_owned(),
+        word.to_lowercase(),
+        word.to_uppercase(),
+        capitalize(str::to_lowercase, str::to_uppercase),
+        turkish_lower(word),
+        turkish_upper(word),
+        capitalize(turkish_lower, turkish_upper),
+    ]
+}
+
+fn turkish_lower(word: &str) -> String {
+    word.replace('I', "ı").replace('İ', "i").to_lowercase()
+}
+
+fn turkish_upper(word: &str) -> String {
+    word.replace('i', "İ").replace('ı', "I").to_uppercase()
+}
+
+/// Canonical decomposition, for the letters in `PAIRS` and Hangul syllables.
+fn nfd(word: &str) -> String {
+    let mut out = String::with_capacity(word.len() * 2);
+    for c in word.chars() {
+        decompose(c, &mut out);
+    }
+    out
+}
+
+fn decompose(c: char, out: &mut String) {
+    let code = u32::from(c);
+    if (HANGUL_BASE..HANGUL_BASE + HANGUL_COUNT).contains(&code) {
+        let index = code - HANGUL_BASE;
+        let jamo = [
+            Some(LEADING_BASE + index / (VOWELS * TRAILINGS)),
+            Some(VOWEL_BASE + index % (VOWELS * TRAILINGS) / TRAILINGS),
+            Some(index % TRAILINGS)
+                .filter(|&trailing| trailing > 0)
+                .map(|t| TRAILING_BASE + t),
+        ];
+        out.extend(jamo.into_iter().flatten().filter_map(char::from_u32));
+        return;
+    }
+    match PAIRS.binary_search_by_key(&c, |&(composed, _, _)| composed) {
+        Ok(at) => {
+            let (_, first, mark) = PAIRS[at];
+            decompose(first, out);
+            out.push(mark);
+        }
+        Err(_) => out.push(c),
+    }
+}
+
+/// Canonical composition: decomposes, then joins each letter with the marks that follow it
+/// until one doesn't combine.
+fn nfc(word: &str) -> String {
+    let mut out: Vec<char> = Vec::with_capacity(word.len());
+    let mut blocked = true;
+    for c in nfd(word).chars() {
+        if !blocked && let Some(composed) = out.last().and_then(|&last| compose(last, c)) {
+            *out.last_mut().expect("checked above") = composed;
+            continue;
+        }
+        // A mark that didn't combine blocks the ones after it; any other character can take marks.
+        blocked = is_mark(c);
+        out.push(c);
+    }
+    out.into_iter().collect()
+}
+
+fn compose(first: char, second: char) -> Option<char> {
+    let (first_code, second_code) = (u32::from(first), u32::from(second));
+    let leading = first_code.wrapping_sub(LEADING_BASE);
+    let vowel = second_code.wrapping_sub(VOWEL_BASE);
+    if leading < 19 && vowel < VOWELS {
+        return char::from_u32(HANGUL_BASE + (leading * VOWELS + vowel) * TRAILINGS);
+    }
+    let syllable = first_code.wrapping_sub(HANGUL_BASE);
+    let trailing = second_code.wrapping_sub(TRAILING_BASE);
+    if syllable < HANGUL_COUNT && syllable % TRAILINGS == 0 && (1..TRAILINGS).contains(&trailing) {
+        return char::from_u32(first_code + trailing);
+    }
+    PAIRS
+        .iter()
+        .find(|&&(_, base, mark)| base == first && mark == second)
+        .map(|&(composed, _, _)| composed)
+}
+
+fn is_mark(c: char) -> bool {
+    PAIRS.iter().any(|&(_, _, mark)| mark == c)
+}
+
+/// `(composed, base, combining mark)` for the Latin, Greek, and Cyrillic letters with canonical
+/// decompositions, sorted by the composed character; generated from the Unicode Character
+/// Database, leaving out composition exclusions.
+#[rustfmt::skip]
+const PAIRS: &[(char, char, char)] = &[
+    ('À', 'A', '\u{300}'), ('Á', 'A', '\u{301}'), ('Â', 'A', '\u{302}'), ('Ã', 'A', '\u{303}'),
+    ('Ä', 'A', '\u{308}'), ('Å', 'A', '\u{30a}'), ('Ç', 'C', '\u{327}'), ('È', 'E', '\u{300}'),
+    ('É', 'E', '\u{301}'), ('Ê', 'E', '\u{302}'), ('Ë', 'E', '\u{308}'), ('Ì', 'I', '\u{300}'),
+    ('Í', 'I', '\u{301}'), ('Î', 'I', '\u{302}'), ('Ï', 'I', '\u{308}'), ('Ñ', 'N', '\u{303}'),
+    ('Ò', 'O', '\u{300}'), ('Ó', 'O', '\u{301}'), ('Ô', 'O', '\u{302}'), ('Õ', 'O', '\u{303}'),
+    ('Ö', 'O', '\u{308}'), ('Ù', 'U', '\u{300}'), ('Ú', 'U', '\u{301}'), ('Û', 'U', '\u{302}'),
+    ('Ü', 'U', '\u{308}'), ('Ý', 'Y', '\u{301}'), ('à', 'a', '\u{300}'), ('á', 'a', '\u{301}'),
+    ('â', 'a', '\u{302}'), ('ã', 'a', '\u{303}'), ('ä', 'a', '\u{308}'), ('å', 'a', '\u{30a}'),
+    ('ç', 'c', '\u{327}'), ('è', 'e', '\u{300}'), ('é', 'e', '\u{301}'), ('ê', 'e', '\u{302}'),
+    ('ë', 'e', '\u{308}'), ('ì', 'i', '\u{300}'), ('í', 'i', '\u{301}'), ('î', 'i', '\u{302}'),
+    ('ï', 'i', '\u{308}'), ('ñ', 'n', '\u{303}'), ('ò', 'o', '\u{300}'), ('ó', 'o', '\u{301}'),
+    ('ô', 'o', '\u{302}'), ('õ', 'o', '\u{303}'), ('ö', 'o', '\u{308}'), ('ù', 'u', '\u{300}'),
+    ('ú', 'u', '\u{301}'), ('û', 'u', '\u{302}'), ('ü', 'u', '\u{308}'), ('ý', 'y', '\u{301}'),
+    ('ÿ', 'y', '\u{308}'), ('Ā', 'A', '\u{304}'), ('ā', 'a', '\u{304}'), ('Ă', 'A', '\u{306}'),
+    ('ă', 'a', '\u{306}'), ('Ą', 'A', '\u{328}'), ('ą', 'a', '\u{328}'), ('Ć', 'C', '\u{301}'),
+    ('ć', 'c', '\u{301}'), ('Ĉ', 'C', '\u{302}'), ('ĉ', 'c', '\u{302}'), ('Ċ', 'C', '\u{307}'),
+    ('ċ', 'c', '\u{307}'), ('Č', 'C', '\u{30c}'), ('č', 'c', '\u{30c}'), ('Ď', 'D', '\u{30c}'),
+    ('ď', 'd', '\u{30c}'), ('Ē', 'E', '\u{304}'), ('ē', 'e', '\u{304}'), ('Ĕ', 'E', '\u{306}'),
+    ('ĕ', 'e', '\u{306}'), ('Ė', 'E', '\u{307}'), ('ė', 'e', '\u{307}'), ('Ę', 'E', '\u{328}'),
+    ('ę', 'e', '\u{328}'), ('Ě', 'E', '\u{30c}'), ('ě', 'e', '\u{30c}'), ('Ĝ', 'G', '\u{302}'),
+    ('ĝ', 'g', '\u{302}'), ('Ğ', 'G', '\u{306}'), ('ğ', 'g', '\u{306}'), ('Ġ', 'G', '\u{307}'),
+    ('ġ', 'g', '\u{307}'), ('Ģ', 'G', '\u{327}'), ('ģ', 'g', '\u{327}'), ('Ĥ', 'H', '\u{302}'),
+    ('ĥ', 'h', '\u{302}'), ('Ĩ', 'I', '\u{303}'), ('ĩ', 'i', '\u{303}'), ('Ī', 'I', '\u{304}'),
+    ('ī', 'i', '\u{304}'), ('Ĭ', 'I', '\u{306}'), ('ĭ', 'i', '\u{306}'), ('Į', 'I', '\u{328}'),
+    ('į', 'i', '\u{328}'), ('İ', 'I', '\u{307}'), ('Ĵ', 'J', '\u{302}'), ('ĵ', 'j', '\u{302}'),
+    ('Ķ', 'K', '\u{327}'), ('ķ', 'k', '\u{327}'), ('Ĺ', 'L', '\u{301}'), ('ĺ', 'l', '\u{301}'),
+    ('Ļ', 'L', '\u{327}'), ('ļ', 'l', '\u{327}'), ('Ľ', 'L', '\u{30c}'), ('ľ', 'l', '\u{30c}'),
+    ('Ń', 'N', '\u{301}'), ('ń', 'n', '\u{301}'), ('Ņ', 'N', '\u{327}'), ('ņ', 'n', '\u{327}'),
+    ('Ň', 'N', '\u{30c}'), ('ň', 'n', '\u{30c}'), ('Ō', 'O', '\u{304}'), ('ō', 'o', '\u{304}'),
+    ('Ŏ', 'O', '\u{306}'), ('ŏ', 'o', '\u{306}'), ('Ő', 'O', '\u{30b}'), ('ő', 'o', '\u{30b}'),
+    ('Ŕ', 'R', '\u{301}'), ('ŕ', 'r', '\u{301}'), ('Ŗ', 'R', '\u{327}'), ('ŗ', 'r', '\u{327}'),
+    ('Ř', 'R', '\u{30c}'), ('ř', 'r', '\u{30c}'), ('Ś', 'S', '\u{301}'), ('ś', 's', '\u{301}'),
+    ('Ŝ', 'S', '\u{302}'), ('ŝ', 's', '\u{302}'), ('Ş', 'S', '\u{327}'), ('ş', 's', '\u{327}'),
+    ('Š', 'S', '\u{30c}'), ('š', 's', '\u{30c}'), ('Ţ', 'T', '\u{327}'), ('ţ', 't', '\u{327}'),
+    ('Ť', 'T', '\u{30c}'), ('ť', 't', '\u{30c}'), ('Ũ', 'U', '\u{303}'), ('ũ', 'u', '\u{303}'),
+    ('Ū', 'U', '\u{304}'), ('ū', 'u', '\u{304}'), ('Ŭ', 'U', '\u{306}'), ('ŭ', 'u', '\u{306}'),
+    ('Ů', 'U', '\u{30a}'), ('ů', 'u', '\u{30a}'), ('Ű', 'U', '\u{30b}'), ('ű', 'u', '\u{30b}'),
+    ('Ų', 'U', '\u{328}'), ('ų', 'u', '\u{328}'), ('Ŵ', 'W', '\u{302}'), ('ŵ', 'w', '\u{302}'),
+    ('Ŷ', 'Y', '\u{302}'), ('ŷ', 'y', '\u{302}'), ('Ÿ', 'Y', '\u{308}'), ('Ź', 'Z', '\u{301}'),
+    ('ź', 'z', '\u{301}'), ('Ż', 'Z', '\u{307}'), ('ż', 'z', '\u{307}'), ('Ž', 'Z', '\u{30c}'),
+    ('ž', 'z', '\u{30c}'), ('Ơ', 'O', '\u{31b}'), ('ơ', 'o', '\u{31b}'), ('Ư', 'U', '\u{31b}'),
+    ('ư', 'u', '\u{31b}'), ('Ǎ', 'A', '\u{30c}'), ('ǎ', 'a', '\u{30c}'), ('Ǐ', 'I', '\u{30c}'),
+    ('ǐ', 'i', '\u{30c}'), ('Ǒ', 'O', '\u{30c}'), ('ǒ', 'o', '\u{30c}'), ('Ǔ', 'U', '\u{30c}'),
+    ('ǔ', 'u', '\u{30c}'), ('Ǖ', 'Ü', '\u{304}'), ('ǖ', 'ü', '\u{304}'), ('Ǘ', 'Ü', '\u{301}'),
+    ('ǘ', 'ü', '\u{301}'), ('Ǚ', 'Ü', '\u{30c}'), ('ǚ', 'ü', '\u{30c}'), ('Ǜ', 'Ü', '\u{300}'),
+    ('ǜ', 'ü', '\u{300}'), ('Ǟ', 'Ä', '\u{304}'), ('ǟ', 'ä', '\u{304}'), ('Ǡ', 'Ȧ', '\u{304}'),
+    ('ǡ', 'ȧ', '\u{304}'), ('Ǣ', 'Æ', '\u{304}'), ('ǣ', 'æ', '\u{304}'), ('Ǧ', 'G', '\u{30c}'),
+    ('ǧ', 'g', '\u{30c}'), ('Ǩ', 'K', '\u{30c}'), ('ǩ', 'k', '\u{30c}'), ('Ǫ', 'O', '\u{328}'),
+    ('ǫ', 'o', '\u{328}'), ('Ǭ', 'Ǫ', '\u{304}'), ('ǭ', 'ǫ', '\u{304}'), ('Ǯ', 'Ʒ', '\u{30c}'),
+    ('ǯ', 'ʒ', '\u{30c}'), ('ǰ', 'j', '\u{30c}'), ('Ǵ', 'G', '\u{301}'), ('ǵ', 'g', '\u{301}'),
+    ('Ǹ', 'N', '\u{300}'), ('ǹ', 'n', '\u{300}'), ('Ǻ', 'Å', '\u{301}'), ('ǻ', 'å', '\u{301}'),
+    ('Ǽ', 'Æ', '\u{301}'), ('ǽ', 'æ', '\u{301}'), ('Ǿ', 'Ø', '\u{301}'), ('ǿ', 'ø', '\u{301}'),
+    ('Ȁ', 'A', '\u{30f}'), ('ȁ', 'a', '\u{30f}'), ('Ȃ', 'A', '\u{311}'), ('ȃ', 'a', '\u{311}'),
+    ('Ȅ', 'E', '\u{30f}'), ('ȅ', 'e', '\u{30f}'), ('Ȇ', 'E', '\u{311}'), ('ȇ', 'e', '\u{311}'),
+    ('Ȉ', 'I', '\u{30f}'), ('ȉ', 'i', '\u{30f}'), ('Ȋ', 'I', '\u{311}'), ('ȋ', 'i', '\u{311}'),
+    ('Ȍ', 'O', '\u{30f}'), ('ȍ', 'o', '\u{30f}'), ('Ȏ', 'O', '\u{311}'), ('ȏ', 'o', '\u{311}'),
+    ('Ȑ', 'R', '\u{30f}'), ('ȑ', 'r', '\u{30f}'), ('Ȓ', 'R', '\u{311}'), ('ȓ', 'r', '\u{311}'),
+    ('Ȕ', 'U', '\u{30f}'), ('ȕ', 'u', '\u{30f}'), ('Ȗ', 'U', '\u{311}'), ('ȗ', 'u', '\u{311}'),
+    ('Ș', 'S', '\u{326}'), ('ș', 's', '\u{326}'), ('Ț', 'T', '\u{326}'), ('ț', 't', '\u{326}'),
+    ('Ȟ', 'H', '\u{30c}'), ('ȟ', 'h', '\u{30c}'), ('Ȧ', 'A', '\u{307}'), ('ȧ', 'a', '\u{307}'),
+    ('Ȩ', 'E', '\u{327}'), ('ȩ', 'e', '\u{327}'), ('Ȫ', 'Ö', '\u{304}'), ('ȫ', 'ö', '\u{304}'),
+    ('Ȭ', 'Õ', '\u{304}'), ('ȭ', 'õ', '\u{304}'), ('Ȯ', 'O', '\u{307}'), ('ȯ', 'o', '\u{307}'),
+    ('Ȱ', 'Ȯ', '\u{304}'), ('ȱ', 'ȯ', '\u{304}'), ('Ȳ', 'Y', '\u{304}'), ('ȳ', 'y', '\u{304}'),
+    ('΅', '¨', '\u{301}'), ('Ά', 'Α', '\u{301}'), ('Έ', 'Ε', '\u{301}'), ('Ή', 'Η', '\u{301}'),
+    ('Ί', 'Ι', '\u{301}'), ('Ό', 'Ο', '\u{301}'), ('Ύ', 'Υ', '\u{301}'), ('Ώ', 'Ω', '\u{301}'),
+    ('ΐ', 'ϊ', '\u{301}'), ('Ϊ', 'Ι', '\u{308}'), ('Ϋ', 'Υ', '\u{308}'), ('ά', 'α', '\u{301}'),
+    ('έ', 'ε', '\u{301}'), ('ή', 'η', '\u{301}'), ('ί', 'ι', '\u{301}'), ('ΰ', 'ϋ', '\u{301}'),
+    ('ϊ', 'ι', '\u{308}'), ('ϋ', 'υ', '\u{308}'), ('ό', 'ο', '\u{301}'), ('ύ', 'υ', '\u{301}'),
+    ('ώ', 'ω', '\u{301}'), ('ϓ', 'ϒ', '\u{301}'), ('ϔ', 'ϒ', '\u{308}'), ('Ѐ', 'Е', '\u{300}'),
+    ('Ё', 'Е', '\u{308}'), ('Ѓ', 'Г', '\u{301}'), ('Ї', 'І', '\u{308}'), ('Ќ', 'К', '\u{301}'),
+    ('Ѝ', 'И', '\u{300}'), ('Ў', 'У', '\u{306}'), ('Й', 'И', '\u{306}'), ('й', 'и', '\u{306}'),
+    ('ѐ', 'е', '\u{300}'), ('ё', 'е', '\u{308}'), ('ѓ', 'г', '\u{301}'), ('ї', 'і', '\u{308}'),
+    ('ќ', 'к', '\u{301}'), ('ѝ', 'и', '\u{300}'), ('ў', 'у', '\u{306}'), ('Ѷ', 'Ѵ', '\u{30f}'),
+    ('ѷ', 'ѵ', '\u{30f}'), ('Ӂ', 'Ж', '\u{306}'), ('ӂ', 'ж', '\u{306}'), ('Ӑ', 'А', '\u{306}'),
+    ('ӑ', 'а', '\u{306}'), ('Ӓ', 'А', '\u{308}'), ('ӓ', 'а', '\u{308}'), ('Ӗ', 'Е', '\u{306}'),
+    ('ӗ', 'е', '\u{306}'), ('Ӛ', 'Ә', '\u{308}'), ('ӛ', 'ә', '\u{308}'), ('Ӝ', 'Ж', '\u{308}'),
+    ('ӝ', 'ж', '\u{308}'), ('Ӟ', 'З', '\u{308}'), ('ӟ', 'з', '\u{308}'), ('Ӣ', 'И', '\u{304}'),
+    ('ӣ', 'и', '\u{304}'), ('Ӥ', 'И', '\u{308}'), ('ӥ', 'и', '\u{308}'), ('Ӧ', 'О', '\u{308}'),
+    ('ӧ', 'о', '\u{308}'), ('Ӫ', 'Ө', '\u{308}'), ('ӫ', 'ө', '\u{308}'), ('Ӭ', 'Э', '\u{308}'),
+    ('ӭ', 'э', '\u{308}'), ('Ӯ', 'У', '\u{304}'), ('ӯ', 'у', '\u{304}'), ('Ӱ', 'У', '\u{308}'),
+    ('ӱ', 'у', '\u{308}'), ('Ӳ', 'У', '\u{30b}'), ('ӳ', 'у', '\u{30b}'), ('Ӵ', 'Ч', '\u{308}'),
+    ('ӵ', 'ч', '\u{308}'), ('Ӹ', 'Ы', '\u{308}'), ('ӹ', 'ы', '\u{308}'), ('Ḁ', 'A', '\u{325}'),
+    ('ḁ', 'a', '\u{325}'), ('Ḃ', 'B', '\u{307}'), ('ḃ', 'b', '\u{307}'), ('Ḅ', 'B', '\u{323}'),
+    ('ḅ', 'b', '\u{323}'), ('Ḇ', 'B', '\u{331}'), ('ḇ', 'b', '\u{331}'), ('Ḉ', 'Ç', '\u{301}'),
+    ('ḉ', 'ç', '\u{301}'), ('Ḋ', 'D', '\u{307}'), ('ḋ', 'd', '\u{307}'), ('Ḍ', 'D', '\u{323}'),
+    ('ḍ', 'd', '\u{323}'), ('Ḏ', 'D', '\u{331}'), ('ḏ', 'd', '\u{331}'), ('Ḑ', 'D', '\u{327}'),
+    ('ḑ', 'd', '\u{327}'), ('Ḓ', 'D', '\u{32d}'), ('ḓ', 'd', '\u{32d}'), ('Ḕ', 'Ē', '\u{300}'),
+    ('ḕ', 'ē', '\u{300}'), ('Ḗ', 'Ē', '\u{301}'), ('ḗ', 'ē', '\u{301}'), ('Ḙ', 'E', '\u{32d}'),
+    ('ḙ', 'e', '\u{32d}'), ('Ḛ', 'E', '\u{330}'), ('ḛ', 'e', '\u{330}'), ('Ḝ', 'Ȩ', '\u{306}'),
+    ('ḝ', 'ȩ', '\u{306}'), ('Ḟ', 'F', '\u{307}'), ('ḟ', 'f', '\u{307}'), ('Ḡ', 'G', '\u{304}'),
+    ('ḡ', 'g', '\u{304}'), ('Ḣ', 'H', '\u{307}'), ('ḣ', 'h', '\u{307}'), ('Ḥ', 'H', '\u{323}'),
+    ('ḥ', 'h', '\u{323}'), ('Ḧ', 'H', '\u{308}'), ('ḧ', 'h', '\u{308}'), ('Ḩ', 'H', '\u{327}'),
+    ('ḩ', 'h', '\u{327}'), ('Ḫ', 'H', '\u{32e}'), ('ḫ', 'h', '\u{32e}'), ('Ḭ', 'I', '\u{330}'),
+    ('ḭ', 'i', '\u{330}'), ('Ḯ', 'Ï', '\u{301}'), ('ḯ', 'ï', '\u{301}'), ('Ḱ', 'K', '\u{301}'),
+    ('ḱ', 'k', '\u{301}'), ('Ḳ', 'K', '\u{323}'), ('ḳ', 'k', '\u{323}'), ('Ḵ', 'K', '\u{331}'),
+    ('ḵ', 'k', '\u{331}'), ('Ḷ', 'L', '\u{323}'), ('ḷ', 'l', '\u{323}'), ('Ḹ', 'Ḷ', '\u{304}'),
+    ('ḹ', 'ḷ', '\u{304}'), ('Ḻ', 'L', '\u{331}'), ('ḻ', 'l', '\u{331}'), ('Ḽ', 'L', '\u{32d}'),
+    ('ḽ', 'l', '\u{32d}'), ('Ḿ', 'M', '\u{301}'), ('ḿ', 'm', '\u{301}'), ('Ṁ', 'M', '\u{307}'),
+    ('ṁ', 'm', '\u{307}'), ('Ṃ', 'M', '\u{323}'), ('ṃ', 'm', '\u{323}'), ('Ṅ', 'N', '\u{307}'),
+    ('ṅ', 'n', '\u{307}'), ('Ṇ', 'N', '\u{323}'), ('ṇ', 'n', '\u{323}'), ('Ṉ', 'N', '\u{331}'),
+    ('ṉ', 'n', '\u{331}'), ('Ṋ', 'N', '\u{32d}'), ('ṋ', 'n', '\u{32d}'), ('Ṍ', 'Õ', '\u{301}'),
+    ('ṍ', 'õ', '\u{301}'), ('Ṏ', 'Õ', '\u{308}'), ('ṏ', 'õ', '\u{308}'), ('Ṑ', 'Ō', '\u{300}'),
+    ('ṑ', 'ō', '\u{300}'), ('Ṓ', 'Ō', '\u{301}'), ('ṓ', 'ō', '\u{301}'), ('Ṕ', 'P', '\u{301}'),
+    ('ṕ', 'p', '\u{301}'), ('Ṗ', 'P', '\u{307}'), ('ṗ', 'p', '\u{307}'), ('Ṙ', 'R', '\u{307}'),
+    ('ṙ', 'r', '\u{307}'), ('Ṛ', 'R', '\u{323}'), ('ṛ', 'r', '\u{323}'), ('Ṝ', 'Ṛ', '\u{304}'),
+    ('ṝ', 'ṛ', '\u{304}'), ('Ṟ', 'R', '\u{331}'), ('ṟ', 'r', '\u{331}'), ('Ṡ', 'S', '\u{307}'),
+    ('ṡ', 's', '\u{307}'), ('Ṣ', 'S', '\u{323}'), ('ṣ', 's', '\u{323}'), ('Ṥ', 'Ś', '\u{307}'),
+    ('ṥ', 'ś', '\u{307}'), ('Ṧ', 'Š', '\u{307}'), ('ṧ', 'š', '\u{307}'), ('Ṩ', 'Ṣ', '\u{307}'),
+    ('ṩ', 'ṣ', '\u{307}'), ('Ṫ', 'T', '\u{307}'), ('ṫ', 't', '\u{307}'), ('Ṭ', 'T', '\u{323}'),
+    ('ṭ', 't', '\u{323}'), ('Ṯ', 'T', '\u{331}'), ('ṯ', 't', '\u{331}'), ('Ṱ', 'T', '\u{32d}'),
+    ('ṱ', 't', '\u{32d}'), ('Ṳ', 'U', '\u{324}'), ('ṳ', 'u', '\u{324}'), ('Ṵ', 'U', '\u{330}'),
+    ('ṵ', 'u', '\u{330}'), ('Ṷ', 'U', '\u{32d}'), ('ṷ', 'u', '\u{32d}'), ('Ṹ', 'Ũ', '\u{301}'),
+    ('ṹ', 'ũ', '\u{301}'), ('Ṻ', 'Ū', '\u{308}'), ('ṻ', 'ū', '\u{308}'), ('Ṽ', 'V', '\u{303}'),
+    ('ṽ', 'v', '\u{303}'), ('Ṿ', 'V', '\u{323}'), ('ṿ', 'v', '\u{323}'), ('Ẁ', 'W', '\u{300}'),
+    ('ẁ', 'w', '\u{300}'), ('Ẃ', 'W', '\u{301}'), ('ẃ', 'w', '\u{301}'), ('Ẅ', 'W', '\u{308}'),
+    ('ẅ', 'w', '\u{308}'), ('Ẇ', 'W', '\u{307}'), ('ẇ', 'w', '\u{307}'), ('Ẉ', 'W', '\u{323}'),
+    ('ẉ', 'w', '\u{323}'), ('Ẋ', 'X', '\u{307}'), ('ẋ', 'x', '\u{307}'), ('Ẍ', 'X', '\u{308}'),
+    ('ẍ', 'x', '\u{308}'), ('Ẏ', 'Y', '\u{307}'), ('ẏ', 'y', '\u{307}'), ('Ẑ', 'Z', '\u{302}'),
+    ('ẑ', 'z', '\u{302}'), ('Ẓ', 'Z', '\u{323}'), ('ẓ', 'z', '\u{323}'), ('Ẕ', 'Z', '\u{331}'),
+    ('ẕ', 'z', '\u{331}'), ('ẖ', 'h', '\u{331}'), ('ẗ', 't', '\u{308}'), ('ẘ', 'w', '\u{30a}'),
+    ('ẙ', 'y', '\u{30a}'), ('ẛ', 'ſ', '\u{307}'), ('Ạ', 'A', '\u{323}'), ('ạ', 'a', '\u{323}'),
+    ('Ả', 'A', '\u{309}'), ('ả', 'a', '\u{309}'), ('Ấ', 'Â', '\u{301}'), ('ấ', 'â', '\u{301}'),
+    ('Ầ', 'Â', '\u{300}'), ('ầ', 'â', '\u{300}'), ('Ẩ', 'Â', '\u{309}'), ('ẩ', 'â', '\u{309}'),
+    ('Ẫ', 'Â', '\u{303}'), ('ẫ', 'â', '\u{303}'), ('Ậ', 'Ạ', '\u{302}'), ('ậ', 'ạ', '\u{302}'),
+    ('Ắ', 'Ă', '\u{301}'), ('ắ', 'ă', '\u{301}'), ('Ằ', 'Ă', '\u{300}'), ('ằ', 'ă', '\u{300}'),
+    ('Ẳ', 'Ă', '\u{309}'), ('ẳ', 'ă', '\u{309}'), ('Ẵ', 'Ă', '\u{303}'), ('ẵ', 'ă', '\u{303}'),
+    ('Ặ', 'Ạ', '\u{306}'), ('ặ', 'ạ', '\u{306}'), ('Ẹ', 'E', '\u{323}'), ('ẹ', 'e', '\u{323}'),
+    ('Ẻ', 'E', '\u{309}'), ('ẻ', 'e', '\u{309}'), ('Ẽ', 'E', '\u{303}'), ('ẽ', 'e', '\u{303}'),
+    ('Ế', 'Ê', '\u{301}'), ('ế', 'ê', '\u{301}'), ('Ề', 'Ê', '\u{300}'), ('ề', 'ê', '\u{300}'),
+    ('Ể', 'Ê', '\u{309}'), ('ể', 'ê', '\u{309}'), ('Ễ', 'Ê', '\u{303}'), ('ễ', 'ê', '\u{303}'),
+    ('Ệ', 'Ẹ', '\u{302}'), ('ệ', 'ẹ', '\u{302}'), ('Ỉ', 'I', '\u{309}'), ('ỉ', 'i', '\u{309}'),
+    ('Ị', 'I', '\u{323}'), ('ị', 'i', '\u{323}'), ('Ọ', 'O', '\u{323}'), ('ọ', 'o', '\u{323}'),
+    ('Ỏ', 'O', '\u{309}'), ('ỏ', 'o', '\u{309}'), ('Ố', 'Ô', '\u{301}'), ('ố', 'ô', '\u{301}'),
+    ('Ồ', 'Ô', '\u{300}'), ('ồ', 'ô', '\u{300}'), ('Ổ', 'Ô', '\u{309}'), ('ổ', 'ô', '\u{309}'),
+    ('Ỗ', 'Ô', '\u{303}'), ('ỗ', 'ô', '\u{303}'), ('Ộ', 'Ọ', '\u{302}'), ('ộ', 'ọ', '\u{302}'),
+    ('Ớ', 'Ơ', '\u{301}'), ('ớ', 'ơ', '\u{301}'), ('Ờ', 'Ơ', '\u{300}'), ('ờ', 'ơ', '\u{300}'),
+    ('Ở', 'Ơ', '\u{309}'), ('ở', 'ơ', '\u{309}'), ('Ỡ', 'Ơ', '\u{303}'), ('ỡ', 'ơ', '\u{303}'),
+    ('Ợ', 'Ơ', '\u{323}'), ('ợ', 'ơ', '\u{323}'), ('Ụ', 'U', '\u{323}'), ('ụ', 'u', '\u{323}'),
+    ('Ủ', 'U', '\u{309}'), ('ủ', 'u', '\u{309}'), ('Ứ', 'Ư', '\u{301}'), ('ứ', 'ư', '\u{301}'),
+    ('Ừ', 'Ư', '\u{300}'), ('ừ', 'ư', '\u{300}'), ('Ử', 'Ư', '\u{309}'), ('ử', 'ư', '\u{309}'),
+    ('Ữ', 'Ư', '\u{303}'), ('ữ', 'ư', '\u{303}'), ('Ự', 'Ư', '\u{323}'), ('ự', 'ư', '\u{323}'),
+    ('Ỳ', 'Y', '\u{300}'), ('ỳ', 'y', '\u{300}'), ('Ỵ', 'Y', '\u{323}'), ('ỵ', 'y', '\u{323}'),
+    ('Ỷ', 'Y', '\u{309}'), ('ỷ', 'y', '\u{309}'), ('Ỹ', 'Y', '\u{303}'), ('ỹ', 'y', '\u{303}'),
+    ('ἀ', 'α', '\u{313}'), ('ἁ', 'α', '\u{314}'), ('ἂ', 'ἀ', '\u{300}'), ('ἃ', 'ἁ', '\u{300}'),
+    ('ἄ', 'ἀ', '\u{301}'), ('ἅ', 'ἁ', '\u{301}'), ('ἆ', 'ἀ', '\u{342}'), ('ἇ', 'ἁ', '\u{342}'),
+    ('Ἀ', 'Α', '\u{313}'), ('Ἁ', 'Α', '\u{314}'), ('Ἂ', 'Ἀ', '\u{300}'), ('Ἃ', 'Ἁ', '\u{300}'),
+    ('Ἄ', 'Ἀ', '\u{301}'), ('Ἅ', 'Ἁ', '\u{301}'), ('Ἆ', 'Ἀ', '\u{342}'), ('Ἇ', 'Ἁ', '\u{342}'),
+    ('ἐ', 'ε', '\u{313}'), ('ἑ', 'ε', '\u{314}'), ('ἒ', 'ἐ', '\u{300}'), ('ἓ', 'ἑ', '\u{300}'),
+    ('ἔ', 'ἐ', '\u{301}'), ('ἕ', 'ἑ', '\u{301}'), ('Ἐ', 'Ε', '\u{313}'), ('Ἑ', 'Ε', '\u{314}'),
+    ('Ἒ', 'Ἐ', '\u{300}'), ('Ἓ', 'Ἑ', '\u{300}'), ('Ἔ', 'Ἐ', '\u{301}'), ('Ἕ', 'Ἑ', '\u{301}'),
+    ('ἠ', 'η', '\u{313}'), ('ἡ', 'η', '\u{314}'), ('ἢ', 'ἠ', '\u{300}'), ('ἣ', 'ἡ', '\u{300}'),
+    ('ἤ', 'ἠ', '\u{301}'), ('ἥ', 'ἡ', '\u{301}'), ('ἦ', 'ἠ', '\u{342}'), ('ἧ', 'ἡ', '\u{342}'),
+    ('Ἠ', 'Η', '\u{313}'), ('Ἡ', 'Η', '\u{314}'), ('Ἢ', 'Ἠ', '\u{300}'), ('Ἣ', 'Ἡ', '\u{300}'),
+    ('Ἤ', 'Ἠ', '\u{301}'), ('Ἥ', 'Ἡ', '\u{301}'), ('Ἦ', 'Ἠ', '\u{342}'), ('Ἧ', 'Ἡ', '\u{342}'),
+    ('ἰ', 'ι', '\u{313}'), ('ἱ', 'ι', '\u{314}'), ('ἲ', 'ἰ', '\u{300}'), ('ἳ', 'ἱ', '\u{300}'),
+    ('ἴ', 'ἰ', '\u{301}'), ('ἵ', 'ἱ', '\u{301}'), ('ἶ', 'ἰ', '\u{342}'), ('ἷ', 'ἱ', '\u{342}'),
+    ('Ἰ', 'Ι', '\u{313}'), ('Ἱ', 'Ι', '\u{314}'), ('Ἲ', 'Ἰ', '\u{300}'), ('Ἳ', 'Ἱ', '\u{300}'),
+    ('Ἴ', 'Ἰ', '\u{301}'), ('Ἵ', 'Ἱ', '\u{301}'), ('Ἶ', 'Ἰ', '\u{342}'), ('Ἷ', 'Ἱ', '\u{342}'),
+    ('ὀ', 'ο', '\u{313}'), ('ὁ', 'ο', '\u{314}'), ('ὂ', 'ὀ', '\u{300}'), ('ὃ', 'ὁ', '\u{300}'),
+    ('ὄ', 'ὀ', '\u{301}'), ('ὅ', 'ὁ', '\u{301}'), ('Ὀ', 'Ο', '\u{313}'), ('Ὁ', 'Ο', '\u{314}'),
+    ('Ὂ', 'Ὀ', '\u{300}'), ('Ὃ', 'Ὁ', '\u{300}'), ('Ὄ', 'Ὀ', '\u{301}'), ('Ὅ', 'Ὁ', '\u{301}'),
+    ('ὐ', 'υ', '\u{313}'), ('ὑ', 'υ', '\u{314}'), ('ὒ', 'ὐ', '\u{300}'), ('ὓ', 'ὑ', '\u{300}'),
+    ('ὔ', 'ὐ', '\u{301}'), ('ὕ', 'ὑ', '\u{301}'), ('ὖ', 'ὐ', '\u{342}'), ('ὗ', 'ὑ', '\u{342}'),
+    ('Ὑ', 'Υ', '\u{314}'), ('Ὓ', 'Ὑ', '\u{300}'), ('Ὕ', 'Ὑ', '\u{301}'), ('Ὗ', 'Ὑ', '\u{342}'),
+    ('ὠ', 'ω', '\u{313}'), ('ὡ', 'ω', '\u{314}'), ('ὢ', 'ὠ', '\u{300}'), ('ὣ', 'ὡ', '\u{300}'),
+    ('ὤ', 'ὠ', '\u{301}'), ('ὥ', 'ὡ', '\u{301}'), ('ὦ', 'ὠ', '\u{342}'), ('ὧ', 'ὡ', '\u{342}'),
+    ('Ὠ', 'Ω', '\u{313}'), ('Ὡ', 'Ω', '\u{314}'), ('Ὢ', 'Ὠ', '\u{300}'), ('Ὣ', 'Ὡ', '\u{300}'),
+    ('Ὤ', 'Ὠ', '\u{301}'), ('Ὥ', 'Ὡ', '\u{301}'), ('Ὦ', 'Ὠ', '\u{342}'), ('Ὧ', 'Ὡ', '\u{342}'),
+    ('ὰ', 'α', '\u{300}'), ('ὲ', 'ε', '\u{300}'), ('ὴ', 'η', '\u{300}'), ('ὶ', 'ι', '\u{300}'),
+    ('ὸ', 'ο', '\u{300}'), ('ὺ', 'υ', '\u{300}'), ('ὼ', 'ω', '\u{300}'), ('ᾀ', 'ἀ', '\u{345}'),
+    ('ᾁ', 'ἁ', '\u{345}'), ('ᾂ', 'ἂ', '\u{345}'), ('ᾃ', 'ἃ', '\u{345}'), ('ᾄ', 'ἄ', '\u{345}'),
+    ('ᾅ', 'ἅ', '\u{345}'), ('ᾆ', 'ἆ', '\u{345}'), ('ᾇ', 'ἇ', '\u{345}'), ('ᾈ', 'Ἀ', '\u{345}'),
+    ('ᾉ', 'Ἁ', '\u{345}'), ('ᾊ', 'Ἂ', '\u{345}'), ('ᾋ', 'Ἃ', '\u{345}'), ('ᾌ', 'Ἄ', '\u{345}'),
+    ('ᾍ', 'Ἅ', '\u{345}'), ('ᾎ', 'Ἆ', '\u{345}'), ('ᾏ', 'Ἇ', '\u{345}'), ('ᾐ', 'ἠ', '\u{345}'),
+    ('ᾑ', 'ἡ', '\u{345}'), ('ᾒ', 'ἢ', '\u{345}'), ('ᾓ', 'ἣ', '\u{345}'), ('ᾔ', 'ἤ', '\u{345}'),
+    ('ᾕ', 'ἥ', '\u{345}'), ('ᾖ', 'ἦ', '\u{345}'), ('ᾗ', 'ἧ', '\u{345}'), ('ᾘ', 'Ἠ', '\u{345}'),
+    ('ᾙ', 'Ἡ', '\u{345}'), ('ᾚ', 'Ἢ', '\u{345}'), ('ᾛ', 'Ἣ', '\u{345}'), ('ᾜ', 'Ἤ', '\u{345}'),
+    ('ᾝ', 'Ἥ', '\u{345}'), ('ᾞ', 'Ἦ', '\u{345}'), ('ᾟ', 'Ἧ', '\u{345}'), ('ᾠ', 'ὠ', '\u{345}'),
+    ('ᾡ', 'ὡ', '\u{345}'), ('ᾢ', 'ὢ', '\u{345}'), ('ᾣ', 'ὣ', '\u{345}'), ('ᾤ', 'ὤ', '\u{345}'),
+    ('ᾥ', 'ὥ', '\u{345}'), ('ᾦ', 'ὦ', '\u{345}'), ('ᾧ', 'ὧ', '\u{345}'), ('ᾨ', 'Ὠ', '\u{345}'),
+    ('ᾩ', 'Ὡ', '\u{345}'), ('ᾪ', 'Ὢ', '\u{345}'), ('ᾫ', 'Ὣ', '\u{345}'), ('ᾬ', 'Ὤ', '\u{345}'),
+    ('ᾭ', 'Ὥ', '\u{345}'), ('ᾮ', 'Ὦ', '\u{345}'), ('ᾯ', 'Ὧ', '\u{345}'), ('ᾰ', 'α', '\u{306}'),
+    ('ᾱ', 'α', '\u{304}'), ('ᾲ', 'ὰ', '\u{345}'), ('ᾳ', 'α', '\u{345}'), ('ᾴ', 'ά', '\u{345}'),
+    ('ᾶ', 'α', '\u{342}'), ('ᾷ', 'ᾶ', '\u{345}'), ('Ᾰ', 'Α', '\u{306}'), ('Ᾱ', 'Α', '\u{304}'),
+    ('Ὰ', 'Α', '\u{300}'), ('ᾼ', 'Α', '\u{345}'), ('῁', '¨', '\u{342}'), ('ῂ', 'ὴ', '\u{345}'),
+    ('ῃ', 'η', '\u{345}'), ('ῄ', 'ή', '\u{345}'), ('ῆ', 'η', '\u{342}'), ('ῇ', 'ῆ', '\u{345}'),
+    ('Ὲ', 'Ε', '\u{300}'), ('Ὴ', 'Η', '\u{300}'), ('ῌ', 'Η', '\u{345}'), ('῍', '᾿', '\u{300}'),
+    ('῎', '᾿', '\u{301}'), ('῏', '᾿', '\u{342}'), ('ῐ', 'ι', '\u{306}'), ('ῑ', 'ι', '\u{304}'),
+    ('ῒ', 'ϊ', '\u{300}'), ('ῖ', 'ι', '\u{342}'), ('ῗ', 'ϊ', '\u{342}'), ('Ῐ', 'Ι', '\u{306}'),
+    ('Ῑ', 'Ι', '\u{304}'), ('Ὶ', 'Ι', '\u{300}'), ('῝', '῾', '\u{300}'), ('῞', '῾', '\u{301}'),
+    ('῟', '῾', '\u{342}'), ('ῠ', 'υ', '\u{306}'), ('ῡ', 'υ', '\u{304}'), ('ῢ', 'ϋ', '\u{300}'),
+    ('ῤ', 'ρ', '\u{313}'), ('ῥ', 'ρ', '\u{314}'), ('ῦ', 'υ', '\u{342}'), ('ῧ', 'ϋ', '\u{342}'),
+    ('Ῠ', 'Υ', '\u{306}'), ('Ῡ', 'Υ', '\u{304}'), ('Ὺ', 'Υ', '\u{300}'), ('Ῥ', 'Ρ', '\u{314}'),
+    ('῭', '¨', '\u{300}'), ('ῲ', 'ὼ', '\u{345}'), ('ῳ', 'ω', '\u{345}'), ('ῴ', 'ώ', '\u{345}'),
+    ('ῶ', 'ω', '\u{342}'), ('ῷ', 'ῶ', '\u{345}'), ('Ὸ', 'Ο', '\u{300}'), ('Ὼ', 'Ω', '\u{300}'),
+    ('ῼ', 'Ω', '\u{345}'),
+];
@@ -0,0 +1,65 @@
+use std::time::Duration;
+
+/// Parses sizes such as `512M`, `4G`, or a plain byte count.
+pub fn parse_size(value: &str) -> Result<u64, String> {
+    let (number, suffix) = split_number(value);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("invalid size \"{value}\" (expected e.g. 512M or 4G)"))?;
+    let multiplier: u64 = match suffix.to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1 << 10,
+        "M" | "MB" | "MIB" => 1 << 20,
+        "G" | "GB" | "GIB" => 1 << 30,
+        "T" | "TB" | "TIB" => 1 << 40,
+        _ => return Err(format!("invalid size suffix in \"{value}\"")),
+    };
+    number
+        .checked_mul(multiplier)
+        .ok_or_else(|| format!("size \"{value}\" is too large"))
+}
+
+/// Parses durations such as `90s`, `15m`, `4h`, `2d`, or a plain number of seconds.
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+    let (number, suffix) = split_number(value);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("invalid duration \"{value}\" (expected e.g. 30s, 15m, or 4h)"))?;
+    let seconds = match suffix.to_ascii_lowercase().as_str() {
+        "" | "s" | "sec" | "secs" => number,
+        "ms" => number / 1000.0,
+        "m" | "min" | "mins" => number * 60.0,
+        "h" | "hr" | "hrs" => number * 3600.0,
+        "d" | "day" | "days" => number * 86_400.0,
+        _ => return Err(format!("invalid duration suffix in \"{value}\"")),
+    };
+    Duration::try_from_secs_f64(seconds).map_err(|_| format!("invalid duration \"{value}\""))
+}
+
+/// Parses fractions such as `1%`, `0.5%`, or `0.01`, above zero and at most the whole.
+pub fn parse_fraction(value: &str) -> Result<f64, String> {
+    let (number, suffix) = split_number(value);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("invalid fraction \"{value}\" (expected e.g. 1% or 0.01)"))?;
+    let fraction = match suffix {
+        "" => number,
+        "%" => number / 100.0,
+        _ => return Err(format!("invalid fraction suffix in \"{value}\"")),
+    };
+    if fraction <= 0.0 || fraction > 1.0 {
+        return Err(format!(
+            "fraction \"{value}\" must be above 0 and at most 100%"
+        ));
+    }
+    Ok(fraction)
+}
+
+fn split_number(value: &str) -> (&str, &str) {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(value.len());
+    let (number, suffix) = value.split_at(split);
+    (number, suffix.trim())
+}
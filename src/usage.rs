@@ -0,0 +1,159 @@
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::status::Breakdown;
+
+/// What the process has used so far, for sizing the machines that run these jobs.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Resources {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub peak_rss_bytes: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_cpu_seconds: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system_cpu_seconds: Option<f64>,
+    /// Bytes fetched from storage, memory-mapped wordlists included; page cache hits are free.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub read_bytes: Option<u64>,
+    /// Page faults that had to read from storage, such as a memory-mapped wordlist's first
+    /// touch of a page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub major_page_faults: Option<u64>,
+    /// Each worker's CPU time over the time the stages ran, from 0 to 1.
+    pub worker_utilization: Vec<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub worker_time: Option<Breakdown>,
+}
+
+/// Snapshots the process's usage, with `workers` the CPU time of each worker over `busy`, the
+/// time its stages ran.
+pub fn measure(workers: &[Duration], busy: Duration) -> Resources {
+    let mut resources = process();
+    resources.read_bytes = read_bytes().or(resources.read_bytes);
+    resources.worker_utilization = workers
+        .iter()
+        .map(|cpu| {
+            if busy.is_zero() {
+                0.0
+            } else {
+                (cpu.as_secs_f64() / busy.as_secs_f64()).min(1.0)
+            }
+        })
+        .collect();
+    resources
+}
+
+#[cfg(unix)]
+fn process() -> Resources {
+    // SAFETY: `usage` is plain data the call fills in.
+    let usage = unsafe {
+        let mut usage: libc::rusage = std::mem::zeroed();
+        if libc::getrusage(libc::RUSAGE_SELF, &mut usage) != 0 {
+            return Resources::default();
+        }
+        usage
+    };
+    let seconds = |time: libc::timeval| time.tv_sec as f64 + time.tv_usec as f64 / 1e6;
+    // Linux and the BSDs count kilobytes; macOS counts bytes.
+    let rss_unit = if cfg!(target_os = "macos") { 1 } else { 1024 };
+    Resources {
+        peak_rss_bytes: Some(usage.ru_maxrss as u64 * rss_unit),
+        user_cpu_seconds: Some(seconds(usage.ru_utime)),
+        system_cpu_seconds: Some(seconds(usage.ru_stime)),
+        read_bytes: Some(usage.ru_inblock as u64 * 512),
+        major_page_faults: Some(usage.ru_majflt as u64),
+        ..Resources::default()
+    }
+}
+
+#[cfg(not(unix))]
+fn process() -> Resources {
+    Resources::default()
+}
+
+/// Linux counts storage reads exactly, where `ru_inblock` rounds to blocks.
+fn read_bytes() -> Option<u64> {
+    let io = std::fs::read_to_string("/proc/self/io").ok()?;
+    io.lines()
+        .find_map(|line| line.strip_prefix("read_bytes:"))?
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// CPU time the calling thread has used, or zero where that is not measurable.
+#[cfg(unix)]
+pub fn thread_cpu_time() -> Duration {
+    // SAFETY: `time` is plain data the call fills in.
+    unsafe {
+        let mut time: libc::timespec = std::mem::zeroed();
+        if libc::clock_gettime(libc::CLOCK_THREAD_CPUTIME_ID, &mut time) != 0 {
+            return Duration::ZERO;
+        }
+        Duration::new(time.tv_sec as u64, time.tv_nsec as u32)
+    }
+}
+
+#[cfg(not(unix))]
+pub fn thread_cpu_time() -> Duration {
+    Duration::ZERO
+}
+
+/// Prints the usage summary; `verbose` adds where worker time went.
+pub fn print(resources: &Resources, verbose: bool) {
+    let mut parts = Vec::new();
+    if let Some(rss) = resources.peak_rss_bytes {
+        parts.push(format!("peak RSS {}", mib(rss)));
+    }
+    if let (Some(user), Some(system)) = (resources.user_cpu_seconds, resources.system_cpu_seconds) {
+        parts.push(format!(
+            "CPU {:.2}s ({user:.2}s user, {system:.2}s system)",
+            user + system
+        ));
+    }
+    if let Some(read) = resources.read_bytes {
+        parts.push(format!("{} read from storage", mib(read)));
+    }
+    if verbose && let Some(faults) = resources.major_page_faults {
+        parts.push(format!("{faults} major page faults"));
+    }
+    if !parts.is_empty() {
+        println!("Resources: {}", parts.join(", "));
+    }
+    if verbose && let Some(split) = &resources.worker_time {
+        println!(
+            "Worker time: {:.0}% building candidates, {:.0}% fast check, {:.0}% verification, \
+             {:.0}% waiting ({} stall{} on pause or throttling); {}",
+            split.generate * 100.0,
+            split.fast_check * 100.0,
+            split.verify * 100.0,
+            split.stalled * 100.0,
+            split.stalls,
+            if split.stalls == 1 { "" } else { "s" },
+            split.bottleneck(resources.major_page_faults.unwrap_or(0))
+        );
+    }
+    let utilization = &resources.worker_utilization;
+    if utilization.is_empty() || utilization.iter().all(|&busy| busy == 0.0) {
+        return;
+    }
+    let average = utilization.iter().sum::<f64>() / utilization.len() as f64;
+    let low = utilization.iter().copied().fold(f64::INFINITY, f64::min);
+    let high = utilization.iter().copied().fold(0.0, f64::max);
+    if utilization.len() == 1 {
+        println!("Worker utilization: {:.0}%", average * 100.0);
+    } else {
+        println!(
+            "Worker utilization: {:.0}% on average, {:.0}% to {:.0}% across {} workers",
+            average * 100.0,
+            low * 100.0,
+            high * 100.0,
+            utilization.len()
+        );
+    }
+}
+
+fn mib(bytes: u64) -> String {
+    format!("{:.1} MiB", bytes as f64 / (1 << 20) as f64)
+}
@@ -0,0 +1,393 @@
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+    process::{Command, Stdio},
+    sync::{Mutex, OnceLock},
+};
+
+use aes::{
+    Aes256, Block,
+    cipher::{BlockCipherEncrypt, KeyInit},
+};
+use anyhow::{Context, Result, bail};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use zeroize::Zeroizing;
+
+const VERSION: u32 = 1;
+/// PBKDF2-HMAC-SHA256 rounds turning the secret into keys; once per salt, so once per run.
+/// Tests, which derive keys for many vaults, use fewer.
+const ITERATIONS: u32 = if cfg!(test) { 1000 } else { 200_000 };
+const KEYRING_SERVICE: &str = "unzippoo";
+const KEYRING_ACCOUNT: &str = "store";
+
+static VAULT: OnceLock<Vault> = OnceLock::new();
+
+/// The AES key and the HMAC key, wiped when dropped.
+type Keys = Zeroizing<[[u8; 32]; 2]>;
+
+/// Where the secret protecting potfiles and result files comes from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum KeySource {
+    /// A random secret kept in the OS keyring, created on first use.
+    Keyring,
+    /// A passphrase in an environment variable.
+    Env(String),
+}
+
+/// Parses `keyring` or `env:NAME`.
+pub fn parse_key_source(value: &str) -> Result<KeySource, String> {
+    match value.strip_prefix("env:") {
+        Some("") => Err("env: needs a variable name, as in env:UNZIPPOO_STORE_KEY".to_owned()),
+        Some(name) => Ok(KeySource::Env(name.to_owned())),
+        None if value == "keyring" => Ok(KeySource::Keyring),
+        None => Err(format!(
+            "invalid key source \"{value}\" (expected keyring or env:NAME)"
+        )),
+    }
+}
+
+/// A record as it is stored once encrypted: AES-256-CTR, then HMAC-SHA256 over the salt,
+/// nonce, and ciphertext. Still one JSON object, so potfiles stay JSON Lines.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Sealed {
+    pub encrypted: u32,
+    salt: String,
+    nonce: String,
+    data: String,
+    mac: String,
+}
+
+/// Encrypts and decrypts records with keys derived from one secret.
+pub struct Vault {
+    /// Wiped when the vault is dropped, like the keys derived from it.
+    secret: Zeroizing<String>,
+    /// Shared by every record this run seals, so readers derive one key per run, not per line.
+    salt: [u8; 16],
+    keys: Mutex<HashMap<Vec<u8>, Keys>>,
+}
+
+impl Vault {
+    pub fn open(source: &KeySource) -> Result<Self> {
+        let secret = match source {
+            KeySource::Keyring => keyring_secret()?,
+            KeySource::Env(name) => match std::env::var(name) {
+                Ok(secret) if !secret.is_empty() => Zeroizing::new(secret),
+                _ => bail!("Environment variable {name} does not hold a store passphrase"),
+            },
+        };
+        Self::from_secret(secret)
+    }
+
+    /// A vault keyed with `secret` itself, however it was obtained.
+    pub fn from_secret(secret: Zeroizing<String>) -> Result<Self> {
+        Ok(Self {
+            secret,
+            salt: random()?,
+            keys: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub fn seal(&self, plaintext: &[u8]) -> Result<Sealed> {
+        let nonce: [u8; 16] = random()?;
+        let keys = self.keys(&self.salt);
+        let [cipher_key, mac_key] = &*keys;
+        let mut data = plaintext.to_vec();
+        apply_keystream(cipher_key, &nonce, &mut data);
+        let mac = authenticate(mac_key, &self.salt, &nonce, &data);
+        Ok(Sealed {
+            encrypted: VERSION,
+            salt: hex(&self.salt),
+            nonce: hex(&nonce),
+            data: hex(&data),
+            mac: hex(&mac),
+        })
+    }
+
+    pub fn unseal(&self, sealed: &Sealed) -> Result<Vec<u8>> {
+        if sealed.encrypted != VERSION {
+            bail!("Encrypted record uses format version {}", sealed.encrypted);
+        }
+        let parse = |field: &str, value: &str| {
+            unhex(value).with_context(|| format!("Encrypted record has an invalid {field}"))
+        };
+        let salt = parse("salt", &sealed.salt)?;
+        let nonce: [u8; 16] = parse("nonce", &sealed.nonce)?
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("Encrypted record has an invalid nonce"))?;
+        let mut data = parse("data", &sealed.data)?;
+        let mac = parse("mac", &sealed.mac)?;
+        let keys = self.keys(&salt);
+        let [cipher_key, mac_key] = &*keys;
+        let mut check = Hmac::<Sha256>::new_from_slice(mac_key).expect("any key length");
+        check.update(&salt);
+        check.update(&nonce);
+        check.update(&data);
+        if check.verify_slice(&mac).is_err() {
+            bail!("Encrypted record does not match the key (wrong passphrase, or tampered with)");
+        }
+        apply_keystream(cipher_key, &nonce, &mut data);
+        Ok(data)
+    }
+
+    fn keys(&self, salt: &[u8]) -> Keys {
+        let mut keys = self
+            .keys
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        keys.entry(salt.to_vec())
+            .or_insert_with(|| {
+                let mut derived = Zeroizing::new([0u8; 64]);
+                pbkdf2::pbkdf2_hmac::<Sha256>(
+                    self.secret.as_bytes(),
+                    salt,
+                    ITERATIONS,
+                    &mut *derived,
+                );
+                let (cipher_key, mac_key) = derived.split_at(32);
+                Zeroizing::new([
+                    cipher_key.try_into().expect("32 bytes"),
+                    mac_key.try_into().expect("32 bytes"),
+                ])
+            })
+            .clone()
+    }
+}
+
+/// Makes `vault` the one every potfile and result file write goes through.
+pub fn install(vault: Vault) {
+    let _ = VAULT.set(vault);
+}
+
+pub fn installed() -> Option<&'static Vault> {
+    VAULT.get()
+}
+
+/// AES-256 in counter mode, with the nonce as the initial counter block.
+fn apply_keystream(key: &[u8; 32], nonce: &[u8; 16], data: &mut [u8]) {
+    let cipher = Aes256::new_from_slice(key).expect("32-byte key");
+    let counter = u128::from_be_bytes(*nonce);
+    for (index, chunk) in data.chunks_mut(16).enumerate() {
+        let mut block = Block::from(counter.wrapping_add(index as u128).to_be_bytes());
+        cipher.encrypt_block(&mut block);
+        for (byte, key) in chunk.iter_mut().zip(block.iter()) {
+            *byte ^= key;
+        }
+    }
+}
+
+fn authenticate(key: &[u8; 32], salt: &[u8], nonce: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("any key length");
+    mac.update(salt);
+    mac.update(nonce);
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn random<const N: usize>() -> Result<[u8; N]> {
+    let mut bytes = [0u8; N];
+    getrandom::fill(&mut bytes)
+        .map_err(|error| anyhow::anyhow!("No secure random numbers available: {error}"))?;
+    Ok(bytes)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn unhex(value: &str) -> Option<Vec<u8>> {
+    // Checked up front, since `from_str_radix` would also take a sign.
+    if !value.len().is_multiple_of(2) || !value.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..value.len())
+        .step_by(2)
+        .map(|at| u8::from_str_radix(value.get(at..at + 2)?, 16).ok())
+        .collect()
+}
+
+/// Looks the store secret up in the OS keyring, creating a random one the first time.
+fn keyring_secret() -> Result<Zeroizing<String>> {
+    let (lookup, store): (Vec<&str>, Vec<&str>) = if cfg!(target_os = "macos") {
+        (
+            vec![
+                "security",
+                "find-generic-password",
+                "-s",
+                KEYRING_SERVICE,
+                "-a",
+                KEYRING_ACCOUNT,
+                "-w",
+            ],
+            vec![
+                "security",
+                "add-generic-password",
+                "-s",
+                KEYRING_SERVICE,
+                "-a",
+                KEYRING_ACCOUNT,
+                "-w",
+            ],
+        )
+    } else if cfg!(unix) {
+        (
+            vec![
+                "secret-tool",
+                "lookup",
+                "service",
+                KEYRING_SERVICE,
+                "account",
+                KEYRING_ACCOUNT,
+            ],
+            vec![
+                "secret-tool",
+                "store",
+                "--label",
+                "unzippoo store key",
+                "service",
+                KEYRING_SERVICE,
+                "account",
+                KEYRING_ACCOUNT,
+            ],
+        )
+    } else {
+        bail!("No supported keyring on this platform; use --store-key env:NAME");
+    };
+
+    let missing = |error: io::Error| {
+        if error.kind() == io::ErrorKind::NotFound {
+            anyhow::anyhow!(
+                "{} not found; install it or use --store-key env:NAME",
+                lookup[0]
+            )
+        } else {
+            anyhow::anyhow!("{} failed: {error}", lookup[0])
+        }
+    };
+    let output = Command::new(lookup[0])
+        .args(&lookup[1..])
+        .stderr(Stdio::null())
+        .output()
+        .map_err(missing)?;
+    let stdout = Zeroizing::new(output.stdout);
+    let found = Zeroizing::new(String::from_utf8_lossy(&stdout).trim().to_owned());
+    if output.status.success() && !found.is_empty() {
+        return Ok(found);
+    }
+
+    let secret = Zeroizing::new(hex(&*Zeroizing::new(random::<32>()?)));
+    // Both read the secret from stdin, never argv, where `ps` would show it to other users:
+    // secret-tool up to end of input, and security, given `-w` last, as a line entered twice.
+    let input = Zeroizing::new(if cfg!(target_os = "macos") {
+        format!("{}\n{}\n", *secret, *secret)
+    } else {
+        secret.to_string()
+    });
+    let mut child = Command::new(store[0])
+        .args(&store[1..])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .map_err(missing)?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(input.as_bytes())?;
+    }
+    let status = child.wait()?;
+    if !status.success() {
+        bail!("{} could not save the store key ({status})", store[0]);
+    }
+    Ok(secret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_secret(secret: &str) -> Vault {
+        Vault::from_secret(Zeroizing::new(secret.to_owned())).unwrap()
+    }
+
+    /// The same hex string with its first digit changed.
+    fn flipped(value: &str) -> String {
+        let first = if value.starts_with('0') { '1' } else { '0' };
+        format!("{first}{}", &value[1..])
+    }
+
+    fn unseal_error(vault: &Vault, sealed: &Sealed) -> String {
+        format!("{:#}", vault.unseal(sealed).unwrap_err())
+    }
+
+    #[test]
+    fn unseals_what_it_sealed() {
+        let vault = with_secret("correct horse");
+        let sealed = vault.seal(b"{\"password\":\"hunter2\"}").unwrap();
+        assert_eq!(sealed.encrypted, VERSION);
+        assert!(!sealed.data.contains("hunter2"));
+        assert_eq!(
+            vault.unseal(&sealed).unwrap(),
+            b"{\"password\":\"hunter2\"}"
+        );
+        assert_eq!(vault.unseal(&vault.seal(b"").unwrap()).unwrap(), b"");
+
+        // A record from another run has its own salt, and the same secret still opens it.
+        let json = serde_json::to_string(&sealed).unwrap();
+        let reread: Sealed = serde_json::from_str(&json).unwrap();
+        let later = with_secret("correct horse");
+        assert_eq!(
+            later.unseal(&reread).unwrap(),
+            b"{\"password\":\"hunter2\"}"
+        );
+    }
+
+    #[test]
+    fn rejects_tampering_and_the_wrong_passphrase() {
+        let vault = with_secret("correct horse");
+        let integrity = "does not match the key";
+        let mut sealed = vault.seal(b"some secret record").unwrap();
+
+        let data = sealed.data.clone();
+        sealed.data = flipped(&data);
+        assert!(unseal_error(&vault, &sealed).contains(integrity));
+        sealed.data = data;
+
+        let mac = sealed.mac.clone();
+        sealed.mac = flipped(&mac);
+        assert!(unseal_error(&vault, &sealed).contains(integrity));
+        sealed.mac = mac;
+
+        let nonce = sealed.nonce.clone();
+        sealed.nonce = flipped(&nonce);
+        assert!(unseal_error(&vault, &sealed).contains(integrity));
+        sealed.nonce = nonce;
+
+        assert!(unseal_error(&with_secret("wrong horse"), &sealed).contains(integrity));
+
+        sealed.encrypted = VERSION + 1;
+        assert!(unseal_error(&vault, &sealed).contains("format version 2"));
+    }
+
+    #[test]
+    fn rejects_malformed_fields() {
+        let vault = with_secret("correct horse");
+        let mut sealed = vault.seal(b"record").unwrap();
+        sealed.nonce.pop();
+        assert!(unseal_error(&vault, &sealed).contains("invalid nonce"));
+        sealed.nonce.truncate(30);
+        assert!(unseal_error(&vault, &sealed).contains("invalid nonce"));
+        sealed.nonce = "00".repeat(16);
+        sealed.data = "zz".to_owned();
+        assert!(unseal_error(&vault, &sealed).contains("invalid data"));
+    }
+
+    #[test]
+    fn unhex_needs_pairs_of_hex_digits() {
+        assert_eq!(unhex(""), Some(Vec::new()));
+        assert_eq!(unhex("00ff7A"), Some(vec![0x00, 0xff, 0x7a]));
+        assert_eq!(unhex(&hex(&[1, 2, 254])), Some(vec![1, 2, 254]));
+        assert_eq!(unhex("abc"), None);
+        assert_eq!(unhex("0g"), None);
+        assert_eq!(unhex("+1"), None);
+        assert_eq!(unhex("é0"), None);
+    }
+}
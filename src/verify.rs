@@ -0,0 +1,247 @@
+use std::io;
+
+use anyhow::{Result, anyhow, bail, ensure};
+use clap::ValueEnum;
+use zip::{ZipArchive, result::ZipError};
+
+use crate::archive::{self, Encryption, Entry};
+use crate::kdf::{self, HmacLanes, LANES};
+use crate::zipcrypto::{self, Keys};
+
+/// Length of the HMAC-SHA1 authentication code that follows WinZip AES data.
+const AES_AUTH_CODE_LEN: usize = 10;
+
+/// Which implementation runs the fast check over each batch of candidates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Backend {
+    /// One candidate at a time with the scalar (SHA extension accelerated) PBKDF2.
+    Cpu,
+    /// Eight candidates at once with the multi-buffer PBKDF2.
+    Simd,
+    Opencl,
+}
+
+impl Backend {
+    /// How many candidates the backend wants per batch.
+    pub fn batch_size(self) -> usize {
+        match self {
+            Backend::Cpu | Backend::Opencl => 1,
+            Backend::Simd => LANES,
+        }
+    }
+}
+
+/// Everything needed to test candidates against the target entry, prepared once per run.
+#[derive(Clone)]
+pub struct Verifier {
+    entry_archive: Vec<u8>,
+    aes: Option<AesCheck>,
+    zipcrypto: Option<ZipCryptoCheck>,
+    backend: Backend,
+}
+
+/// The cached ZipCrypto encryption header and the check byte its decrypted form ends with.
+#[derive(Clone)]
+struct ZipCryptoCheck {
+    header: [u8; zipcrypto::HEADER_LEN],
+    expected: u8,
+}
+
+/// The cached WinZip AES header: the salt, and which two derived bytes must match the stored
+/// password verifier.
+#[derive(Clone)]
+struct AesCheck {
+    salt: Vec<u8>,
+    expected: [u8; 2],
+    /// PBKDF2 output block (1-based) holding the verifier, which follows both keys.
+    block: u32,
+    offset: usize,
+}
+
+impl Verifier {
+    pub fn new(entry_archive: Vec<u8>, entry: &Entry, backend: Backend) -> Result<Self> {
+        if backend == Backend::Opencl {
+            bail!("The OpenCL backend is not available in this build");
+        }
+        let aes = match entry.encryption {
+            Encryption::Aes { strength } => Some(AesCheck::new(
+                archive::entry_data(&entry_archive, entry)?,
+                strength,
+            )?),
+            _ => None,
+        };
+        let zipcrypto = match entry.encryption {
+            Encryption::ZipCrypto => {
+                let data = archive::entry_data(&entry_archive, entry)?;
+                let header = data
+                    .get(..zipcrypto::HEADER_LEN)
+                    .and_then(|header| header.try_into().ok())
+                    .ok_or_else(|| anyhow!("ZipCrypto entry is too short to hold its header"))?;
+                Some(ZipCryptoCheck {
+                    header,
+                    expected: entry.zipcrypto_check_byte(),
+                })
+            }
+            _ => None,
+        };
+        Ok(Self {
+            entry_archive,
+            aes,
+            zipcrypto,
+            backend,
+        })
+    }
+
+    /// The standalone single-entry archive holding the target.
+    pub fn entry_archive(&self) -> &[u8] {
+        &self.entry_archive
+    }
+
+    pub fn batch_size(&self) -> usize {
+        self.backend.batch_size()
+    }
+
+    /// The fast check: marks which candidates may be the password. It never rejects the right
+    /// one, but about 1 in 65536 wrong candidates pass for AES and 1 in 256 for ZipCrypto, so
+    /// each marked candidate still has to be confirmed.
+    pub fn check_batch(&self, candidates: &[&str]) -> Vec<bool> {
+        if let Some(zipcrypto) = &self.zipcrypto {
+            return candidates
+                .iter()
+                .map(|candidate| zipcrypto.passes(candidate.as_bytes()))
+                .collect();
+        }
+        let Some(aes) = &self.aes else {
+            return vec![true; candidates.len()];
+        };
+        match self.backend {
+            Backend::Simd => candidates
+                .chunks(LANES)
+                .flat_map(|chunk| aes.passes_lanes(chunk))
+                .collect(),
+            Backend::Cpu | Backend::Opencl => candidates
+                .iter()
+                .map(|candidate| aes.passes(candidate.as_bytes()))
+                .collect(),
+        }
+    }
+
+    /// Confirms a candidate that passed the fast check by decrypting the entry.
+    pub fn confirm(&self, password: &str) -> Result<bool> {
+        password_matches(&self.entry_archive, password)
+    }
+}
+
+impl ZipCryptoCheck {
+    fn passes(&self, password: &[u8]) -> bool {
+        Keys::from_password(password).header_check_byte(&self.header) == self.expected
+    }
+}
+
+impl AesCheck {
+    fn new(data: &[u8], strength: u8) -> Result<Self> {
+        let key_len = match strength {
+            1 => 16,
+            2 => 24,
+            3 => 32,
+            other => bail!("Unknown AES key strength {other}"),
+        };
+        let salt_len = key_len / 2;
+        ensure!(
+            data.len() >= salt_len + 2 + AES_AUTH_CODE_LEN,
+            "AES entry is too short to hold its salt and password verifier"
+        );
+        let verifier_at = 2 * key_len;
+        Ok(Self {
+            salt: data[..salt_len].to_vec(),
+            expected: [data[salt_len], data[salt_len + 1]],
+            block: (verifier_at / kdf::DIGEST_LEN) as u32 + 1,
+            offset: verifier_at % kdf::DIGEST_LEN,
+        })
+    }
+
+    fn passes(&self, password: &[u8]) -> bool {
+        let block = kdf::pbkdf2_block(password, &self.salt, kdf::AES_ITERATIONS, self.block);
+        block[self.offset..self.offset + 2] == self.expected
+    }
+
+    /// Checks up to `LANES` candidates in one multi-buffer derivation; unused lanes are padded.
+    fn passes_lanes<'a>(&'a self, candidates: &'a [&str]) -> impl Iterator<Item = bool> + 'a {
+        let passwords = std::array::from_fn(|lane| {
+            candidates
+                .get(lane)
+                .map_or(&[][..], |candidate| candidate.as_bytes())
+        });
+        let blocks =
+            HmacLanes::new(&passwords).pbkdf2_block(&self.salt, kdf::AES_ITERATIONS, self.block);
+        blocks
+            .into_iter()
+            .take(candidates.len())
+            .map(|block| block[self.offset..self.offset + 2] == self.expected)
+    }
+}
+
+/// What testing one wrong candidate against `encryption` costs, in the terms of the fast check
+/// and full confirmation.
+pub fn cost_model(encryption: Encryption) -> String {
+    match encryption {
+        Encryption::Aes { .. } => format!(
+            "one {}-iteration PBKDF2-HMAC-SHA1 block per candidate, the one holding the password \
+             verifier; about 1 in 65536 wrong candidates match it and have the entry decrypted",
+            kdf::AES_ITERATIONS
+        ),
+        Encryption::ZipCrypto => "the password's keys set up and run over the 12-byte \
+                                  ZipCrypto header per candidate; about 1 in 256 wrong \
+                                  candidates match its check byte and have the entry decrypted \
+                                  and checked against its CRC"
+            .to_owned(),
+        Encryption::None | Encryption::Strong => "no password check".to_owned(),
+    }
+}
+
+fn password_matches(entry_archive: &[u8], password: &str) -> Result<bool> {
+    let cursor = std::io::Cursor::new(entry_archive);
+    let mut archive = ZipArchive::new(cursor)?;
+
+    let mut file = match archive.by_index_decrypt(0, password.as_bytes()) {
+        Ok(file) => file,
+        Err(ZipError::InvalidPassword) => return Ok(false),
+        Err(error) => return Err(error.into()),
+    };
+
+    // A wrong key that slips past the check byte or verifier surfaces as a corrupt stream, a
+    // truncated one, or a CRC or authentication mismatch, so reading to the end is what
+    // actually confirms the password. Anything else is an error in its own right.
+    match std::io::copy(&mut file, &mut std::io::sink()) {
+        Ok(_) => Ok(true),
+        Err(error)
+            if matches!(
+                error.kind(),
+                io::ErrorKind::InvalidData
+                    | io::ErrorKind::InvalidInput
+                    | io::ErrorKind::UnexpectedEof
+            ) =>
+        {
+            Ok(false)
+        }
+        Err(error) => Err(error.into()),
+    }
+}
+
+/// Whether a failed confirmation may succeed when tried again. The entry is in memory, so only
+/// interrupted or timed-out reads and running out of memory qualify.
+pub fn is_transient(error: &anyhow::Error) -> bool {
+    let io = match error.downcast_ref::<ZipError>() {
+        Some(ZipError::Io(io)) => Some(io),
+        _ => error.downcast_ref::<io::Error>(),
+    };
+    io.is_some_and(|io| {
+        matches!(
+            io.kind(),
+            io::ErrorKind::Interrupted
+                | io::ErrorKind::WouldBlock
+                | io::ErrorKind::TimedOut
+                | io::ErrorKind::OutOfMemory
+        )
+    })
+}
@@ -0,0 +1,216 @@
+use std::{
+    ffi::OsString,
+    fs::File,
+    io::{self, BufRead, Write},
+    path::PathBuf,
+};
+
+use anyhow::{Context, Result};
+
+use crate::archive::{self, Encryption};
+use crate::cpus;
+use crate::generate::{self, BruteForce, Generator, Hints};
+use crate::paths;
+use crate::verify::Backend;
+use crate::{strength, tune, verify::Verifier};
+
+#[derive(clap::Args, Debug)]
+pub struct WizardArgs {
+    /// The password-protected ZIP file to recover.
+    zip: PathBuf,
+}
+
+/// Inspects the archive, asks what the user remembers, and returns the arguments of the attack
+/// it settled on, or `None` if the user declined to start it.
+pub fn plan(args: &WizardArgs) -> Result<Option<Vec<OsString>>> {
+    let zip = &args.zip;
+    let mut file = File::open(paths::extended(zip))
+        .with_context(|| format!("Failed to open archive: {}", zip.display()))?;
+    let entries = archive::read_central_directory(&mut file)?.entries;
+    let encrypted = entries
+        .iter()
+        .filter(|entry| entry.encryption != Encryption::None)
+        .count();
+    println!(
+        "{} has {} entries, {encrypted} of them encrypted",
+        zip.display(),
+        entries.len()
+    );
+    let index = archive::select_target(&entries, None, &[])?;
+    let entry = &entries[index];
+    entry.ensure_verifiable()?;
+    println!(
+        "Testing passwords against {} ({}, {})",
+        entry.name, entry.compression, entry.encryption
+    );
+    let aes = matches!(entry.encryption, Encryption::Aes { .. });
+    let backend = if aes { Backend::Simd } else { Backend::Cpu };
+    if aes {
+        println!(
+            "AES derives a key from every candidate, so each one costs thousands of times more \
+             than with ZipCrypto; the more you remember, the better."
+        );
+    }
+    let threads = cpus::available();
+    let entry_archive = archive::extract_entry_archive(&mut file, entry)?;
+    let rate = tune::probe_rate(&Verifier::new(entry_archive, entry, backend)?) * threads as f64;
+    println!(
+        "This machine tests about {rate:.0} candidates/s with {threads} thread{}.",
+        if threads == 1 { "" } else { "s" }
+    );
+    println!();
+
+    let mut argv: Vec<OsString> = vec!["--zip".into(), zip.into()];
+    if aes {
+        argv.extend(["--backend".into(), "simd".into()]);
+    }
+    let hints = Hints {
+        prefix: ask("Do you remember how it starts? (blank if not)")?,
+        suffix: ask("How it ends? (blank if not)")?,
+        contains: ask("Any other part of it? (blank if not)")?,
+    };
+    for (flag, fragment) in [
+        ("--known-prefix", &hints.prefix),
+        ("--known-suffix", &hints.suffix),
+        ("--known-contains", &hints.contains),
+    ] {
+        if !fragment.is_empty() {
+            argv.extend([flag.into(), fragment.into()]);
+        }
+    }
+
+    let mut wordlists = 0;
+    loop {
+        let question = if wordlists == 0 {
+            "Wordlist to try first (a file or @NAME; blank for none):"
+        } else {
+            "Another wordlist (blank to continue):"
+        };
+        let answer = ask(question)?;
+        if answer.is_empty() {
+            break;
+        }
+        argv.extend(["--wordlist".into(), answer.into()]);
+        wordlists += 1;
+    }
+
+    if wordlists == 0
+        || yes(
+            &ask("Brute force once the wordlists are exhausted? [Y/n]")?,
+            true,
+        )
+    {
+        let (spec, min_len, max_len) = loop {
+            let (min_len, max_len) = loop {
+                let answer = ask(
+                    "How long is the whole password? (e.g. 8 or 6-10; blank if you don't remember)",
+                )?;
+                if answer.is_empty() {
+                    break (hints.len().max(1), hints.len() + 6);
+                }
+                match generate::parse_length(&answer) {
+                    Ok(lengths) => break lengths,
+                    Err(error) => println!("  {error}"),
+                }
+            };
+            let spec = loop {
+                let answer = ask(
+                    "Which kinds of characters could it use? (any of lower, upper, digits, \
+                     symbols; blank for lower and digits)",
+                )?;
+                match classes(&answer) {
+                    Ok(spec) => break spec,
+                    Err(error) => println!("  {error}"),
+                }
+            };
+            let charset = generate::parse_charset(&spec).map_err(anyhow::Error::msg)?;
+            match BruteForce::new(charset.chars, min_len, max_len, hints.clone()) {
+                Ok(brute_force) => {
+                    let keyspace = Generator::BruteForce(brute_force).len();
+                    println!(
+                        "Brute force covers {keyspace} candidates, about {} at worst",
+                        strength::describe(keyspace as f64 / rate)
+                    );
+                    break (spec, min_len, max_len);
+                }
+                Err(error) => println!("  {error:#}"),
+            }
+        };
+        argv.extend([
+            "--charset".into(),
+            spec.into(),
+            "--length".into(),
+            if min_len == max_len {
+                min_len.to_string().into()
+            } else {
+                format!("{min_len}-{max_len}").into()
+            },
+        ]);
+    }
+
+    println!();
+    let command: Vec<String> = argv
+        .iter()
+        .map(|arg| quote(&arg.to_string_lossy()))
+        .collect();
+    println!("Equivalent command: unzippoo {}", command.join(" "));
+    if !yes(&ask("Start the attack? [Y/n]")?, true) {
+        return Ok(None);
+    }
+    Ok(Some(argv))
+}
+
+/// Turns "lower, digits" into a charset spec such as `?l?d`.
+fn classes(answer: &str) -> Result<String, String> {
+    if answer.is_empty() {
+        return Ok("?l?d".to_owned());
+    }
+    let mut spec = String::new();
+    for word in answer
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|word| !word.is_empty() && *word != "and")
+    {
+        let class = match word.to_ascii_lowercase().as_str() {
+            "lower" | "lowercase" => "?l",
+            "upper" | "uppercase" => "?u",
+            "digit" | "digits" | "numbers" => "?d",
+            "symbol" | "symbols" => "?s",
+            "all" | "any" => "?a",
+            _ => return Err(format!("unknown kind of character \"{word}\"")),
+        };
+        spec.push_str(class);
+    }
+    Ok(spec)
+}
+
+/// Prints `question` and reads one line of the answer; end of input counts as a blank answer.
+fn ask(question: &str) -> Result<String> {
+    print!("{question} ");
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    if answer.is_empty() {
+        println!();
+    }
+    Ok(answer.trim().to_owned())
+}
+
+fn yes(answer: &str, default: bool) -> bool {
+    match answer.to_ascii_lowercase().as_str() {
+        "" => default,
+        answer => answer.starts_with('y'),
+    }
+}
+
+/// Quotes an argument for display if the shell would split or expand it.
+fn quote(arg: &str) -> String {
+    if !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./@:=".contains(c))
+    {
+        arg.to_owned()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
@@ -0,0 +1,1144 @@
+use std::{
+    cmp::Reverse,
+    fmt,
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Read, Write},
+    path::Path,
+    sync::{
+        Arc,
+        mpsc::{self, Receiver, SyncSender, TryRecvError, TrySendError},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result, anyhow, bail};
+use memmap2::Mmap;
+
+use crate::cpus;
+use crate::hugepages;
+use crate::{paths, spool};
+
+/// Size of each buffer passed from the reader thread to the line splitter.
+const BLOCK_SIZE: usize = 256 * 1024;
+/// Buffers in flight between the two threads; decoding runs this far ahead of splitting.
+const RING_SLOTS: usize = 8;
+/// Mapped wordlists smaller than this per thread are indexed with fewer threads.
+const MIN_SCAN_RANGE: usize = 1 << 20;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Bzip2,
+    Xz,
+    Zstd,
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Compression::None => "uncompressed",
+            Compression::Gzip => "gzip",
+            Compression::Bzip2 => "bzip2",
+            Compression::Xz => "xz",
+            Compression::Zstd => "zstd",
+        })
+    }
+}
+
+/// How the candidates are encoded once any compression is undone.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LineEnd {
+    #[default]
+    Lf,
+    CrLf,
+    /// Classic Mac OS line endings, a bare carriage return.
+    Cr,
+}
+
+/// What the start of a wordlist says about its text: tools on Windows tend to write UTF-16LE
+/// with a byte order mark and CRLF line endings, all of which would otherwise end up in the
+/// candidates or keep them from being read at all.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Format {
+    pub encoding: Encoding,
+    pub bom: bool,
+    pub line_end: LineEnd,
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self.encoding {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Utf16Le => "UTF-16LE",
+            Encoding::Utf16Be => "UTF-16BE",
+        })?;
+        if self.bom {
+            f.write_str(" with BOM")?;
+        }
+        f.write_str(match self.line_end {
+            LineEnd::Lf => ", LF",
+            LineEnd::CrLf => ", CRLF",
+            LineEnd::Cr => ", CR",
+        })
+    }
+}
+
+impl Format {
+    /// The encoding from a byte order mark, or failing that from where the NUL bytes are,
+    /// which UTF-8 text has none of but UTF-16 has in every other byte for ASCII.
+    fn sniff(head: &[u8]) -> Self {
+        let (encoding, bom) = if head.starts_with(&[0xef, 0xbb, 0xbf]) {
+            (Encoding::Utf8, true)
+        } else if head.starts_with(&[0xff, 0xfe]) {
+            (Encoding::Utf16Le, true)
+        } else if head.starts_with(&[0xfe, 0xff]) {
+            (Encoding::Utf16Be, true)
+        } else {
+            let pairs = &head[..head.len().min(1024) & !1];
+            let zeros = |offset| {
+                pairs
+                    .iter()
+                    .skip(offset)
+                    .step_by(2)
+                    .filter(|&&b| b == 0)
+                    .count()
+            };
+            let (even, odd) = (zeros(0), zeros(1));
+            let units = pairs.len() / 2;
+            if odd * 2 >= units.max(1) && even == 0 {
+                (Encoding::Utf16Le, false)
+            } else if even * 2 >= units.max(1) && odd == 0 {
+                (Encoding::Utf16Be, false)
+            } else {
+                (Encoding::Utf8, false)
+            }
+        };
+        Self {
+            encoding,
+            bom,
+            line_end: LineEnd::Lf,
+        }
+    }
+
+    fn bom_len(self) -> usize {
+        match (self.bom, self.encoding) {
+            (false, _) => 0,
+            (true, Encoding::Utf8) => 3,
+            (true, Encoding::Utf16Le | Encoding::Utf16Be) => 2,
+        }
+    }
+}
+
+impl LineEnd {
+    /// Line endings from the first block of UTF-8 text. Lines are trimmed, so CRLF splits on
+    /// `\n` like LF; only a list without any `\n` splits on `\r`.
+    fn sniff(text: &[u8]) -> Self {
+        match memchr::memchr(b'\n', text) {
+            Some(at) if at > 0 && text[at - 1] == b'\r' => LineEnd::CrLf,
+            Some(_) => LineEnd::Lf,
+            None if memchr::memchr(b'\r', text).is_some() => LineEnd::Cr,
+            None => LineEnd::Lf,
+        }
+    }
+
+    fn terminator(self) -> u8 {
+        if self == LineEnd::Cr { b'\r' } else { b'\n' }
+    }
+}
+
+/// `--lines`: 1-based lines of a wordlist file, both ends included.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LineRange {
+    pub first: u64,
+    /// `u64::MAX` for a range that runs to the end of the file.
+    pub last: u64,
+}
+
+impl LineRange {
+    pub fn contains(self, line: u64) -> bool {
+        (self.first..=self.last).contains(&line)
+    }
+}
+
+impl fmt::Display for LineRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.last {
+            u64::MAX => write!(f, "{}-", self.first),
+            last if last == self.first => write!(f, "{last}"),
+            last => write!(f, "{}-{last}", self.first),
+        }
+    }
+}
+
+/// Parses `N`, `FIRST-LAST`, or `FIRST-` for everything from `FIRST` on.
+pub fn parse_lines(value: &str) -> Result<LineRange, String> {
+    let invalid = || format!("invalid line range \"{value}\" (expected e.g. 1000001-2000000)");
+    let (first, last) = match value.split_once('-') {
+        Some((first, "")) => (first.trim(), None),
+        Some((first, last)) => (first.trim(), Some(last.trim())),
+        None => (value.trim(), Some(value.trim())),
+    };
+    let first: u64 = first.parse().map_err(|_| invalid())?;
+    let last = match last {
+        Some(last) => last.parse().map_err(|_| invalid())?,
+        None => u64::MAX,
+    };
+    if first == 0 || first > last {
+        return Err(invalid());
+    }
+    Ok(LineRange { first, last })
+}
+
+/// How the load went; shown with `--verbose`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LoadStats {
+    pub decoded_bytes: u64,
+    pub lines: u64,
+    /// Times the reader found the ring full and had to wait for the splitter.
+    pub producer_stalls: u64,
+    pub producer_stalled: Duration,
+    /// Times the splitter found the ring empty and had to wait for the reader.
+    pub consumer_stalls: u64,
+    pub consumer_stalled: Duration,
+    pub elapsed: Duration,
+}
+
+pub struct Wordlist {
+    pub words: Words,
+    pub compression: Compression,
+    pub format: Format,
+    pub stats: LoadStats,
+}
+
+/// Candidates in line order, sliced out of one contiguous buffer so no line gets its own
+/// allocation. Clones share that buffer and copy only the line index.
+#[derive(Clone)]
+pub struct Words {
+    storage: Arc<Storage>,
+    /// Start offset and length of each trimmed, non-empty line.
+    lines: Vec<(u64, u32)>,
+    /// Arenas drop blank lines, so they record `(index, skipped)` wherever the number of blank
+    /// lines before a candidate grows; mapped files count newlines instead.
+    blank_runs: Vec<(u64, u64)>,
+    /// The byte mapped files end their lines with.
+    terminator: u8,
+    /// With `--counts`, arenas reordered by count record each candidate's line here, and
+    /// `weights` holds the running total of the counts in candidate order.
+    numbers: Vec<u64>,
+    weights: Vec<u64>,
+}
+
+enum Storage {
+    /// Decoded bytes of a compressed list, candidates packed back to back.
+    Arena(Vec<u8>),
+    /// The uncompressed file itself.
+    Mapped(Mmap),
+    /// An arena that outgrew `--memory-limit`, mapped from the temp file it spilled to.
+    Spilled(Mmap),
+}
+
+/// Decoded candidates being packed back to back: in memory, until they would pass
+/// `--memory-limit`, then in an anonymous temp file.
+enum Arena {
+    Memory(Vec<u8>),
+    Spilled { file: BufWriter<File>, len: u64 },
+}
+
+impl Arena {
+    fn len(&self) -> u64 {
+        match self {
+            Arena::Memory(bytes) => bytes.len() as u64,
+            Arena::Spilled { len, .. } => *len,
+        }
+    }
+
+    fn push(&mut self, bytes: &[u8]) -> Result<()> {
+        if let Arena::Memory(packed) = self
+            && (packed.len() + bytes.len()) as u64 > spool::memory_limit()
+        {
+            let mut file = BufWriter::new(spool::temp_file()?);
+            file.write_all(packed)?;
+            *self = Arena::Spilled {
+                file,
+                len: packed.len() as u64,
+            };
+        }
+        match self {
+            Arena::Memory(packed) => packed.extend_from_slice(bytes),
+            Arena::Spilled { file, len } => {
+                file.write_all(bytes)
+                    .context("Failed to spill the wordlist to disk")?;
+                *len += bytes.len() as u64;
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Storage> {
+        Ok(match self {
+            Arena::Memory(packed) => Storage::Arena(packed),
+            Arena::Spilled { file, .. } => {
+                let file = file.into_inner().map_err(io::IntoInnerError::into_error)?;
+                // SAFETY: nothing else can reach the unnamed file to change it underneath.
+                Storage::Spilled(unsafe { Mmap::map(&file)? })
+            }
+        })
+    }
+}
+
+impl Words {
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    pub fn is_mapped(&self) -> bool {
+        matches!(*self.storage, Storage::Mapped(_))
+    }
+
+    pub fn is_spilled(&self) -> bool {
+        matches!(*self.storage, Storage::Spilled(_))
+    }
+
+    /// The 1-based line of the file that candidate `index` came from.
+    pub fn line_number(&self, index: usize) -> u64 {
+        match &*self.storage {
+            Storage::Mapped(map) => {
+                let start = self.lines[index].0 as usize;
+                memchr::memchr_iter(self.terminator, &map[..start]).count() as u64 + 1
+            }
+            Storage::Arena(_) | Storage::Spilled(_) if !self.numbers.is_empty() => {
+                self.numbers[index]
+            }
+            Storage::Arena(_) | Storage::Spilled(_) => {
+                let index = index as u64;
+                let run = self.blank_runs.partition_point(|&(at, _)| at <= index);
+                let skipped = run.checked_sub(1).map_or(0, |run| self.blank_runs[run].1);
+                index + 1 + skipped
+            }
+        }
+    }
+
+    /// Drops the candidates `keep` rejects, renumbering the rest while their line numbers still
+    /// point into the original file.
+    pub fn retain(&mut self, mut keep: impl FnMut(&str) -> bool) {
+        let kept: Vec<usize> = (0..self.len())
+            .filter(|&index| keep(self.get(index)))
+            .collect();
+        self.keep(kept);
+    }
+
+    /// Keeps only the candidates on the lines `range` covers; the lines must still be in file
+    /// order.
+    pub fn keep_lines(&mut self, range: LineRange) {
+        let kept = match &*self.storage {
+            Storage::Mapped(map) => {
+                // Counting newlines once across the file, not from the top for every line.
+                let (mut line, mut counted) = (1, 0);
+                let mut kept = Vec::new();
+                for (index, &(start, _)) in self.lines.iter().enumerate() {
+                    let start = start as usize;
+                    line +=
+                        memchr::memchr_iter(self.terminator, &map[counted..start]).count() as u64;
+                    counted = start;
+                    if line > range.last {
+                        break;
+                    }
+                    if line >= range.first {
+                        kept.push(index);
+                    }
+                }
+                kept
+            }
+            Storage::Arena(_) | Storage::Spilled(_) => (0..self.len())
+                .filter(|&index| range.contains(self.line_number(index)))
+                .collect(),
+        };
+        self.keep(kept);
+    }
+
+    /// `--counts`: reads each line as `count word` (like `uniq -c` prints) or `word<TAB>count`,
+    /// keeps only the word, and puts the words with the highest counts first. Ties keep their
+    /// order in the file.
+    pub fn sort_by_counts(&mut self) -> Result<()> {
+        let mut counted = Vec::with_capacity(self.len());
+        for index in 0..self.len() {
+            let line = self.get(index);
+            let start = self.lines[index].0;
+            let parsed = match line.rsplit_once('\t') {
+                Some((word, count)) if count.trim().parse::<u64>().is_ok() => count
+                    .trim()
+                    .parse()
+                    .ok()
+                    .map(|count| (0, word.len(), count)),
+                _ => line.split_once(' ').and_then(|(count, word)| {
+                    let count = count.parse().ok()?;
+                    Some((line.len() - word.len(), word.len(), count))
+                }),
+            };
+            let Some((skip, len, count)) = parsed.filter(|&(_, len, _)| len > 0) else {
+                bail!(
+                    "line {} is not `count word` or `word<TAB>count`",
+                    self.line_number(index)
+                );
+            };
+            counted.push(((start + skip as u64, len as u32), count));
+        }
+        if !self.is_mapped() {
+            self.numbers = (0..self.len())
+                .map(|index| self.line_number(index))
+                .collect();
+        }
+        let mut order: Vec<usize> = (0..counted.len()).collect();
+        order.sort_by_key(|&index| Reverse(counted[index].1));
+        let mut total = 0u64;
+        self.weights = order
+            .iter()
+            .map(|&index| {
+                total = total.saturating_add(counted[index].1);
+                total
+            })
+            .collect();
+        for (index, &(range, _)) in counted.iter().enumerate() {
+            self.lines[index] = range;
+        }
+        self.keep_in_order(order);
+        Ok(())
+    }
+
+    /// The share of the counts the first `tried` candidates carry, with `--counts`.
+    pub fn coverage(&self, tried: u64) -> Option<f64> {
+        let total = *self.weights.last()?;
+        let covered = tried.checked_sub(1).map_or(0, |last| {
+            self.weights[(last as usize).min(self.weights.len() - 1)]
+        });
+        Some(covered as f64 / total.max(1) as f64)
+    }
+
+    fn keep(&mut self, kept: Vec<usize>) {
+        if !self.weights.is_empty() {
+            let mut total = 0u64;
+            self.weights = kept
+                .iter()
+                .map(|&index| {
+                    let count =
+                        self.weights[index] - index.checked_sub(1).map_or(0, |i| self.weights[i]);
+                    total += count;
+                    total
+                })
+                .collect();
+        }
+        self.keep_in_order(kept);
+    }
+
+    /// Narrows the candidates to `kept`, in that order, leaving `weights` to the caller.
+    fn keep_in_order(&mut self, kept: Vec<usize>) {
+        if !self.numbers.is_empty() {
+            self.numbers = kept.iter().map(|&index| self.numbers[index]).collect();
+        } else if !self.is_mapped() {
+            let mut blank_runs = Vec::new();
+            for (index, &old) in kept.iter().enumerate() {
+                let skipped = self.line_number(old) - 1 - index as u64;
+                if skipped != blank_runs.last().map_or(0, |&(_, skipped)| skipped) {
+                    blank_runs.push((index as u64, skipped));
+                }
+            }
+            self.blank_runs = blank_runs;
+        }
+        self.lines = kept.into_iter().map(|index| self.lines[index]).collect();
+    }
+
+    /// With `--huge-pages`, moves the line index and decoded candidates into memory backed by
+    /// huge pages; a mapped or spilled file stays in the page cache.
+    fn use_huge_pages(&mut self) {
+        if !hugepages::is_enabled() {
+            return;
+        }
+        self.lines = hugepages::relocate(&self.lines);
+        if let Some(Storage::Arena(arena)) = Arc::get_mut(&mut self.storage) {
+            *arena = hugepages::relocate(arena);
+        }
+    }
+
+    pub fn get(&self, index: usize) -> &str {
+        let bytes = match &*self.storage {
+            Storage::Arena(arena) => &arena[..],
+            Storage::Mapped(map) | Storage::Spilled(map) => &map[..],
+        };
+        let (start, len) = self.lines[index];
+        let bytes = &bytes[start as usize..start as usize + len as usize];
+        // Every recorded range was validated as UTF-8 when the index was built.
+        unsafe { std::str::from_utf8_unchecked(bytes) }
+    }
+}
+
+/// Reads one candidate per non-empty line. Uncompressed UTF-8 files are memory-mapped and
+/// indexed in parallel; gzip, bzip2, xz, and zstd lists are decompressed, and UTF-16 lists
+/// converted, on a dedicated I/O thread. A byte order mark is skipped.
+pub fn load(path: &Path) -> Result<Wordlist> {
+    let started_at = Instant::now();
+    let file = File::open(paths::extended(path))
+        .with_context(|| format!("Failed to open wordlist: {}", path.display()))?;
+    let mut file = BufReader::new(file);
+    let compression = detect(&mut file)
+        .with_context(|| format!("Failed to read wordlist: {}", path.display()))?;
+    if compression == Compression::None
+        && Format::sniff(file.fill_buf()?).encoding == Encoding::Utf8
+        && file.get_ref().metadata()?.is_file()
+    {
+        let (mut words, format, stats) = map_lines(file.into_inner())
+            .with_context(|| format!("Failed to read wordlist: {}", path.display()))?;
+        words.use_huge_pages();
+        return Ok(Wordlist {
+            words,
+            compression,
+            format,
+            stats: LoadStats {
+                elapsed: started_at.elapsed(),
+                ..stats
+            },
+        });
+    }
+    let mut reader = BufReader::new(decoder(file, compression)?);
+    let format = Format::sniff(
+        reader
+            .fill_buf()
+            .with_context(|| format!("Failed to read wordlist: {}", path.display()))?,
+    );
+    reader.consume(format.bom_len());
+    let reader: Box<dyn Read + Send> = match format.encoding {
+        Encoding::Utf8 => Box::new(reader),
+        Encoding::Utf16Le => Box::new(Utf16::new(reader, u16::from_le_bytes)),
+        Encoding::Utf16Be => Box::new(Utf16::new(reader, u16::from_be_bytes)),
+    };
+
+    let (filled_tx, filled_rx) = mpsc::sync_channel::<Vec<u8>>(RING_SLOTS);
+    let (empty_tx, empty_rx) = mpsc::sync_channel::<Vec<u8>>(RING_SLOTS);
+    for _ in 0..RING_SLOTS {
+        empty_tx
+            .send(Vec::with_capacity(BLOCK_SIZE))
+            .expect("ring receiver is alive");
+    }
+    let producer = thread::spawn(move || produce(reader, &filled_tx, &empty_rx));
+
+    let mut stats = LoadStats::default();
+    let consumed = consume(&filled_rx, &empty_tx, &mut stats);
+    let (consumed, line_end) = match consumed {
+        Ok((words, line_end)) => (Ok(words), line_end),
+        Err(error) => (Err(error), LineEnd::Lf),
+    };
+    // Unblock the reader if splitting stopped early, then collect its side of the story.
+    drop(filled_rx);
+    drop(empty_tx);
+    let produced = producer.join().expect("wordlist reader panicked");
+    let (producer_stats, read_result) = produced;
+    stats.producer_stalls = producer_stats.producer_stalls;
+    stats.producer_stalled = producer_stats.producer_stalled;
+    stats.decoded_bytes = producer_stats.decoded_bytes;
+    stats.elapsed = started_at.elapsed();
+
+    read_result.with_context(|| format!("Failed to read wordlist: {}", path.display()))?;
+    let mut words =
+        consumed.with_context(|| format!("Failed to read wordlist: {}", path.display()))?;
+    words.use_huge_pages();
+    Ok(Wordlist {
+        words,
+        compression,
+        format: Format { line_end, ..format },
+        stats,
+    })
+}
+
+/// Maps `file` and records where every candidate lies, splitting the scan across threads at
+/// line boundaries.
+fn map_lines(file: File) -> Result<(Words, Format, LoadStats)> {
+    // SAFETY: the mapping is read-only; a wordlist truncated underneath a running attack is
+    // outside what we can guard against, as with any mmap-based reader.
+    let map = unsafe { Mmap::map(&file)? };
+    let bytes = &map[..];
+    let format = Format {
+        line_end: LineEnd::sniff(&bytes[..bytes.len().min(BLOCK_SIZE)]),
+        ..Format::sniff(bytes)
+    };
+    let terminator = format.line_end.terminator();
+
+    let parts = cpus::available().clamp(1, (bytes.len() / MIN_SCAN_RANGE).max(1));
+    let mut bounds = vec![format.bom_len()];
+    for part in 1..parts {
+        let guess = (bytes.len() * part / parts).max(*bounds.last().unwrap());
+        let boundary =
+            memchr::memchr(terminator, &bytes[guess..]).map_or(bytes.len(), |at| guess + at + 1);
+        bounds.push(boundary);
+    }
+    bounds.push(bytes.len());
+
+    let scanned: Vec<Result<RangeScan, ScanError>> = thread::scope(|scope| {
+        let scans: Vec<_> = bounds
+            .windows(2)
+            .map(|range| scope.spawn(move || scan_range(bytes, range[0], range[1], terminator)))
+            .collect();
+        scans
+            .into_iter()
+            .map(|scan| scan.join().expect("wordlist scan panicked"))
+            .collect()
+    });
+
+    let mut lines = Vec::new();
+    let mut line_count = 0u64;
+    for scan in scanned {
+        match scan {
+            Ok(scan) => {
+                lines.extend(scan.lines);
+                line_count += scan.count;
+            }
+            Err((line, reason)) => bail!("line {} {reason}", line_count + line),
+        }
+    }
+    let stats = LoadStats {
+        decoded_bytes: bytes.len() as u64,
+        lines: line_count,
+        ..LoadStats::default()
+    };
+    let words = Words {
+        storage: Arc::new(Storage::Mapped(map)),
+        lines,
+        blank_runs: Vec::new(),
+        terminator,
+        numbers: Vec::new(),
+        weights: Vec::new(),
+    };
+    Ok((words, format, stats))
+}
+
+struct RangeScan {
+    lines: Vec<(u64, u32)>,
+    /// Lines in the range, including blank ones.
+    count: u64,
+}
+
+/// A range-local line number and what is wrong with that line.
+type ScanError = (u64, &'static str);
+
+/// Indexes the lines in `bytes[start..end]`.
+fn scan_range(
+    bytes: &[u8],
+    start: usize,
+    end: usize,
+    terminator: u8,
+) -> Result<RangeScan, ScanError> {
+    let mut ranges = Vec::new();
+    let mut count = 0u64;
+    let mut line_start = start;
+    while line_start < end {
+        let line_end =
+            memchr::memchr(terminator, &bytes[line_start..end]).map_or(end, |at| line_start + at);
+        count += 1;
+        let line = std::str::from_utf8(&bytes[line_start..line_end])
+            .map_err(|_| (count, "is not valid UTF-8"))?;
+        let trimmed = line.trim();
+        if !trimmed.is_empty() {
+            let offset = line_start + (trimmed.as_ptr() as usize - line.as_ptr() as usize);
+            let len = u32::try_from(trimmed.len()).map_err(|_| (count, "is too long"))?;
+            ranges.push((offset as u64, len));
+        }
+        line_start = line_end + 1;
+    }
+    Ok(RangeScan {
+        lines: ranges,
+        count,
+    })
+}
+
+fn detect(file: &mut BufReader<File>) -> io::Result<Compression> {
+    let head = file.fill_buf()?;
+    Ok(if head.starts_with(&[0x1f, 0x8b]) {
+        Compression::Gzip
+    } else if head.starts_with(b"BZh") {
+        Compression::Bzip2
+    } else if head.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+        Compression::Xz
+    } else if head.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+        Compression::Zstd
+    } else {
+        Compression::None
+    })
+}
+
+fn decoder(file: BufReader<File>, compression: Compression) -> Result<Box<dyn Read + Send>> {
+    Ok(match compression {
+        Compression::None => Box::new(file),
+        Compression::Gzip => Box::new(flate2::read::MultiGzDecoder::new(file)),
+        #[cfg(feature = "bzip2")]
+        Compression::Bzip2 => Box::new(bzip2::read::MultiBzDecoder::new(file)),
+        #[cfg(not(feature = "bzip2"))]
+        Compression::Bzip2 => return Err(missing_feature(compression, "bzip2")),
+        #[cfg(feature = "xz")]
+        Compression::Xz => Box::new(lzma_rust2::XzReader::new(file, true)),
+        #[cfg(not(feature = "xz"))]
+        Compression::Xz => return Err(missing_feature(compression, "xz")),
+        #[cfg(feature = "zstd")]
+        Compression::Zstd => Box::new(zstd::stream::read::Decoder::new(file)?),
+        #[cfg(not(feature = "zstd"))]
+        Compression::Zstd => return Err(missing_feature(compression, "zstd")),
+    })
+}
+
+#[cfg(not(all(feature = "bzip2", feature = "xz", feature = "zstd")))]
+fn missing_feature(compression: Compression, feature: &str) -> anyhow::Error {
+    anyhow!("Wordlist is {compression}-compressed, but this build lacks the `{feature}` feature")
+}
+
+/// Fills recycled buffers from `reader` until end of input or the splitter goes away.
+fn produce(
+    mut reader: Box<dyn Read + Send>,
+    filled: &SyncSender<Vec<u8>>,
+    empty: &Receiver<Vec<u8>>,
+) -> (LoadStats, io::Result<()>) {
+    let mut stats = LoadStats::default();
+    let result = (|| {
+        loop {
+            let Ok(mut buf) = empty.recv() else {
+                return Ok(());
+            };
+            buf.clear();
+            let read = reader
+                .by_ref()
+                .take(BLOCK_SIZE as u64)
+                .read_to_end(&mut buf)?;
+            if read == 0 {
+                return Ok(());
+            }
+            stats.decoded_bytes += read as u64;
+            match filled.try_send(buf) {
+                Ok(()) => {}
+                Err(TrySendError::Full(buf)) => {
+                    let waited = Instant::now();
+                    stats.producer_stalls += 1;
+                    if filled.send(buf).is_err() {
+                        return Ok(());
+                    }
+                    stats.producer_stalled += waited.elapsed();
+                }
+                Err(TrySendError::Disconnected(_)) => return Ok(()),
+            }
+        }
+    })();
+    (stats, result)
+}
+
+/// Splits buffers into trimmed, non-empty lines, handing each buffer back once consumed. The
+/// line endings are told from the first buffer.
+fn consume(
+    filled: &Receiver<Vec<u8>>,
+    empty: &SyncSender<Vec<u8>>,
+    stats: &mut LoadStats,
+) -> Result<(Words, LineEnd)> {
+    let mut line_end = None;
+    let mut arena = Arena::Memory(Vec::new());
+    let mut lines = Lines::default();
+    let mut partial: Vec<u8> = Vec::new();
+    loop {
+        let buf = match filled.try_recv() {
+            Ok(buf) => buf,
+            Err(TryRecvError::Empty) => {
+                let waited = Instant::now();
+                match filled.recv() {
+                    Ok(buf) => {
+                        stats.consumer_stalls += 1;
+                        stats.consumer_stalled += waited.elapsed();
+                        buf
+                    }
+                    Err(_) => break,
+                }
+            }
+            Err(TryRecvError::Disconnected) => break,
+        };
+
+        let terminator = line_end
+            .get_or_insert_with(|| LineEnd::sniff(&buf))
+            .terminator();
+        let mut rest = &buf[..];
+        while let Some(newline) = memchr::memchr(terminator, rest) {
+            if partial.is_empty() {
+                lines.push(&rest[..newline], &mut arena, stats)?;
+            } else {
+                partial.extend_from_slice(&rest[..newline]);
+                lines.push(&partial, &mut arena, stats)?;
+                partial.clear();
+            }
+            rest = &rest[newline + 1..];
+        }
+        partial.extend_from_slice(rest);
+        // The reader may already be gone at the very end; the buffer is simply dropped then.
+        let _ = empty.send(buf);
+    }
+    if !partial.is_empty() {
+        lines.push(&partial, &mut arena, stats)?;
+    }
+    let words = Words {
+        storage: Arc::new(arena.finish()?),
+        lines: lines.ranges,
+        blank_runs: lines.blank_runs,
+        terminator: b'\n',
+        numbers: Vec::new(),
+        weights: Vec::new(),
+    };
+    Ok((words, line_end.unwrap_or_default()))
+}
+
+/// Line ranges being collected into an arena, with the blank lines they skipped.
+#[derive(Default)]
+struct Lines {
+    ranges: Vec<(u64, u32)>,
+    blank_runs: Vec<(u64, u64)>,
+}
+
+impl Lines {
+    fn push(&mut self, line: &[u8], arena: &mut Arena, stats: &mut LoadStats) -> Result<()> {
+        stats.lines += 1;
+        let line = std::str::from_utf8(line)
+            .map_err(|_| anyhow!("line {} is not valid UTF-8", stats.lines))?;
+        let trimmed = line.trim();
+        if !trimmed.is_empty() {
+            let len = u32::try_from(trimmed.len())
+                .map_err(|_| anyhow!("line {} is too long", stats.lines))?;
+            let index = self.ranges.len() as u64;
+            let skipped = stats.lines - 1 - index;
+            if skipped != self.blank_runs.last().map_or(0, |&(_, skipped)| skipped) {
+                self.blank_runs.push((index, skipped));
+            }
+            self.ranges.push((arena.len(), len));
+            arena.push(trimmed.as_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+/// Converts UTF-16 to UTF-8 as it is read, holding back a code unit or surrogate split across
+/// reads until the rest of it arrives.
+struct Utf16<R> {
+    inner: R,
+    unit: fn([u8; 2]) -> u16,
+    raw: Vec<u8>,
+    decoded: Vec<u8>,
+    position: usize,
+}
+
+impl<R: Read> Utf16<R> {
+    fn new(inner: R, unit: fn([u8; 2]) -> u16) -> Self {
+        Self {
+            inner,
+            unit,
+            raw: Vec::new(),
+            decoded: Vec::new(),
+            position: 0,
+        }
+    }
+
+    /// Decodes the next block; leaves `decoded` empty only at the end of input.
+    fn refill(&mut self) -> io::Result<()> {
+        self.decoded.clear();
+        self.position = 0;
+        while self.decoded.is_empty() {
+            let held = self.raw.len();
+            self.raw.resize(held + BLOCK_SIZE, 0);
+            let read = self.inner.read(&mut self.raw[held..])?;
+            self.raw.truncate(held + read);
+            if read == 0 && !self.raw.is_empty() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "the wordlist ends partway through a UTF-16 character",
+                ));
+            }
+            if read == 0 {
+                return Ok(());
+            }
+            let mut units: Vec<u16> = self
+                .raw
+                .chunks_exact(2)
+                .map(|pair| (self.unit)([pair[0], pair[1]]))
+                .collect();
+            if units
+                .last()
+                .is_some_and(|unit| (0xd800..0xdc00).contains(unit))
+            {
+                units.pop();
+            }
+            for c in char::decode_utf16(units.iter().copied()) {
+                let c = c.map_err(|_| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "the wordlist is not valid UTF-16",
+                    )
+                })?;
+                self.decoded
+                    .extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+            }
+            self.raw.drain(..units.len() * 2);
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for Utf16<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.position == self.decoded.len() {
+            self.refill()?;
+        }
+        let read = buf.len().min(self.decoded.len() - self.position);
+        buf[..read].copy_from_slice(&self.decoded[self.position..self.position + read]);
+        self.position += read;
+        Ok(read)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use flate2::{Compression as Level, write::GzEncoder};
+
+    use super::*;
+
+    /// Loads `bytes` as a wordlist file named after `test`.
+    fn load_bytes(test: &str, bytes: &[u8]) -> Result<Wordlist> {
+        let path: PathBuf =
+            std::env::temp_dir().join(format!("unzippoo-wordlist-{}-{test}", std::process::id()));
+        std::fs::write(&path, bytes).unwrap();
+        let loaded = load(&path);
+        std::fs::remove_file(path).unwrap();
+        loaded
+    }
+
+    /// `bytes` gzipped, so the list is decoded into an arena instead of mapped.
+    fn gzipped(bytes: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Level::fast());
+        encoder.write_all(bytes).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn candidates(words: &Words) -> Vec<&str> {
+        (0..words.len()).map(|index| words.get(index)).collect()
+    }
+
+    fn utf16(text: &str, unit: fn(u16) -> [u8; 2]) -> Vec<u8> {
+        text.encode_utf16().flat_map(unit).collect()
+    }
+
+    fn load_error(test: &str, bytes: &[u8]) -> String {
+        match load_bytes(test, bytes) {
+            Ok(_) => panic!("{test} loaded"),
+            Err(error) => format!("{error:#}"),
+        }
+    }
+
+    /// Loads `bytes` both mapped and from an arena, checking the words and format found.
+    fn assert_utf8(test: &str, bytes: &[u8], expected: &[&str], bom: bool, line_end: LineEnd) {
+        for (storage, bytes) in [("mapped", bytes.to_vec()), ("arena", gzipped(bytes))] {
+            let list = load_bytes(&format!("{test}-{storage}"), &bytes).unwrap();
+            assert_eq!(candidates(&list.words), expected, "{test} {storage}");
+            assert_eq!(list.format.encoding, Encoding::Utf8, "{test} {storage}");
+            assert_eq!(list.format.bom, bom, "{test} {storage}");
+            assert_eq!(list.format.line_end, line_end, "{test} {storage}");
+        }
+    }
+
+    #[test]
+    fn sniffs_utf8_line_endings_and_bom() {
+        assert_utf8(
+            "lf",
+            b"alpha\nbeta\n",
+            &["alpha", "beta"],
+            false,
+            LineEnd::Lf,
+        );
+        let bom_crlf = b"\xef\xbb\xbfalpha\r\nbeta\r\n";
+        assert_utf8(
+            "bom-crlf",
+            bom_crlf,
+            &["alpha", "beta"],
+            true,
+            LineEnd::CrLf,
+        );
+        let cr = b"one\rtwo\r\rthree";
+        assert_utf8("cr", cr, &["one", "two", "three"], false, LineEnd::Cr);
+        assert_utf8(
+            "unterminated",
+            b"one\ntwo",
+            &["one", "two"],
+            false,
+            LineEnd::Lf,
+        );
+        let list = load_bytes("cr-lines", cr).unwrap();
+        assert_eq!(list.words.line_number(2), 4);
+    }
+
+    #[test]
+    fn decodes_utf16_with_and_without_bom() {
+        let text = "hunter2\r\nzo\u{eb}\r\n\u{1f511}key";
+        let expected = ["hunter2", "zo\u{eb}", "\u{1f511}key"];
+        let cases = [
+            (
+                "le-bom",
+                Encoding::Utf16Le,
+                true,
+                [&[0xff, 0xfe][..], &utf16(text, u16::to_le_bytes)].concat(),
+            ),
+            (
+                "be-bom",
+                Encoding::Utf16Be,
+                true,
+                [&[0xfe, 0xff][..], &utf16(text, u16::to_be_bytes)].concat(),
+            ),
+            (
+                "le",
+                Encoding::Utf16Le,
+                false,
+                utf16(text, u16::to_le_bytes),
+            ),
+            (
+                "be",
+                Encoding::Utf16Be,
+                false,
+                utf16(text, u16::to_be_bytes),
+            ),
+        ];
+        for (test, encoding, bom, bytes) in cases {
+            let list = load_bytes(test, &bytes).unwrap();
+            assert_eq!(candidates(&list.words), expected, "{test}");
+            assert_eq!(list.format.encoding, encoding, "{test}");
+            assert_eq!(list.format.bom, bom, "{test}");
+            assert_eq!(list.format.line_end, LineEnd::CrLf, "{test}");
+        }
+        let list = load_bytes("le-cr", &utf16("a\rb", u16::to_le_bytes)).unwrap();
+        assert_eq!(candidates(&list.words), ["a", "b"]);
+        assert_eq!(list.format.line_end, LineEnd::Cr);
+    }
+
+    #[test]
+    fn rejects_broken_utf16() {
+        let mut lone_low = [0xff, 0xfe].to_vec();
+        lone_low.extend(utf16("ab\n", u16::to_le_bytes));
+        lone_low.extend(0xdc00u16.to_le_bytes());
+        lone_low.extend(utf16("c\n", u16::to_le_bytes));
+        assert!(load_error("lone-low", &lone_low).contains("not valid UTF-16"));
+
+        let mut trailing_high = [0xff, 0xfe].to_vec();
+        trailing_high.extend(utf16("ab\n", u16::to_le_bytes));
+        trailing_high.extend(0xd800u16.to_le_bytes());
+        assert!(load_error("trailing-high", &trailing_high).contains("partway through"));
+
+        let mut odd = [0xfe, 0xff].to_vec();
+        odd.extend(utf16("ab", u16::to_be_bytes));
+        odd.push(0);
+        assert!(load_error("odd", &odd).contains("partway through"));
+    }
+
+    #[test]
+    fn parses_line_ranges() {
+        let range = |first, last| Ok(LineRange { first, last });
+        assert_eq!(parse_lines("5"), range(5, 5));
+        assert_eq!(parse_lines("3-7"), range(3, 7));
+        assert_eq!(parse_lines(" 3 - 7 "), range(3, 7));
+        assert_eq!(parse_lines("10-"), range(10, u64::MAX));
+        for invalid in ["", "0", "0-4", "7-3", "-5", "a-b", "1-2-3"] {
+            assert!(parse_lines(invalid).is_err(), "{invalid:?}");
+        }
+        assert_eq!(parse_lines("10-").unwrap().to_string(), "10-");
+        assert_eq!(parse_lines("4-4").unwrap().to_string(), "4");
+    }
+
+    #[test]
+    fn keeps_lines_across_blank_runs() {
+        // Lines 2, 4, and 5 are blank; d is on line 7.
+        let bytes = b"a\n\nb\n\n\t\nc\nd";
+        let cases: [(&str, &[&str]); 7] = [
+            ("1-7", &["a", "b", "c", "d"]),
+            ("3-6", &["b", "c"]),
+            ("2", &[]),
+            ("4-5", &[]),
+            ("7-", &["d"]),
+            ("2-", &["b", "c", "d"]),
+            ("8-", &[]),
+        ];
+        for (storage, bytes) in [("mapped", bytes.to_vec()), ("arena", gzipped(bytes))] {
+            for (range, expected) in cases {
+                let mut words = load_bytes(&format!("lines-{storage}"), &bytes)
+                    .unwrap()
+                    .words;
+                words.keep_lines(parse_lines(range).unwrap());
+                assert_eq!(candidates(&words), expected, "{range} {storage}");
+            }
+            let mut words = load_bytes(&format!("numbers-{storage}"), &bytes)
+                .unwrap()
+                .words;
+            words.keep_lines(parse_lines("3-").unwrap());
+            let lines: Vec<u64> = (0..words.len())
+                .map(|index| words.line_number(index))
+                .collect();
+            assert_eq!(lines, [3, 6, 7], "{storage}");
+        }
+    }
+
+    #[test]
+    fn orders_by_counts_and_tracks_coverage() {
+        let bytes = b"3 alpha\n\n10 beta\ngamma ray\t 5\n10 delta";
+        for (storage, bytes) in [("mapped", bytes.to_vec()), ("arena", gzipped(bytes))] {
+            let mut words = load_bytes(&format!("counts-{storage}"), &bytes)
+                .unwrap()
+                .words;
+            words.sort_by_counts().unwrap();
+            assert_eq!(
+                candidates(&words),
+                ["beta", "delta", "gamma ray", "alpha"],
+                "{storage}"
+            );
+            let lines: Vec<u64> = (0..words.len())
+                .map(|index| words.line_number(index))
+                .collect();
+            assert_eq!(lines, [3, 5, 4, 1], "{storage}");
+            assert_eq!(words.coverage(0), Some(0.0));
+            assert_eq!(words.coverage(1), Some(10.0 / 28.0));
+            assert_eq!(words.coverage(4), Some(1.0));
+            assert_eq!(words.coverage(100), Some(1.0));
+
+            words.retain(|word| word != "delta");
+            assert_eq!(words.coverage(2), Some(15.0 / 18.0), "{storage}");
+        }
+        let words = load_bytes("uncounted", b"alpha\n").unwrap().words;
+        assert_eq!(words.coverage(1), None);
+    }
+
+    #[test]
+    fn rejects_malformed_counts() {
+        for line in [
+            "alpha",
+            "12",
+            "x alpha",
+            "-3 alpha",
+            "alpha\tmany",
+            "alpha\t-5",
+            "18446744073709551616 alpha",
+        ] {
+            let bytes = format!("1 first\n\n{line}\n2 last\n");
+            let mut words = load_bytes("malformed", bytes.as_bytes()).unwrap().words;
+            let error = words.sort_by_counts().unwrap_err().to_string();
+            assert_eq!(
+                error, "line 3 is not `count word` or `word<TAB>count`",
+                "{line:?}"
+            );
+        }
+    }
+}
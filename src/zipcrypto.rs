@@ -0,0 +1,343 @@
+use std::{
+    fmt,
+    str::FromStr,
+    sync::{
+        Mutex,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
+    thread,
+    time::Instant,
+};
+
+use anyhow::{Context, Error, Result, bail, ensure};
+
+use crate::archive::{self, Encryption, Entry};
+use crate::cpus;
+use crate::generate::{self, Charset};
+
+/// The encryption header that precedes every entry's data.
+pub const HEADER_LEN: usize = 12;
+
+const CRC_TABLE: [u32; 256] = crc_table();
+/// Indexed by the top byte of a table value, which is unique to it.
+const CRC_INVERSE: [u32; 256] = crc_inverse_table();
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut byte = 0;
+    while byte < 256 {
+        let mut crc = byte as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[byte] = crc;
+        byte += 1;
+    }
+    table
+}
+
+const fn crc_inverse_table() -> [u32; 256] {
+    let mut inverse = [0u32; 256];
+    let mut index = 0;
+    while index < 256 {
+        let value = CRC_TABLE[index];
+        inverse[(value >> 24) as usize] = (value << 8) ^ index as u32;
+        index += 1;
+    }
+    inverse
+}
+
+fn crc32_update(crc: u32, byte: u8) -> u32 {
+    (crc >> 8) ^ CRC_TABLE[usize::from((crc as u8) ^ byte)]
+}
+
+/// Undoes [`crc32_update`], given the byte that went in.
+fn crc32_inverse(crc: u32, byte: u8) -> u32 {
+    (crc << 8) ^ CRC_INVERSE[(crc >> 24) as usize] ^ u32::from(byte)
+}
+
+/// The three 32-bit words of ZipCrypto's cipher state. Every entry of an archive starts from
+/// the same keys, so they decrypt all of its ZipCrypto entries without the password.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Keys {
+    key0: u32,
+    key1: u32,
+    key2: u32,
+}
+
+impl Keys {
+    pub fn from_password(password: &[u8]) -> Self {
+        let mut keys = Self {
+            key0: 0x1234_5678,
+            key1: 0x2345_6789,
+            key2: 0x3456_7890,
+        };
+        for &byte in password {
+            keys.update(byte);
+        }
+        keys
+    }
+
+    fn update(&mut self, plain: u8) {
+        self.key0 = crc32_update(self.key0, plain);
+        self.key1 = self
+            .key1
+            .wrapping_add(self.key0 & 0xff)
+            .wrapping_mul(134_775_813)
+            .wrapping_add(1);
+        self.key2 = crc32_update(self.key2, (self.key1 >> 24) as u8);
+    }
+
+    fn keystream_byte(&self) -> u8 {
+        let temp = (self.key2 | 2) as u16;
+        (temp.wrapping_mul(temp ^ 1) >> 8) as u8
+    }
+
+    /// Decrypts just the last byte of an entry's encryption header, the one compared with the
+    /// entry's check byte.
+    pub fn header_check_byte(mut self, header: &[u8; HEADER_LEN]) -> u8 {
+        for &byte in &header[..HEADER_LEN - 1] {
+            let plain = byte ^ self.keystream_byte();
+            self.update(plain);
+        }
+        header[HEADER_LEN - 1] ^ self.keystream_byte()
+    }
+
+    /// Decrypts an entry's data in place, encryption header included.
+    pub fn decrypt(mut self, data: &mut [u8]) {
+        for byte in data {
+            *byte ^= self.keystream_byte();
+            self.update(*byte);
+        }
+    }
+}
+
+/// Hexadecimal, in the order and format bkcrack uses.
+impl fmt::Display for Keys {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:08x} {:08x} {:08x}", self.key0, self.key1, self.key2)
+    }
+}
+
+/// Three hexadecimal words separated by spaces, commas, or colons.
+impl FromStr for Keys {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self> {
+        let words: Vec<&str> = text
+            .split([' ', ',', ':'])
+            .filter(|word| !word.is_empty())
+            .collect();
+        ensure!(
+            words.len() == 3,
+            "Expected three hexadecimal keys, like \"c4490e28 b414a23d 91404b31\""
+        );
+        let mut keys = [0u32; 3];
+        for (key, word) in keys.iter_mut().zip(&words) {
+            let digits = word.trim_start_matches("0x");
+            if digits.len() > 8 {
+                bail!("Key \"{word}\" is longer than 32 bits");
+            }
+            *key = u32::from_str_radix(digits, 16)
+                .with_context(|| format!("Key \"{word}\" is not hexadecimal"))?;
+        }
+        Ok(Self {
+            key0: keys[0],
+            key1: keys[1],
+            key2: keys[2],
+        })
+    }
+}
+
+/// Turns the single-entry archive [`archive::extract_entry_archive`] built for a ZipCrypto entry
+/// into an unencrypted one. Wrong keys surface as a CRC error when the result is read.
+pub fn decrypt_entry_archive(entry_archive: &[u8], entry: &Entry, keys: Keys) -> Result<Vec<u8>> {
+    ensure!(
+        entry.encryption == Encryption::ZipCrypto,
+        "\"{}\" uses {}; internal keys only decrypt ZipCrypto entries",
+        entry.name,
+        entry.encryption
+    );
+    let mut data = archive::entry_data(entry_archive, entry)?.to_vec();
+    ensure!(
+        data.len() >= HEADER_LEN,
+        "Data for \"{}\" is shorter than the encryption header",
+        entry.name
+    );
+    keys.decrypt(&mut data);
+    archive::with_decrypted_data(entry_archive, entry, &data[HEADER_LEN..])
+}
+
+/// Bytes of the password solved from the keys rather than enumerated.
+const SOLVED: usize = 4;
+
+#[derive(clap::Args, Debug)]
+pub struct RecoverPasswordArgs {
+    /// The internal keys, e.g. from a known-plaintext attack, as three hexadecimal words.
+    #[arg(long, value_name = "KEYS")]
+    keys: Keys,
+
+    /// Password lengths in bytes, as `N` or `MIN-MAX`.
+    #[arg(short = 'l', long, value_name = "RANGE", default_value = "1-9", value_parser = generate::parse_length)]
+    length: (usize, usize),
+
+    /// ASCII characters the password may use (literals plus `?l ?u ?d ?s ?a` classes). Its
+    /// last four bytes are solved from the keys and may be anything.
+    #[arg(short = 'c', long, value_name = "SET", default_value = "?a", value_parser = generate::parse_charset)]
+    charset: Charset,
+
+    /// Number of worker threads.
+    #[arg(long, default_value_t = cpus::available())]
+    threads: usize,
+}
+
+/// Reconstructs a password that produces the given internal keys, trying the shortest
+/// lengths first.
+pub fn recover_password(args: &RecoverPasswordArgs) -> Result<()> {
+    if args.threads == 0 {
+        bail!("--threads must be at least 1");
+    }
+    let Ok(charset) = args
+        .charset
+        .chars
+        .iter()
+        .map(|&c| u8::try_from(c).ok().filter(u8::is_ascii).ok_or(c))
+        .collect::<Result<Vec<u8>, char>>()
+    else {
+        bail!("--charset must be ASCII; passwords are recovered byte by byte");
+    };
+    let (min, max) = args.length;
+    // The empty password costs nothing to check, whatever the lengths asked for.
+    let found = if Keys::from_password(b"") == args.keys {
+        Some(Vec::new())
+    } else {
+        (min..=max).find_map(|len| {
+            let started_at = Instant::now();
+            let found = search_length(args.keys, &charset, len, args.threads);
+            if found.is_none() {
+                println!("  length {len}: no match ({:.2?})", started_at.elapsed());
+            }
+            found
+        })
+    };
+    let Some(password) = found else {
+        bail!(
+            "No password of {min}-{max} bytes over {} produces these keys",
+            args.charset.spec
+        );
+    };
+    match String::from_utf8(password) {
+        Ok(password) => println!("Password found: {password}"),
+        Err(error) => println!("Password found (hex): {}", hex(error.as_bytes())),
+    }
+    Ok(())
+}
+
+/// Enumerates every prefix of `len - 4` bytes over `charset`, then solves the remaining bytes.
+fn search_length(target: Keys, charset: &[u8], len: usize, threads: usize) -> Option<Vec<u8>> {
+    let tail = Tail::new(target, len.min(SOLVED));
+    let prefix_len = len - tail.len;
+    if prefix_len == 0 {
+        return tail.solve(Keys::from_password(b""), target);
+    }
+
+    // Workers take the first byte of the prefix from a shared counter.
+    let next = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+    let found = Mutex::new(None);
+    thread::scope(|scope| {
+        for _ in 0..threads.min(charset.len()) {
+            scope.spawn(|| {
+                let mut prefix = Vec::with_capacity(len);
+                while !stop.load(Ordering::Relaxed) {
+                    let Some(&first) = charset.get(next.fetch_add(1, Ordering::Relaxed)) else {
+                        break;
+                    };
+                    prefix.clear();
+                    prefix.push(first);
+                    let keys = Keys::from_password(&prefix);
+                    if let Some(password) =
+                        extend(keys, &mut prefix, prefix_len, charset, &tail, target, &stop)
+                    {
+                        stop.store(true, Ordering::Relaxed);
+                        *found.lock().expect("no worker panics holding the lock") = Some(password);
+                    }
+                }
+            });
+        }
+    });
+    found
+        .into_inner()
+        .expect("no worker panics holding the lock")
+}
+
+fn extend(
+    keys: Keys,
+    prefix: &mut Vec<u8>,
+    prefix_len: usize,
+    charset: &[u8],
+    tail: &Tail,
+    target: Keys,
+    stop: &AtomicBool,
+) -> Option<Vec<u8>> {
+    if prefix.len() == prefix_len {
+        let solved = tail.solve(keys, target)?;
+        let mut password = prefix.clone();
+        password.extend_from_slice(&solved);
+        return Some(password);
+    }
+    if stop.load(Ordering::Relaxed) {
+        return None;
+    }
+    for &byte in charset {
+        let mut next = keys;
+        next.update(byte);
+        prefix.push(byte);
+        let found = extend(next, prefix, prefix_len, charset, tail, target, stop);
+        prefix.pop();
+        if found.is_some() {
+            return found;
+        }
+    }
+    None
+}
+
+/// The last bytes of a password, which follow from the keys before them and the target keys:
+/// `key0` is a CRC-32 of the password, and up to four bytes of CRC input can be read back from
+/// the states on either side. The other two keys then confirm the match.
+struct Tail {
+    len: usize,
+    /// The target `key0` with `len` zero bytes undone.
+    key0: u32,
+}
+
+impl Tail {
+    fn new(target: Keys, len: usize) -> Self {
+        let key0 = (0..len).fold(target.key0, |crc, _| crc32_inverse(crc, 0));
+        Self { len, key0 }
+    }
+
+    fn solve(&self, from: Keys, target: Keys) -> Option<Vec<u8>> {
+        // What the undone zero bytes differ from the real ones by, first byte lowest.
+        let bytes = from.key0 ^ self.key0;
+        if self.len < 4 && bytes >> (8 * self.len) != 0 {
+            return None;
+        }
+        let solved: Vec<u8> = (0..self.len).map(|i| (bytes >> (8 * i)) as u8).collect();
+        let mut keys = from;
+        for &byte in &solved {
+            keys.update(byte);
+        }
+        (keys == target).then_some(solved)
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}